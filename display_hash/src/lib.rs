@@ -1,30 +1,86 @@
 #![cfg_attr(test, feature(formatting_options))]
 
-use std::{
-  fmt::Write,
-  hash::{Hash as _, Hasher},
-};
+use std::{fmt::Write, hash::Hasher};
 
+// Feeds the formatted output to `hasher` as raw bytes, so that the hash only depends
+// on the resulting string and not on how `fmt` chose to split it up.
 pub struct HashWriter<'a, H: Hasher> {
   pub hasher: &'a mut H,
 }
 
 impl<'a, H: Hasher> Write for HashWriter<'a, H> {
   fn write_str(&mut self, s: &str) -> std::fmt::Result {
-    s.hash(self.hasher);
+    self.hasher.write(s.as_bytes());
     Ok(())
   }
 }
 
+// 64-bit FNV-1a. Unlike `std::hash::DefaultHasher`, its output is fixed, so names
+// derived from it are the same across Rust releases and platforms. Integers are
+// hashed as little endian bytes for the latter.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher(u64);
+
+impl StableHasher {
+  const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+  const PRIME: u64 = 0x100000001b3;
+
+  pub fn new() -> Self {
+    Self(Self::OFFSET_BASIS)
+  }
+}
+
+impl Default for StableHasher {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+macro_rules! write_le {
+  ($($method:ident: $t:ty),* $(,)?) => {
+    $(
+      fn $method(&mut self, i: $t) {
+        self.write(&i.to_le_bytes());
+      }
+    )*
+  };
+}
+
+impl Hasher for StableHasher {
+  fn write(&mut self, bytes: &[u8]) {
+    for byte in bytes {
+      self.0 ^= *byte as u64;
+      self.0 = self.0.wrapping_mul(Self::PRIME);
+    }
+  }
+
+  fn finish(&self) -> u64 {
+    self.0
+  }
+
+  write_le!(
+    write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128,
+    write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128,
+  );
+
+  fn write_usize(&mut self, i: usize) {
+    self.write_u64(i as u64);
+  }
+
+  fn write_isize(&mut self, i: isize) {
+    self.write_i64(i as i64);
+  }
+}
+
 #[cfg(test)]
 mod tests {
 
   use std::{
     fmt::{Display, Formatter, FormattingOptions},
-    hash::{DefaultHasher, Hash, Hasher as _},
+    hash::{Hash, Hasher},
   };
 
-  use super::HashWriter;
+  use super::{HashWriter, StableHasher};
 
   #[derive(Debug, Clone, Copy)]
   struct A1 {
@@ -73,19 +129,19 @@ mod tests {
     let c = A2 { x: 32, y: 20 };
 
     let hash_a = {
-      let mut hasher = DefaultHasher::new();
+      let mut hasher = StableHasher::new();
       a.hash(&mut hasher);
       hasher.finish()
     };
 
     let hash_b = {
-      let mut hasher = DefaultHasher::new();
+      let mut hasher = StableHasher::new();
       b.hash(&mut hasher);
       hasher.finish()
     };
 
     let hash_c = {
-      let mut hasher = DefaultHasher::new();
+      let mut hasher = StableHasher::new();
       c.hash(&mut hasher);
       hasher.finish()
     };
@@ -93,4 +149,33 @@ mod tests {
     assert_eq!(hash_a, hash_b);
     assert_ne!(hash_a, hash_c);
   }
+
+  #[test]
+  fn test_stable_hasher() {
+    let hash = |bytes: &[u8]| {
+      let mut hasher = StableHasher::new();
+      hasher.write(bytes);
+      hasher.finish()
+    };
+
+    // Reference values of 64-bit FNV-1a.
+    assert_eq!(hash(b""), 0xcbf29ce484222325);
+    assert_eq!(hash(b"a"), 0xaf63dc4c8601ec8c);
+    assert_eq!(hash(b"foobar"), 0x85944171f73967e8);
+
+    let mut hasher = StableHasher::new();
+    0x01020304u32.hash(&mut hasher);
+    assert_eq!(hasher.finish(), hash(&[4, 3, 2, 1]));
+  }
+
+  #[test]
+  fn test_hash_writer_ignores_chunks() {
+    let mut h1 = StableHasher::new();
+    A1 { x: 64, y: 36 }.hash(&mut h1);
+
+    let mut h2 = StableHasher::new();
+    h2.write(b"64 36");
+
+    assert_eq!(h1.finish(), h2.finish());
+  }
 }
@@ -2,9 +2,67 @@
 #define VECS_VECS_H
 
 #include <stdbool.h>
+#include <stddef.h>
 #include <stdint.h>
+#include <stdlib.h>
 
-typedef struct vecs_engine vecs_engine_t;
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Result of operations that may allocate.
+typedef enum vecs_status {
+  VECS_OK = 0,
+  // An allocation failed. The operation had no effect unless stated otherwise.
+  VECS_ERR_OOM,
+} vecs_status_t;
+
+// Memory allocation hooks. Every container in the engine goes through these.
+// `user` is passed along untouched. `alloc` and `realloc` should return NULL on
+// failure, and `realloc` may be given a NULL `ptr` with an `old_size` of 0. The
+// requested size is never 0.
+typedef struct vecs_allocator {
+  void *(*alloc)(void *user, size_t size);
+  void *(*realloc)(void *user, void *ptr, size_t old_size, size_t new_size);
+  void (*free)(void *user, void *ptr);
+  void *user;
+} vecs_allocator_t;
+
+inline static void *vecs_default_alloc(void *user, size_t size) {
+  (void)user;
+  return malloc(size);
+}
+
+inline static void *vecs_default_realloc(void *user, void *ptr, size_t old_size,
+                                         size_t new_size) {
+  (void)user;
+  (void)old_size;
+  return realloc(ptr, new_size);
+}
+
+inline static void vecs_default_free(void *user, void *ptr) {
+  (void)user;
+  free(ptr);
+}
+
+// Uses the standard library. Positional so that C++ can include this.
+static const vecs_allocator_t vecs_allocator_default = {
+    vecs_default_alloc, vecs_default_realloc, vecs_default_free, NULL};
+
+// Containers of empty structs would request 0 bytes, and `realloc` is allowed to
+// free the memory and return NULL in that case.
+inline static void *vecs_alloc(const vecs_allocator_t *a, size_t size) {
+  return a->alloc(a->user, size > 0 ? size : 1);
+}
+
+inline static void *vecs_realloc(const vecs_allocator_t *a, void *ptr,
+                                 size_t old_size, size_t new_size) {
+  return a->realloc(a->user, ptr, old_size, new_size > 0 ? new_size : 1);
+}
+
+inline static void vecs_free(const vecs_allocator_t *a, void *ptr) {
+  a->free(a->user, ptr);
+}
 
 // Index and gen in one struct. Used for permanent storage of entities and
 // component references.
@@ -19,13 +77,18 @@ typedef struct vecs_tmp_id {
   uint32_t index;
 } vecs_tmp_id_t;
 
-static const vecs_id_t vecs_id_invalid = {.index = UINT32_MAX,
-                                          .gen = UINT32_MAX};
+static const vecs_id_t vecs_id_invalid = {UINT32_MAX, UINT32_MAX};
 
 inline static bool vecs_id_is_invalid(vecs_id_t id) {
   return id.index == UINT32_MAX && id.gen == UINT32_MAX;
 }
 
+static const vecs_tmp_id_t vecs_tmp_id_invalid = {UINT32_MAX};
+
+inline static bool vecs_tmp_id_is_invalid(vecs_tmp_id_t id) {
+  return id.index == UINT32_MAX;
+}
+
 inline static vecs_id_t vecs_id_max() { return vecs_id_invalid; }
 
 inline static bool vecs_id_eq(vecs_id_t a, vecs_id_t b) {
@@ -38,4 +101,15 @@ inline static uint8_t vecs_id_cmp(vecs_id_t a, vecs_id_t b) {
   return a64 == b64 ? 0 : a64 < b64 ? -1 : 1;
 }
 
+// Payload of the built-in `frame` event.
+typedef struct vecs_frame {
+  float delta;
+  double runtime;
+  uint64_t frame;
+} vecs_frame_t;
+
+#ifdef __cplusplus
+}
+#endif
+
 #endif // !VECS_VECS_H
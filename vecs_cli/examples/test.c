@@ -45,69 +45,104 @@ void click(vecs_engine_t *engine, vecs_node_click_t node,
          event.x, event.button, l->mode);
 }
 
+#define TRY(expr)                                                              \
+  do {                                                                         \
+    if ((expr) != VECS_OK)                                                     \
+      goto oom;                                                                \
+  } while (0)
+
+#define TRY_ID(expr)                                                           \
+  do {                                                                         \
+    if (vecs_id_is_invalid(expr))                                              \
+      goto oom;                                                                \
+  } while (0)
+
 int main() {
+  for (size_t i = 0; i < vecs_component_infos_len; ++i) {
+    const vecs_component_info_t *info = &vecs_component_infos[i];
+    printf("component %s: %zu bytes, mask[%d] & (1 << %d)\n", info->name,
+           info->size, info->mask_i, info->mask_j);
+  }
+
   vecs_engine_t e;
-  vecs_init(&e);
+  if (vecs_init(&e, NULL) != VECS_OK) {
+    fprintf(stderr, "out of memory\n");
+    return 1;
+  }
 
-  vecs_id_t ent1 = vecs_add_entity(&e);
+  vecs_id_t ent1;
+  TRY(vecs_add_entity(&e, &ent1));
 
   printf("1 has transform %d; ", vecs_has_component_transform(&e, ent1));
   vecs_component_transform_t t1 = {.x = 1.5, .y = 3.0};
-  vecs_main_add_component_transform(&e, ent1, t1);
+  TRY_ID(vecs_main_add_component_transform(&e, ent1, t1));
   printf("2 has transform %d; ", vecs_has_component_transform(&e, ent1));
 
   vecs_main_disable_component_transform(&e, ent1);
   printf("3 has transform %d; ", vecs_has_component_transform(&e, ent1));
-  vecs_main_enable_component_transform(&e, ent1);
+  TRY(vecs_main_enable_component_transform(&e, ent1));
   printf("4 has transform %d; ", vecs_has_component_transform(&e, ent1));
 
   // vecs_component_render_t r1 = {.texture = 6};
-  vecs_main_add_component_render(&e, ent1);
+  TRY(vecs_main_add_component_render(&e, ent1));
+
+  vecs_component_velocity_t v1 = {.dx = 0.25, .dy = -1};
+  TRY_ID(vecs_main_add_component_velocity(&e, ent1, v1));
 
   vecs_component_layout_t l1 = {.mode = 4};
-  vecs_main_upsert_component_layout(&e, ent1, l1);
+  TRY_ID(vecs_main_upsert_component_layout(&e, ent1, l1));
 
   vecs_event_mouse_click_t mc1 = {.x = 0.5, .y = 0.328, .button = 2};
-  vecs_emit_mouse_click(&e, mc1);
+  TRY(vecs_emit_mouse_click(&e, mc1));
 
   vecs_event_frame_t f1 = {.delta = 0.16, .runtime = 0.16, .frame = 1};
-  vecs_emit_frame(&e, f1);
+  TRY(vecs_emit_frame(&e, f1));
 
   printf("  run\n");
-  vecs_run_state_main(&e);
+  TRY(vecs_run_state_main(&e));
 
   printf("  remove render\n");
-  vecs_emit_mouse_click(&e, mc1);
-  vecs_emit_frame(&e, f1);
+  TRY(vecs_emit_mouse_click(&e, mc1));
+  TRY(vecs_emit_frame(&e, f1));
   vecs_main_remove_component_render(&e, ent1);
-  vecs_run_state_main(&e);
+  TRY(vecs_run_state_main(&e));
 
   printf("  add render\n");
-  vecs_emit_mouse_click(&e, mc1);
-  vecs_emit_frame(&e, f1);
-  vecs_main_add_component_render(&e, ent1);
-  vecs_run_state_main(&e);
+  TRY(vecs_emit_mouse_click(&e, mc1));
+  TRY(vecs_emit_frame(&e, f1));
+  TRY(vecs_main_add_component_render(&e, ent1));
+  TRY(vecs_run_state_main(&e));
 
   printf("  update render\n");
-  vecs_emit_mouse_click(&e, mc1);
-  vecs_emit_frame(&e, f1);
+  TRY(vecs_emit_mouse_click(&e, mc1));
+  TRY(vecs_emit_frame(&e, f1));
   t1.x = 2.5;
   vecs_main_update_component_transform(&e, ent1, t1);
-  vecs_run_state_main(&e);
+  TRY(vecs_run_state_main(&e));
 
   printf("  main -> menu\n");
-  vecs_state_main_to_menu(&e);
+  TRY(vecs_state_main_to_menu(&e));
 
-  vecs_emit_mouse_click(&e, mc1);
-  vecs_emit_frame(&e, f1);
-  vecs_run_state_menu(&e);
+  TRY(vecs_emit_mouse_click(&e, mc1));
+  TRY(vecs_emit_frame(&e, f1));
+  TRY(vecs_run_state_menu(&e));
 
   printf("  menu -> main\n");
-  vecs_state_menu_to_main(&e);
+  TRY(vecs_state_menu_to_main(&e));
+
+  TRY(vecs_emit_mouse_click(&e, mc1));
+  TRY(vecs_emit_frame(&e, f1));
+  TRY(vecs_run_state_main(&e));
 
-  vecs_emit_mouse_click(&e, mc1);
-  vecs_emit_frame(&e, f1);
-  vecs_run_state_main(&e);
+  vecs_dump_json(&e, stdout);
+#ifdef VECS_PROFILE
+  vecs_profile_report(&e, stdout);
+#endif
+  vecs_destroy(&e);
+  return 0;
 
+oom:
+  fprintf(stderr, "out of memory\n");
   vecs_destroy(&e);
+  return 1;
 }
@@ -0,0 +1,140 @@
+use std::{
+  env,
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  error::Error,
+  generate::{RUNTIME_HEADER, generate_header, generate_impl},
+  output::Output,
+  spec::{Spec, set_prefix},
+};
+
+// Generates an engine from a build script and compiles it with `cc`, e.g.
+//
+//   vecs_cli::build::Build::new("src/engine.vecs")
+//     .file("src/systems.c")
+//     .compile("engine");
+//
+// writes `engine.h` and `engine.c` to `OUT_DIR`, along with the runtime's `vecs.h`,
+// and links them as `libengine.a`. C files added with `file` can include `engine.h`,
+// and the spec's directory is an include directory, for the headers it includes.
+// Cargo is told to rerun the script when the spec or the C files change.
+pub struct Build {
+  spec: PathBuf,
+  prefix: Option<String>,
+  profile: bool,
+  cc: cc::Build,
+}
+
+impl Build {
+  pub fn new(spec: impl AsRef<Path>) -> Self {
+    Self {
+      spec: spec.as_ref().to_path_buf(),
+      prefix: None,
+      profile: false,
+      cc: cc::Build::new(),
+    }
+  }
+
+  // Overrides `set prefix` in the spec.
+  pub fn prefix(&mut self, prefix: &str) -> &mut Self {
+    self.prefix = Some(prefix.to_string());
+    self
+  }
+
+  // See `--profile`.
+  pub fn profile(&mut self, profile: bool) -> &mut Self {
+    self.profile = profile;
+    self
+  }
+
+  // A C file to compile along with the engine, typically the systems.
+  pub fn file(&mut self, file: impl AsRef<Path>) -> &mut Self {
+    println!("cargo:rerun-if-changed={}", file.as_ref().display());
+    self.cc.file(file);
+    self
+  }
+
+  // The underlying `cc::Build`, for flags, defines and the like.
+  pub fn cc(&mut self) -> &mut cc::Build {
+    &mut self.cc
+  }
+
+  // Returns the directory containing the headers. `name` can't be `vecs`, since that's
+  // the name of the runtime header.
+  pub fn try_compile(&mut self, name: &str) -> Result<PathBuf, Error> {
+    if name == "vecs" {
+      return Err(Error::InvalidName(name.to_string()));
+    }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is not set"));
+    let spec_path = self.spec.to_string_lossy();
+    println!("cargo:rerun-if-changed={}", spec_path);
+
+    let spec = Spec::read(&spec_path)?;
+    let mut cst = spec.resolve()?;
+
+    if let Some(prefix) = &self.prefix {
+      set_prefix(&mut cst, prefix)?;
+    }
+
+    if self.profile {
+      cst.settings.profile = true;
+    }
+
+    let h_name = format!("{}.h", name);
+    let c_name = format!("{}.c", name);
+
+    let mut runtime_out = Output::new(&out_dir, "vecs.h");
+    let mut h_out = Output::new(&out_dir, &h_name);
+    let mut c_out = Output::new(&out_dir, &c_name);
+
+    let write_error = |name: &str, error| Error::Io {
+      path: out_dir.join(name),
+      error,
+    };
+
+    runtime_out
+      .write_all(RUNTIME_HEADER.as_bytes())
+      .map_err(|e| write_error("vecs.h", e))?;
+    generate_header(&cst, &mut h_out).map_err(|e| write_error(&h_name, e))?;
+    generate_impl(&cst, &h_name, &mut c_out).map_err(|e| write_error(&c_name, e))?;
+
+    for output in [&runtime_out, &h_out, &c_out] {
+      output.finish().map_err(|e| write_error(&output.name, e))?;
+    }
+
+    let spec_dir = match self.spec.parent() {
+      Some(dir) if !dir.as_os_str().is_empty() => dir,
+      _ => Path::new("."),
+    };
+
+    self
+      .cc
+      .include(spec_dir)
+      .include(&out_dir)
+      .file(out_dir.join(&c_name))
+      .compile(name);
+
+    Ok(out_dir)
+  }
+
+  // Like `try_compile`, but panics on error, which is how build scripts fail.
+  pub fn compile(&mut self, name: &str) -> PathBuf {
+    self.try_compile(name).unwrap_or_else(|e| panic!("{}", e))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Build;
+  use crate::error::Error;
+
+  #[test]
+  fn test_invalid_name() {
+    let result = Build::new("engine.vecs").try_compile("vecs");
+    assert!(matches!(result, Err(Error::InvalidName(name)) if name == "vecs"));
+  }
+}
@@ -1,11 +1,16 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 /// ECS component and main loop generator
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Cli {
+  #[command(subcommand)]
+  pub command: Option<Command>,
+
   /// Path to Vecs source file
-  pub source: String,
+  #[arg(required = true)]
+  pub source: Option<String>,
 
   /// Path to output directory
   #[arg(short = 'o', long = "out-dir", default_value = ".")]
@@ -18,4 +23,119 @@ pub struct Cli {
   /// Path of generated C header file
   #[arg(short = 'H', long = "h-out", default_value = "vecs.h")]
   pub h_output: String,
+
+  /// Path of generated C++ header file, used with `--target cpp`
+  #[arg(long = "hpp-out", default_value = "vecs.hpp")]
+  pub hpp_output: String,
+
+  /// Path of generated Rust module, used with `--target rust`
+  #[arg(short = 'R', long = "rs-out", default_value = "vecs.rs")]
+  pub rs_output: String,
+
+  /// Language of the generated code
+  #[arg(long = "target", value_enum, default_value = "c")]
+  pub target: Target,
+
+  /// Instead of generating code, write the resolved spec to stdout in another format
+  #[arg(long = "emit", value_enum)]
+  pub emit: Option<Emit>,
+
+  /// Wrap systems and deferred operations in timing counters, see `vecs_profile_report`
+  #[arg(long = "profile")]
+  pub profile: bool,
+
+  /// Write only the header, with the runtime and, behind `VECS_IMPLEMENTATION`, the
+  /// implementation inlined
+  #[arg(long = "single-header")]
+  pub single_header: bool,
+
+  /// Write a Makefile depfile listing the sources of the generated files, the spec
+  /// and the headers it includes from its directory, like `cc -MMD -MF`. Unlike the
+  /// outputs, it is relative to the working directory
+  #[arg(long = "depfile")]
+  pub depfile: Option<String>,
+
+  /// Keep running and regenerate the outputs whenever the source changes. Errors are
+  /// reported without touching the outputs. Linux only
+  #[arg(long = "watch")]
+  pub watch: bool,
+
+  /// Prefix of every generated symbol, overrides `set prefix`. Defaults to `vecs`
+  #[arg(long = "prefix")]
+  pub prefix: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+  /// A header and an implementation file
+  C,
+  /// The C files, plus a header with C++ wrappers
+  Cpp,
+  /// A single module, meant to be included with `mod`
+  Rust,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Emit {
+  /// The components, events, nodes, systems, states, globals and includes as JSON
+  CstJson,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+  /// Write a C file with an empty definition for every system
+  Stubs(StubsArgs),
+  /// Write the components, nodes, systems and states as a Graphviz (DOT) graph
+  Graph(GraphArgs),
+  /// Run a language server on stdin and stdout
+  Lsp,
+  /// Format Vecs source files in place
+  Fmt(FmtArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct StubsArgs {
+  /// Path to Vecs source file
+  pub source: String,
+
+  /// Path of the generated C file
+  #[arg(short = 'o', long = "out", default_value = "-")]
+  pub output: String,
+
+  /// Name of the generated C header to include
+  #[arg(short = 'H', long = "h-out", default_value = "vecs.h")]
+  pub h_output: String,
+
+  /// Append the systems that are not defined yet to the existing output
+  #[arg(long = "update")]
+  pub update: bool,
+
+  /// Prefix of every generated symbol, overrides `set prefix`. Defaults to `vecs`
+  #[arg(long = "prefix")]
+  pub prefix: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct GraphArgs {
+  /// Path to Vecs source file
+  pub source: String,
+
+  /// Path of the generated DOT file
+  #[arg(short = 'o', long = "out", default_value = "-")]
+  pub output: String,
+}
+
+#[derive(Args, Debug)]
+pub struct FmtArgs {
+  /// Paths to Vecs source files. `-` formats stdin to stdout
+  #[arg(required = true)]
+  pub sources: Vec<String>,
+
+  /// Don't write anything, but fail if some file isn't formatted
+  #[arg(long = "check")]
+  pub check: bool,
+
+  /// Width past which lists are written one entry per line
+  #[arg(long = "width", default_value_t = vecs_cli::format::DEFAULT_WIDTH)]
+  pub width: usize,
 }
@@ -0,0 +1,160 @@
+use std::{io, path::PathBuf};
+
+use crate::{
+  parse::data::{result::ParseError, str::Span},
+  resolve::result::ResolveError,
+};
+
+// Where an error happened in a spec. Lines are 1-based and columns 0-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorLocation {
+  pub file: Option<String>,
+  pub line: usize,
+  pub column: usize,
+}
+
+// Where the span starts.
+impl<'src> From<Span<'src>> for ErrorLocation {
+  fn from(span: Span<'src>) -> Self {
+    ErrorLocation {
+      file: span.file.map(str::to_string),
+      line: span.start_line,
+      column: span.start_column,
+    }
+  }
+}
+
+impl std::fmt::Display for ErrorLocation {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if let Some(file) = &self.file {
+      write!(f, "{}:", file)?;
+    }
+
+    write!(f, "{}:{}", self.line, self.column)
+  }
+}
+
+// A macro call a resolution error happened in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroExpansion {
+  pub name: String,
+  pub call: ErrorLocation,
+  pub definition: ErrorLocation,
+}
+
+// Everything that can go wrong between reading a spec and writing the generated code.
+// Unlike `ParseError` and `ResolveError`, it doesn't borrow the source.
+#[derive(Debug)]
+pub enum Error {
+  // Reading a spec or writing an output failed.
+  Io {
+    path: PathBuf,
+    error: io::Error,
+  },
+  // The spec is not syntactically valid.
+  Parse {
+    location: ErrorLocation,
+    message: String,
+  },
+  // The spec is well formed but doesn't make sense, e.g. a node requiring a component
+  // that doesn't exist. If it happened in a macro, `location` is in its definition,
+  // and `expansions` are the calls that led there, innermost first.
+  Resolve {
+    location: ErrorLocation,
+    message: String,
+    expansions: Vec<MacroExpansion>,
+  },
+  // A prefix given outside of the spec can't start C identifiers.
+  InvalidPrefix(String),
+  // The generated files can't be named like this, e.g. `vecs`, which is taken by the
+  // runtime header.
+  InvalidName(String),
+}
+
+impl std::fmt::Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Error::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+      Error::Parse { location, message } => {
+        write!(f, "{}: parsing error: {}", location, message)
+      }
+      Error::Resolve {
+        location,
+        message,
+        expansions,
+      } => {
+        write!(f, "{}: resolving error: {}", location, message)?;
+
+        // A macro calling itself would repeat the same call over and over.
+        for calls in expansions.chunk_by(|a, b| a == b) {
+          let expansion = &calls[0];
+          write!(
+            f,
+            "\n{}: note: in expansion of macro `{}`, defined at {}",
+            expansion.call, expansion.name, expansion.definition
+          )?;
+
+          if calls.len() > 1 {
+            write!(f, " ({} times)", calls.len())?;
+          }
+        }
+
+        Ok(())
+      }
+      Error::InvalidPrefix(prefix) => write!(
+        f,
+        "prefix `{}` should be made of letters, digits and underscores, starting with a letter",
+        prefix
+      ),
+      Error::InvalidName(name) => {
+        write!(
+          f,
+          "the engine can't be called `{}`, that's the runtime's name",
+          name
+        )
+      }
+    }
+  }
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::Io { error, .. } => Some(error),
+      _ => None,
+    }
+  }
+}
+
+impl<'src> From<ParseError<'src>> for Error {
+  fn from(e: ParseError<'src>) -> Self {
+    let location = e.location();
+
+    Error::Parse {
+      location: ErrorLocation {
+        file: location.file.map(str::to_string),
+        line: location.line,
+        column: location.column,
+      },
+      message: e.message().to_string(),
+    }
+  }
+}
+
+impl<'src> From<ResolveError<'src>> for Error {
+  fn from(e: ResolveError<'src>) -> Self {
+    Error::Resolve {
+      location: ErrorLocation::from(e.span()),
+      message: e.message().to_string(),
+      expansions: e
+        .expansions()
+        .iter()
+        .map(|expansion| MacroExpansion {
+          name: expansion.name.to_string(),
+          call: ErrorLocation::from(expansion.call),
+          definition: ErrorLocation::from(expansion.definition),
+        })
+        .collect(),
+    }
+  }
+}
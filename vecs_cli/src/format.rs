@@ -0,0 +1,287 @@
+use crate::{
+  error::Error,
+  parse::{
+    ast::{Comment, Expression, ExpressionKind, ListEntry},
+    comments::attach_comments,
+    data::src::ParseSrc,
+    parse, strip_comments,
+    tokens::tokenize,
+  },
+};
+
+// The width `vecs_cli fmt` formats to by default.
+pub const DEFAULT_WIDTH: usize = 80;
+
+// Pretty-prints a spec. Declarations end with `;` and are indented by two spaces per
+// level. Lists stay on one line, with their entries separated by `;`, unless they
+// don't fit in `width` or contain comments, then every entry goes on its own line.
+// Comments and single empty lines between declarations are kept, integers and
+// strings are written as they were.
+pub fn format(file: Option<&str>, text: &str, width: usize) -> Result<String, Error> {
+  let tokens = tokenize(ParseSrc::new(file, text));
+  let stripped = strip_comments(text, &tokens)?;
+
+  let mut ast = parse(ParseSrc::new(file, &stripped))?.value;
+  attach_comments(&mut ast, text, &tokens);
+
+  let mut printer = Printer::new(text, width, 0);
+  let entries = ast
+    .expressions
+    .iter()
+    .map(|e| Entry {
+      embed: false,
+      expression: e,
+    })
+    .collect::<Vec<_>>();
+  printer.entries(&entries, &ast.comments);
+
+  Ok(printer.out)
+}
+
+// A declaration or an entry of a list.
+struct Entry<'a, 'src> {
+  embed: bool,
+  expression: &'a Expression<'src>,
+}
+
+impl<'a, 'src> Entry<'a, 'src> {
+  fn list(entries: &'a [ListEntry<'src>]) -> Vec<Self> {
+    entries
+      .iter()
+      .map(|entry| match entry {
+        ListEntry::Expr(expression) => Entry {
+          embed: false,
+          expression,
+        },
+        ListEntry::Embed(expression) => Entry {
+          embed: true,
+          expression,
+        },
+      })
+      .collect()
+  }
+}
+
+struct Printer<'a> {
+  text: &'a str,
+  width: usize,
+  out: String,
+  indent: usize,
+}
+
+impl<'a> Printer<'a> {
+  fn new(text: &'a str, width: usize, indent: usize) -> Self {
+    Self {
+      text,
+      width,
+      out: String::new(),
+      indent,
+    }
+  }
+
+  fn column(&self) -> usize {
+    let line_start = self.out.rfind('\n').map_or(0, |i| i + 1);
+    self.out[line_start..].chars().count()
+  }
+
+  fn push_indent(&mut self) {
+    self.out.push_str(&" ".repeat(self.indent));
+  }
+
+  // Comments on lines of their own. Empty lines before the first one are dropped.
+  fn comment_lines(&mut self, comments: &[Comment], mut first: bool) {
+    for comment in comments.iter() {
+      if comment.blank_line_before && !first {
+        self.out.push('\n');
+      }
+
+      self.push_indent();
+      self.out.push_str(comment.text);
+      self.out.push('\n');
+      first = false;
+    }
+  }
+
+  // Each entry on its own line, followed by `;`.
+  fn entries(&mut self, entries: &[Entry], dangling: &[Comment]) {
+    for (i, entry) in entries.iter().enumerate() {
+      let comments = &entry.expression.comments;
+
+      self.comment_lines(&comments.leading, i == 0);
+      if comments.blank_line_before && (i > 0 || !comments.leading.is_empty()) {
+        self.out.push('\n');
+      }
+
+      self.push_indent();
+      self.entry(entry);
+      self.out.push(';');
+
+      for comment in comments.trailing.iter() {
+        self.out.push(' ');
+        self.out.push_str(comment.text);
+      }
+
+      self.out.push('\n');
+    }
+
+    self.comment_lines(dangling, entries.is_empty());
+  }
+
+  fn entry(&mut self, entry: &Entry) {
+    if entry.embed {
+      self.out.push_str("...");
+    }
+
+    self.expression(entry.expression);
+  }
+
+  // Comments in the middle of a declaration.
+  fn inline_comments(&mut self, comments: &[Comment]) {
+    for comment in comments.iter() {
+      self.out.push_str(comment.text);
+
+      if comment.is_line() {
+        self.out.push('\n');
+        self.push_indent();
+      } else {
+        self.out.push(' ');
+      }
+    }
+  }
+
+  fn expression(&mut self, expression: &Expression) {
+    match &expression.kind {
+      ExpressionKind::Application(children) => {
+        for (i, child) in children.iter().enumerate() {
+          // `name($a, $b)`, like a function.
+          if i > 0 && !matches!(child.kind, ExpressionKind::Tuple(_)) {
+            self.out.push(' ');
+          }
+
+          self.inline_comments(&child.comments.leading);
+          self.expression(child);
+        }
+      }
+      ExpressionKind::Tuple(items) => {
+        self.out.push('(');
+
+        for (i, item) in items.iter().enumerate() {
+          if i > 0 {
+            self.out.push_str(", ");
+          }
+
+          self.inline_comments(&item.comments.leading);
+          self.expression(item);
+        }
+
+        self.out.push(')');
+      }
+      ExpressionKind::List(entries) => {
+        let entries = Entry::list(entries);
+        let dangling = &expression.comments.dangling;
+
+        if entries.is_empty() && dangling.is_empty() {
+          self.out.push_str("{}");
+          return;
+        }
+
+        // Lists are followed by at least a `;`.
+        if let Some(inline) = self.inline(&entries, dangling)
+          && self.column() + inline.chars().count() < self.width
+        {
+          self.out.push_str(&inline);
+          return;
+        }
+
+        self.out.push_str("{\n");
+        self.indent += 2;
+        self.entries(&entries, dangling);
+        self.indent -= 2;
+        self.push_indent();
+        self.out.push('}');
+      }
+      _ => {
+        let span = expression.span;
+        self.out.push_str(span.slice(self.text));
+      }
+    }
+  }
+
+  // The list on one line, if it has no comments.
+  fn inline(&self, entries: &[Entry], dangling: &[Comment]) -> Option<String> {
+    if !dangling.is_empty() {
+      return None;
+    }
+
+    let mut inline = Vec::new();
+    for entry in entries {
+      if !entry.expression.comments.is_empty() {
+        return None;
+      }
+
+      let mut printer = Printer::new(self.text, usize::MAX, 0);
+      printer.entry(entry);
+
+      if printer.out.contains('\n') {
+        return None;
+      }
+      inline.push(printer.out);
+    }
+
+    Some(format!("{{ {} }}", inline.join("; ")))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::format;
+
+  #[test]
+  fn test_format() {
+    let text = concat!(
+      "// The engine.\n",
+      "\n",
+      "include   \"proto//gen.h\";\n",
+      "component transform {double x,double y,}; // Position.\n",
+      "component render;\n",
+      "\n",
+      "\n",
+      "system move on tick { transform, };\n",
+      "macro body ( $name,$mass ) { component $name { double $mass } };\n",
+      "body(ball, weight);\n",
+      "state main { systems { { move; render_loop; physics_step }; { render } }; };\n",
+      "node n {\n",
+      "  transform; /* Inline. */\n",
+      "  // Dangling.\n",
+      "};\n",
+      "// The end.\n",
+    );
+
+    let expected = concat!(
+      "// The engine.\n",
+      "\n",
+      "include \"proto//gen.h\";\n",
+      "component transform { double x; double y }; // Position.\n",
+      "component render;\n",
+      "\n",
+      "system move on tick { transform };\n",
+      "macro body($name, $mass) { component $name { double $mass } };\n",
+      "body(ball, weight);\n",
+      "state main {\n",
+      "  systems { { move; render_loop; physics_step }; { render } };\n",
+      "};\n",
+      "node n {\n",
+      "  transform; /* Inline. */\n",
+      "  // Dangling.\n",
+      "};\n",
+      "// The end.\n",
+    );
+
+    let formatted = format(None, text, 70).expect("format error");
+    assert_eq!(formatted, expected);
+    assert_eq!(
+      format(None, &formatted, 70).expect("format error"),
+      expected
+    );
+  }
+}
@@ -2,65 +2,132 @@ use std::fmt::Display;
 
 use derive_display_hash::DisplayHash;
 
-use crate::generate::generics::common::GenericElement;
+use crate::{generate::generics::common::GenericElement, resolve::cst::Prefix};
 
 // A struct named $name that has one `name` field and implements Display with the
-// provided format string, where the name is the first positional argument. Construct
-// it with `$name::new(name)`.
+// provided format string, where the lower case prefix is the first positional
+// argument and the name is the second. Construct it with `$name::new(prefix, name)`.
 macro_rules! format_struct {
   ($name:ident, $format:expr) => {
     #[derive(Debug, Clone, DisplayHash)]
-    pub struct $name<T: Display> {
+    pub struct $name<'p, T: Display> {
+      pub prefix: &'p Prefix,
       pub name: T,
     }
 
-    impl<T: Display> $name<T> {
-      pub fn new(name: T) -> Self {
-        Self { name: name }
+    impl<'p, T: Display> $name<'p, T> {
+      pub fn new(prefix: &'p Prefix, name: T) -> Self {
+        Self { prefix, name }
       }
     }
 
-    impl<T: Display> Display for $name<T> {
+    impl<'p, T: Display> Display for $name<'p, T> {
       fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, $format, self.name)
+        write!(f, $format, self.prefix.lower, self.name)
       }
     }
   };
 }
 
+// Engine-wide structs, such as `vecs_entity_t`:
+format_struct!(EngineStructName, "{}_{}_t");
+
 // Event:
-format_struct!(EventStructName, "vecs_event_{}_t");
+format_struct!(EventStructName, "{}_event_{}_t");
 
 // Component:
-format_struct!(ComponentStructName, "vecs_component_{}_t");
+format_struct!(ComponentStructName, "{}_component_{}_t");
 
 // Component deferred operations:
-format_struct!(ComponentOpAddStructName, "vecs_op_add_component_{}_t");
-format_struct!(
-  ComponentOpAddTmpStructName,
-  "vecs_op_tmp_add_component_{}_t"
-);
-format_struct!(ComponentOpUpdateStructName, "vecs_op_update_component_{}_t");
+format_struct!(ComponentOpAddStructName, "{}_op_add_component_{}_t");
+format_struct!(ComponentOpAddTmpStructName, "{}_op_tmp_add_component_{}_t");
+format_struct!(ComponentOpUpdateStructName, "{}_op_update_component_{}_t");
 
 // Node:
-format_struct!(NodeStructName, "vecs_node_{}_t");
+format_struct!(NodeStructName, "{}_node_{}_t");
+
+// The doc comment of a declaration as C `//` comments, one per line, indented by
+// `indent` spaces. Writes nothing if there's no doc comment.
+pub struct DocComment<'a> {
+  pub lines: &'a [&'a str],
+  pub indent: usize,
+}
+
+impl<'a> Display for DocComment<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for line in self.lines {
+      // A trailing backslash would splice the next line of the header into the comment.
+      let line = line.trim_end_matches(|c: char| c == '\\' || c.is_whitespace());
+      write!(f, "{:1$}//", "", self.indent)?;
+
+      if line.is_empty() {
+        writeln!(f)?;
+      } else {
+        writeln!(f, " {}", line)?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+// Formats a snake case name from the spec in upper camel case, followed by `suffix`.
+// E.g. `mouse_click` with suffix `Event` becomes `MouseClickEvent`.
+pub struct UpperCamel<'a> {
+  pub name: &'a str,
+  pub suffix: &'a str,
+}
+
+impl<'a> UpperCamel<'a> {
+  pub fn new(name: &'a str, suffix: &'a str) -> Self {
+    Self { name, suffix }
+  }
+}
+
+impl<'a> Display for UpperCamel<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for word in self.name.split('_').filter(|w| !w.is_empty()) {
+      let mut chars = word.chars();
+
+      if let Some(first) = chars.next() {
+        write!(f, "{}{}", first.to_ascii_uppercase(), chars.as_str())?;
+      }
+    }
+
+    write!(f, "{}", self.suffix)
+  }
+}
 
 /// Helper to generate an instance of all of the component-specific operations at once:
 /// add, add_tmp & update.
 // TODO: Is this really helpful?
 #[derive(Clone)]
-pub struct ComponentTmpOps<T: GenericElement> {
-  pub add_t: ComponentOpAddStructName<T>,
-  pub add_tmp_t: ComponentOpAddTmpStructName<T>,
-  pub update_t: ComponentOpUpdateStructName<T>,
+pub struct ComponentTmpOps<'p, T: GenericElement> {
+  pub add_t: ComponentOpAddStructName<'p, T>,
+  pub add_tmp_t: ComponentOpAddTmpStructName<'p, T>,
+  pub update_t: ComponentOpUpdateStructName<'p, T>,
 }
 
-impl<T: GenericElement> ComponentTmpOps<T> {
-  pub fn new(name: T) -> Self {
+impl<'p, T: GenericElement> ComponentTmpOps<'p, T> {
+  pub fn new(prefix: &'p Prefix, name: T) -> Self {
     Self {
-      add_t: ComponentOpAddStructName::new(name.clone()),
-      add_tmp_t: ComponentOpAddTmpStructName::new(name.clone()),
-      update_t: ComponentOpUpdateStructName::new(name.clone()),
+      add_t: ComponentOpAddStructName::new(prefix, name.clone()),
+      add_tmp_t: ComponentOpAddTmpStructName::new(prefix, name.clone()),
+      update_t: ComponentOpUpdateStructName::new(prefix, name.clone()),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::UpperCamel;
+
+  #[test]
+  fn test_upper_camel() {
+    assert_eq!(
+      format!("{}", UpperCamel::new("mouse_click", "Event")),
+      "MouseClickEvent"
+    );
+    assert_eq!(format!("{}", UpperCamel::new("_a__b2_", "")), "AB2");
+  }
+}
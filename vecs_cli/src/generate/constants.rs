@@ -2,39 +2,51 @@ use std::fmt::Display;
 
 use derive_display_hash::DisplayHash;
 
-use crate::resolve::cst::{Component, Node};
+use crate::resolve::cst::{Component, Node, Prefix};
 
 #[derive(Debug, Clone, DisplayHash)]
 pub struct ComponentMaskName<'a> {
+  pub prefix: &'a Prefix,
   pub name: &'a str,
 }
 
 impl<'a> ComponentMaskName<'a> {
-  pub fn new(name: &'a str) -> Self {
-    Self { name }
+  pub fn new(prefix: &'a Prefix, name: &'a str) -> Self {
+    Self { prefix, name }
   }
 }
 
 impl<'a> Display for ComponentMaskName<'a> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "VECS_COMPONENT_{}_MASK", self.name.to_ascii_uppercase())
+    write!(
+      f,
+      "{}_COMPONENT_{}_MASK",
+      self.prefix.upper,
+      self.name.to_ascii_uppercase()
+    )
   }
 }
 
 #[derive(Debug, Clone, DisplayHash)]
 pub struct NodeMaskName<'a> {
+  pub prefix: &'a Prefix,
   pub name: &'a str,
 }
 
 impl<'a> NodeMaskName<'a> {
-  pub fn new(name: &'a str) -> Self {
-    Self { name }
+  pub fn new(prefix: &'a Prefix, name: &'a str) -> Self {
+    Self { prefix, name }
   }
 }
 
 impl<'a> Display for NodeMaskName<'a> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "VECS_NODE_{}_MASK", self.name.to_ascii_uppercase())
+    write!(
+      f,
+      "{}_NODE_{}_MASK",
+      self.prefix.upper,
+      self.name.to_ascii_uppercase()
+    )
   }
 }
 
@@ -117,18 +129,24 @@ impl Display for NodeMask {
 
 #[derive(Debug, Clone, DisplayHash)]
 pub struct StateIdName<'a> {
+  pub prefix: &'a Prefix,
   pub name: &'a str,
 }
 
 impl<'a> StateIdName<'a> {
-  pub fn new(name: &'a str) -> Self {
-    Self { name }
+  pub fn new(prefix: &'a Prefix, name: &'a str) -> Self {
+    Self { prefix, name }
   }
 }
 
 impl<'a> Display for StateIdName<'a> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "VECS_STATE_{}", self.name.to_ascii_uppercase())
+    write!(
+      f,
+      "{}_STATE_{}",
+      self.prefix.upper,
+      self.name.to_ascii_uppercase()
+    )
   }
 }
 
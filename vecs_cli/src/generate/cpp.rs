@@ -0,0 +1,751 @@
+use std::fmt::Display;
+
+use crate::{
+  generate::{
+    common::{ComponentStructName, EventStructName, NodeStructName, UpperCamel},
+    constants::StateIdName,
+    generics::{
+      common::method_name, skip_lists::SkipList, sparse_dyn_arrays::SparseDynArray,
+    },
+  },
+  resolve::cst::{Component, Cst},
+};
+
+// C++ wrapper. A header on top of the C one, with an RAII `Engine` class and typed
+// handles. Components, events and nodes are tag structs whose static functions call
+// into the C API, so that `Engine` only needs one template per operation.
+
+// Classes of the wrapper itself, which a component tag must not shadow.
+const RESERVED: [&str; 7] = [
+  "ComponentId",
+  "Engine",
+  "Entity",
+  "NodeRange",
+  "State",
+  "TmpComponentId",
+  "TmpEntity",
+];
+
+// Name of the tag struct of a component, e.g. `Transform`.
+fn component_class(component: &Component) -> String {
+  let class = UpperCamel::new(component.name(), "").to_string();
+
+  if RESERVED.contains(&class.as_str()) {
+    class + "Component"
+  } else {
+    class
+  }
+}
+
+pub struct CppHeader<'a> {
+  pub data: &'a Cst<'a>,
+  pub header_name: &'a str,
+}
+
+impl<'a> CppHeader<'a> {
+  // Writes a switch on `state_expr` that returns `call` for each state, where `call`
+  // is given the state name.
+  fn write_state_switch(
+    &self,
+    f: &mut std::fmt::Formatter<'_>,
+    state_expr: &str,
+    call: impl Fn(&str) -> String,
+  ) -> std::fmt::Result {
+    writeln!(f, "    switch ({}) {{", state_expr)?;
+
+    for state in self.data.states.values() {
+      writeln!(
+        f,
+        "    case State::{}:\n      return {};",
+        UpperCamel::new(state.name, ""),
+        call(state.name),
+      )?;
+    }
+
+    writeln!(f, "    }}\n    std::abort();")
+  }
+
+  fn write_component(
+    &self,
+    f: &mut std::fmt::Formatter<'_>,
+    component: &Component,
+  ) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+    let name = component.name();
+    let component_t = ComponentStructName::new(prefix, name);
+    let has_states = !self.data.states.is_empty();
+
+    write!(
+      f,
+      concat!(
+        "// Component `{name}`.\n",
+        "struct {class} {{\n",
+        "  using type = {type};\n",
+      ),
+      name = name,
+      class = component_class(component),
+      type = if component.is_empty() {
+        "void".to_string()
+      } else {
+        component_t.to_string()
+      },
+    )?;
+
+    if !component.is_empty() {
+      let index_index = SkipList::new(prefix, "vecs_id_t", "uint32_t");
+      let index_index_t = index_index.get_type();
+      let component_array = SparseDynArray::new(prefix, component_t.clone());
+      let component_array_t = component_array.get_type();
+
+      write!(
+        f,
+        concat!(
+          "\n",
+          "  static type *of_entity({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+          "    uint32_t index;\n",
+          "    if (!{index_get}(&e->entity_to_component_{name}, entity, &index))\n",
+          "      return nullptr;\n",
+          "    return {array_get_unchecked}(&e->components_{name}, index);\n",
+          "  }}\n",
+        ),
+        name = name,
+        index_get = method_name!(&index_index_t, "get"),
+        array_get_unchecked = method_name!(&component_array_t, "get_unchecked"),
+        prefix = prefix.lower,
+      )?;
+
+      for node in self.data.nodes.values() {
+        if node.components.contains(name) {
+          write!(
+            f,
+            concat!(
+              "\n",
+              "  static type *of_node({prefix}_engine_t *e, {node_t} node) {{\n",
+              "    return {prefix}_node_{node_name}_get_{name}(e, node);\n",
+              "  }}\n",
+            ),
+            name = name,
+            node_name = node.name,
+            node_t = NodeStructName::new(prefix, node.name),
+            prefix = prefix.lower,
+          )?;
+        }
+      }
+    }
+
+    // There are no immediate operations without states.
+    if has_states {
+      write!(
+        f,
+        concat!(
+          "\n",
+          "  static bool has({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+          "    return {prefix}_has_component_{name}(e, entity);\n",
+          "  }}\n",
+        ),
+        name = name,
+        prefix = prefix.lower,
+      )?;
+
+      if !component.is_empty() {
+        write!(
+          f,
+          concat!(
+            "\n",
+            "  static type *of_id({prefix}_engine_t *e, vecs_id_t component) {{\n",
+            "    return {prefix}_get_component_{name}(e, component);\n",
+            "  }}\n",
+          ),
+          name = name,
+          prefix = prefix.lower,
+        )?;
+      }
+
+      if component.is_empty() {
+        write!(
+          f,
+          "\n  static vecs_status_t add({prefix}_engine_t *e, State s, vecs_id_t entity) {{\n",
+          prefix = prefix.lower,
+        )?;
+        self.write_state_switch(f, "s", |state| {
+          format!(
+            "{}_{}_add_component_{}(e, entity)",
+            prefix.lower, state, name
+          )
+        })?;
+        write!(
+          f,
+          concat!(
+            "  }}\n",
+            "\n",
+            "  static bool remove({prefix}_engine_t *e, State s, vecs_id_t entity) {{\n",
+            "    bool had = has(e, entity);\n",
+            "    switch (s) {{\n",
+          ),
+          prefix = prefix.lower,
+        )?;
+
+        for state in self.data.states.values() {
+          writeln!(
+            f,
+            concat!(
+              "    case State::{State}:\n",
+              "      {prefix}_{state}_remove_component_{name}(e, entity);\n",
+              "      break;",
+            ),
+            State = UpperCamel::new(state.name, ""),
+            state = state.name,
+            name = name,
+            prefix = prefix.lower,
+          )?;
+        }
+
+        writeln!(f, "    }}\n    return had;\n  }}")?;
+      } else {
+        for op in ["add", "upsert", "update"] {
+          write!(
+            f,
+            "\n  static vecs_id_t {op}({prefix}_engine_t *e, State s, vecs_id_t entity, const type &component) {{\n",
+            op = op,
+            prefix = prefix.lower,
+          )?;
+          self.write_state_switch(f, "s", |state| {
+            format!(
+              "{}_{}_{}_component_{}(e, entity, component)",
+              prefix.lower, state, op, name
+            )
+          })?;
+          writeln!(f, "  }}")?;
+        }
+
+        write!(
+          f,
+          "\n  static bool remove({prefix}_engine_t *e, State s, vecs_id_t entity) {{\n",
+          prefix = prefix.lower,
+        )?;
+        self.write_state_switch(f, "s", |state| {
+          format!(
+            "{}_{}_remove_component_{}(e, entity)",
+            prefix.lower, state, name
+          )
+        })?;
+        writeln!(f, "  }}")?;
+      }
+
+      write!(
+        f,
+        "\n  static vecs_status_t enable({prefix}_engine_t *e, State s, vecs_id_t entity) {{\n",
+        prefix = prefix.lower,
+      )?;
+      self.write_state_switch(f, "s", |state| {
+        format!(
+          "{}_{}_enable_component_{}(e, entity)",
+          prefix.lower, state, name
+        )
+      })?;
+      write!(
+        f,
+        concat!(
+          "  }}\n",
+          "\n",
+          "  static void disable({prefix}_engine_t *e, State s, vecs_id_t entity) {{\n",
+          "    switch (s) {{\n",
+        ),
+        prefix = prefix.lower,
+      )?;
+
+      for state in self.data.states.values() {
+        writeln!(
+          f,
+          concat!(
+            "    case State::{State}:\n",
+            "      {prefix}_{state}_disable_component_{name}(e, entity);\n",
+            "      break;",
+          ),
+          State = UpperCamel::new(state.name, ""),
+          state = state.name,
+          name = name,
+          prefix = prefix.lower,
+        )?;
+      }
+
+      writeln!(f, "    }}\n  }}")?;
+    }
+
+    // Deferred operations:
+    if component.is_empty() {
+      write!(
+        f,
+        concat!(
+          "\n",
+          "  static vecs_status_t schedule_add({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+          "    return {prefix}_schedule_add_component_{name}(e, entity);\n",
+          "  }}\n",
+          "\n",
+          "  static vecs_status_t schedule_add({prefix}_engine_t *e, vecs_tmp_id_t entity) {{\n",
+          "    return {prefix}_schedule_tmp_add_component_{name}(e, entity);\n",
+          "  }}\n",
+        ),
+        name = name,
+        prefix = prefix.lower,
+      )?;
+    } else {
+      write!(
+        f,
+        concat!(
+          "\n",
+          "  static vecs_tmp_id_t schedule_add({prefix}_engine_t *e, vecs_id_t entity, const type &component) {{\n",
+          "    return {prefix}_schedule_add_component_{name}(e, entity, component);\n",
+          "  }}\n",
+          "\n",
+          "  static vecs_tmp_id_t schedule_add({prefix}_engine_t *e, vecs_tmp_id_t entity, const type &component) {{\n",
+          "    return {prefix}_schedule_tmp_add_component_{name}(e, entity, component);\n",
+          "  }}\n",
+          "\n",
+          "  static vecs_status_t schedule_upsert({prefix}_engine_t *e, vecs_id_t entity, const type &component) {{\n",
+          "    return {prefix}_schedule_upsert_component_{name}(e, entity, component);\n",
+          "  }}\n",
+          "\n",
+          "  static vecs_status_t schedule_update({prefix}_engine_t *e, vecs_id_t entity, const type &component) {{\n",
+          "    return {prefix}_schedule_update_component_{name}(e, entity, component);\n",
+          "  }}\n",
+          "\n",
+          "  static vecs_status_t schedule_store_entity_in({prefix}_engine_t *e, vecs_tmp_id_t entity, vecs_id_t *location) {{\n",
+          "    return {prefix}_schedule_store_entity_in_{name}(e, entity, location);\n",
+          "  }}\n",
+          "\n",
+          "  static vecs_status_t schedule_store_component({prefix}_engine_t *e, vecs_tmp_id_t component, vecs_id_t *location) {{\n",
+          "    return {prefix}_schedule_store_component_{name}(e, component, location);\n",
+          "  }}\n",
+        ),
+        name = name,
+        prefix = prefix.lower,
+      )?;
+    }
+
+    write!(
+      f,
+      concat!(
+        "\n",
+        "  static vecs_status_t schedule_remove({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+        "    return {prefix}_schedule_remove_component_{name}(e, entity);\n",
+        "  }}\n",
+        "\n",
+        "  static vecs_status_t schedule_enable({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+        "    return {prefix}_schedule_enable_component_{name}(e, entity);\n",
+        "  }}\n",
+        "\n",
+        "  static vecs_status_t schedule_disable({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+        "    return {prefix}_schedule_disable_component_{name}(e, entity);\n",
+        "  }}\n",
+        "}};\n",
+        "\n",
+      ),
+      name = name,
+      prefix = prefix.lower,
+    )
+  }
+
+  fn write_engine(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    write!(
+      f,
+      concat!(
+        "// Owns a `{prefix}_engine_t`. Operations that run out of memory call\n",
+        "// `{PREFIX}_OUT_OF_MEMORY()`, and immediate operations use the state the engine is\n",
+        "// in. The engine can be neither copied nor moved, since its containers point into it.\n",
+        "class Engine {{\n",
+        "public:\n",
+        "  // `allocator` is copied, if NULL the standard library is used.\n",
+        "  explicit Engine(State state, const vecs_allocator_t *allocator = nullptr)\n",
+        "      : state_(state) {{\n",
+        "    check({prefix}_init(&e_, allocator));\n",
+        "  }}\n",
+        "\n",
+        "  ~Engine() {{ {prefix}_destroy(&e_); }}\n",
+        "\n",
+        "  Engine(const Engine &) = delete;\n",
+        "  Engine &operator=(const Engine &) = delete;\n",
+        "  Engine(Engine &&) = delete;\n",
+        "  Engine &operator=(Engine &&) = delete;\n",
+        "\n",
+        "  // The engine given to a system, if it was created through this class.\n",
+        "  static Engine &from({prefix}_engine_t *e) {{\n",
+        "    return *reinterpret_cast<Engine *>(e);\n",
+        "  }}\n",
+        "\n",
+        "  {prefix}_engine_t *raw() {{ return &e_; }}\n",
+        "  {prefix}_globals_t &globals() {{ return e_.globals; }}\n",
+        "  State state() const {{ return state_; }}\n",
+        "\n",
+        "  // Moves the nodes over to `state`.\n",
+        "  void set_state(State state) {{\n",
+        "    switch (state_) {{\n",
+      ),
+      prefix = prefix.lower,
+      PREFIX = prefix.upper,
+    )?;
+
+    for from in self.data.states.values() {
+      writeln!(
+        f,
+        "    case State::{}:\n      switch (state) {{",
+        UpperCamel::new(from.name, "")
+      )?;
+
+      for to in self.data.states.values() {
+        if from.name != to.name {
+          writeln!(
+            f,
+            concat!(
+              "      case State::{To}:\n",
+              "        check({prefix}_state_{from}_to_{to}(&e_));\n",
+              "        break;",
+            ),
+            To = UpperCamel::new(to.name, ""),
+            from = from.name,
+            to = to.name,
+            prefix = prefix.lower,
+          )?;
+        }
+      }
+
+      writeln!(f, "      default:\n        break;\n      }}\n      break;")?;
+    }
+
+    write!(
+      f,
+      concat!(
+        "    }}\n",
+        "    state_ = state;\n",
+        "  }}\n",
+        "\n",
+        "  // Runs the systems of the current state, then the deferred operations.\n",
+        "  void run() {{\n",
+        "    switch (state_) {{\n",
+      ),
+    )?;
+
+    for state in self.data.states.values() {
+      writeln!(
+        f,
+        concat!(
+          "    case State::{State}:\n",
+          "      check({prefix}_run_state_{state}(&e_));\n",
+          "      break;",
+        ),
+        State = UpperCamel::new(state.name, ""),
+        state = state.name,
+        prefix = prefix.lower,
+      )?;
+    }
+
+    write!(
+      f,
+      concat!(
+        "    }}\n",
+        "  }}\n",
+        "\n",
+        "  Entity add_entity() {{\n",
+        "    Entity entity;\n",
+        "    check({prefix}_add_entity(&e_, &entity.id));\n",
+        "    return entity;\n",
+        "  }}\n",
+        "\n",
+        "  TmpEntity schedule_add_entity() {{\n",
+        "    return TmpEntity{{{prefix}_schedule_add_entity(&e_)}};\n",
+        "  }}\n",
+        "\n",
+        "  // Components.\n",
+        "\n",
+        "  template <typename C> bool has(Entity entity) {{\n",
+        "    return C::has(&e_, entity.id);\n",
+        "  }}\n",
+        "\n",
+        "  // NULL if `entity` has no `C`. Disabled components are still found.\n",
+        "  template <typename C> typename C::type *get(Entity entity) {{\n",
+        "    return C::of_entity(&e_, entity.id);\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C> typename C::type *get(ComponentId<C> component) {{\n",
+        "    return C::of_id(&e_, component.id);\n",
+        "  }}\n",
+        "\n",
+        "  // `node` is a node struct with a `C`.\n",
+        "  template <typename C, typename N> typename C::type *get(const N &node) {{\n",
+        "    return C::of_node(&e_, node);\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C>\n",
+        "  ComponentId<C> add(Entity entity, const typename C::type &component) {{\n",
+        "    return check_id<C>(C::add(&e_, state_, entity.id, component));\n",
+        "  }}\n",
+        "\n",
+        "  // Markers.\n",
+        "  template <typename C> void add(Entity entity) {{\n",
+        "    check(C::add(&e_, state_, entity.id));\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C>\n",
+        "  ComponentId<C> upsert(Entity entity, const typename C::type &component) {{\n",
+        "    return check_id<C>(C::upsert(&e_, state_, entity.id, component));\n",
+        "  }}\n",
+        "\n",
+        "  // `entity` must have a `C`.\n",
+        "  template <typename C>\n",
+        "  ComponentId<C> update(Entity entity, const typename C::type &component) {{\n",
+        "    return ComponentId<C>{{C::update(&e_, state_, entity.id, component)}};\n",
+        "  }}\n",
+        "\n",
+        "  // Whether there was a `C` to remove.\n",
+        "  template <typename C> bool remove(Entity entity) {{\n",
+        "    return C::remove(&e_, state_, entity.id);\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C> void enable(Entity entity) {{\n",
+        "    check(C::enable(&e_, state_, entity.id));\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C> void disable(Entity entity) {{\n",
+        "    C::disable(&e_, state_, entity.id);\n",
+        "  }}\n",
+        "\n",
+        "  // Deferred operations, applied at the end of `run`.\n",
+        "\n",
+        "  template <typename C>\n",
+        "  TmpComponentId<C> schedule_add(Entity entity, const typename C::type &component) {{\n",
+        "    return check_tmp_id<C>(C::schedule_add(&e_, entity.id, component));\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C>\n",
+        "  TmpComponentId<C> schedule_add(TmpEntity entity,\n",
+        "                                 const typename C::type &component) {{\n",
+        "    return check_tmp_id<C>(C::schedule_add(&e_, entity.id, component));\n",
+        "  }}\n",
+        "\n",
+        "  // Markers.\n",
+        "  template <typename C> void schedule_add(Entity entity) {{\n",
+        "    check(C::schedule_add(&e_, entity.id));\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C> void schedule_add(TmpEntity entity) {{\n",
+        "    check(C::schedule_add(&e_, entity.id));\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C>\n",
+        "  void schedule_upsert(Entity entity, const typename C::type &component) {{\n",
+        "    check(C::schedule_upsert(&e_, entity.id, component));\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C>\n",
+        "  void schedule_update(Entity entity, const typename C::type &component) {{\n",
+        "    check(C::schedule_update(&e_, entity.id, component));\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C> void schedule_remove(Entity entity) {{\n",
+        "    check(C::schedule_remove(&e_, entity.id));\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C> void schedule_enable(Entity entity) {{\n",
+        "    check(C::schedule_enable(&e_, entity.id));\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C> void schedule_disable(Entity entity) {{\n",
+        "    check(C::schedule_disable(&e_, entity.id));\n",
+        "  }}\n",
+        "\n",
+        "  // `location` must be inside a `C`.\n",
+        "  template <typename C>\n",
+        "  void schedule_store_entity_in(TmpEntity entity, vecs_id_t *location) {{\n",
+        "    check(C::schedule_store_entity_in(&e_, entity.id, location));\n",
+        "  }}\n",
+        "\n",
+        "  // `location` must be inside a `C`.\n",
+        "  template <typename C, typename D>\n",
+        "  void schedule_store_component(TmpComponentId<D> component, vecs_id_t *location) {{\n",
+        "    check(C::schedule_store_component(&e_, component.id, location));\n",
+        "  }}\n",
+        "\n",
+        "  // Events and nodes.\n",
+        "\n",
+        "  template <typename E> void emit(const typename E::type &event) {{\n",
+        "    check(E::emit(&e_, event));\n",
+        "  }}\n",
+        "\n",
+        "  // Invalidated by anything that adds or removes nodes.\n",
+        "  template <typename N> NodeRange<typename N::type> nodes() {{\n",
+        "    return N::all(&e_);\n",
+        "  }}\n",
+        "\n",
+        "private:\n",
+        "  static void check(vecs_status_t status) {{\n",
+        "    if (status != VECS_OK)\n",
+        "      {PREFIX}_OUT_OF_MEMORY();\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C> static ComponentId<C> check_id(vecs_id_t id) {{\n",
+        "    if (vecs_id_is_invalid(id))\n",
+        "      {PREFIX}_OUT_OF_MEMORY();\n",
+        "    return ComponentId<C>{{id}};\n",
+        "  }}\n",
+        "\n",
+        "  template <typename C> static TmpComponentId<C> check_tmp_id(vecs_tmp_id_t id) {{\n",
+        "    if (vecs_tmp_id_is_invalid(id))\n",
+        "      {PREFIX}_OUT_OF_MEMORY();\n",
+        "    return TmpComponentId<C>{{id}};\n",
+        "  }}\n",
+        "\n",
+        "  // First, for `from`.\n",
+        "  {prefix}_engine_t e_;\n",
+        "  State state_;\n",
+        "}};\n",
+        "\n",
+        "static_assert(std::is_standard_layout<Engine>::value,\n",
+        "              \"Engine::from needs the C engine at the start of Engine\");\n",
+        "\n",
+      ),
+      prefix = prefix.lower,
+      PREFIX = prefix.upper,
+    )
+  }
+}
+
+impl<'a> Display for CppHeader<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    write!(
+      f,
+      concat!(
+        "#ifndef {PREFIX}_ECS_HPP\n",
+        "#define {PREFIX}_ECS_HPP\n",
+        "\n",
+        "#include <cstdint>\n",
+        "#include <cstdlib>\n",
+        "#include <new>\n",
+        "#include <type_traits>\n",
+        "\n",
+        "#include \"{header_name}\"\n",
+        "\n",
+        "// Define before including this header to not use exceptions.\n",
+        "#ifndef {PREFIX}_OUT_OF_MEMORY\n",
+        "#define {PREFIX}_OUT_OF_MEMORY() throw std::bad_alloc()\n",
+        "#endif\n",
+        "\n",
+        "namespace {prefix} {{\n",
+        "\n",
+        "struct Entity {{\n",
+        "  vecs_id_t id;\n",
+        "\n",
+        "  bool operator==(Entity other) const {{ return vecs_id_eq(id, other.id); }}\n",
+        "  bool operator!=(Entity other) const {{ return !(*this == other); }}\n",
+        "}};\n",
+        "\n",
+        "// Refers to an entity that will exist after the deferred operations.\n",
+        "struct TmpEntity {{\n",
+        "  vecs_tmp_id_t id;\n",
+        "}};\n",
+        "\n",
+        "template <typename C> struct ComponentId {{\n",
+        "  vecs_id_t id;\n",
+        "\n",
+        "  bool valid() const {{ return !vecs_id_is_invalid(id); }}\n",
+        "}};\n",
+        "\n",
+        "// Refers to a component that will exist after the deferred operations.\n",
+        "template <typename C> struct TmpComponentId {{\n",
+        "  vecs_tmp_id_t id;\n",
+        "}};\n",
+        "\n",
+        "// Contiguous nodes, for range-based for loops.\n",
+        "template <typename T> class NodeRange {{\n",
+        "public:\n",
+        "  NodeRange(T *items, uint32_t len) : items_(items), len_(len) {{}}\n",
+        "\n",
+        "  T *begin() const {{ return items_; }}\n",
+        "  T *end() const {{ return items_ + len_; }}\n",
+        "  uint32_t size() const {{ return len_; }}\n",
+        "  bool empty() const {{ return len_ == 0; }}\n",
+        "  T &operator[](uint32_t i) const {{ return items_[i]; }}\n",
+        "\n",
+        "private:\n",
+        "  T *items_;\n",
+        "  uint32_t len_;\n",
+        "}};\n",
+        "\n",
+        "enum class State {{\n",
+      ),
+      header_name = self.header_name,
+      prefix = prefix.lower,
+      PREFIX = prefix.upper,
+    )?;
+
+    for state in self.data.states.values() {
+      writeln!(
+        f,
+        "  {} = {},",
+        UpperCamel::new(state.name, ""),
+        StateIdName::new(prefix, state.name),
+      )?;
+    }
+
+    writeln!(f, "}};\n")?;
+
+    for component in self.data.components.values() {
+      self.write_component(f, component)?;
+    }
+
+    for event in self.data.events.values() {
+      write!(
+        f,
+        concat!(
+          "// Event `{name}`.\n",
+          "struct {class} {{\n",
+          "  using type = {event_t};\n",
+          "\n",
+          "  static vecs_status_t emit({prefix}_engine_t *e, const type &event) {{\n",
+          "    return {prefix}_emit_{name}(e, event);\n",
+          "  }}\n",
+          "}};\n",
+          "\n",
+        ),
+        name = event.name,
+        class = UpperCamel::new(event.name, "Event"),
+        event_t = EventStructName::new(prefix, event.name),
+        prefix = prefix.lower,
+      )?;
+    }
+
+    for node in self.data.nodes.values() {
+      write!(
+        f,
+        concat!(
+          "// Node `{name}`.\n",
+          "struct {class} {{\n",
+          "  using type = {node_t};\n",
+          "\n",
+          "  static NodeRange<type> all({prefix}_engine_t *e) {{\n",
+          "    {prefix}_node_{name}_array_t nodes = {prefix}_nodes_{name}(e);\n",
+          "    return NodeRange<type>(nodes.items, nodes.len);\n",
+          "  }}\n",
+          "}};\n",
+          "\n",
+        ),
+        name = node.name,
+        class = UpperCamel::new(node.name, "Node"),
+        node_t = NodeStructName::new(prefix, node.name),
+        prefix = prefix.lower,
+      )?;
+    }
+
+    self.write_engine(f)?;
+
+    write!(
+      f,
+      "}} // namespace {prefix}\n\n#endif // !{PREFIX}_ECS_HPP\n",
+      prefix = prefix.lower,
+      PREFIX = prefix.upper,
+    )
+  }
+}
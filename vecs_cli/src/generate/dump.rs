@@ -0,0 +1,296 @@
+use std::fmt::Display;
+
+use crate::{
+  generate::{
+    common::{ComponentStructName, EngineStructName},
+    constants::ComponentMaskName,
+    generics::{
+      common::method_name, skip_lists::SkipList, sparse_dyn_arrays::SparseDynArray,
+    },
+  },
+  resolve::cst::{Cst, Field},
+};
+
+// How a field is printed in the JSON dump, based on its C type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldFormat {
+  Signed,
+  Unsigned,
+  Float,
+  Bool,
+}
+
+fn field_format(field: &Field) -> Option<FieldFormat> {
+  let typ = field.type_components.join(" ");
+
+  match typ.as_str() {
+    "char" | "signed char" | "short" | "short int" | "signed short" | "int"
+    | "signed" | "signed int" | "long" | "long int" | "signed long" | "long long"
+    | "long long int" | "signed long long" | "int8_t" | "int16_t" | "int32_t"
+    | "int64_t" | "ptrdiff_t" | "intptr_t" | "ssize_t" => Some(FieldFormat::Signed),
+    "unsigned char"
+    | "unsigned short"
+    | "unsigned short int"
+    | "unsigned"
+    | "unsigned int"
+    | "unsigned long"
+    | "unsigned long int"
+    | "unsigned long long"
+    | "unsigned long long int"
+    | "uint8_t"
+    | "uint16_t"
+    | "uint32_t"
+    | "uint64_t"
+    | "size_t"
+    | "uintptr_t" => Some(FieldFormat::Unsigned),
+    "float" | "double" | "long double" => Some(FieldFormat::Float),
+    "bool" | "_Bool" => Some(FieldFormat::Bool),
+    _ => None,
+  }
+}
+
+pub struct DumpJsonHeader<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> Display for DumpJsonHeader<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    write!(
+      f,
+      concat!(
+        "// Writes every live entity to `out` as JSON. Meant for debugging.\n",
+        "void {prefix}_dump_json({prefix}_engine_t *e, FILE *out);\n",
+      ),
+      prefix = prefix.lower,
+    )
+  }
+}
+
+pub struct DumpJsonImpl<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> Display for DumpJsonImpl<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    let entity_array =
+      SparseDynArray::new(prefix, EngineStructName::new(prefix, "entity"));
+    let entity_array_t = entity_array.get_type();
+
+    let index_index = SkipList::new(prefix, "vecs_id_t", "uint32_t");
+    let index_index_t = index_index.get_type();
+
+    write!(
+      f,
+      concat!(
+        "void {prefix}_dump_json({prefix}_engine_t *e, FILE *out) {{\n",
+        "  bool first_entity = true;\n",
+        "  fputs(\"[\", out);\n",
+        "\n",
+        "  for (uint32_t i = 0; i < e->entities.items.len; ++i) {{\n",
+        "    if ({entity_method_is_hole}(&e->entities, i))\n",
+        "      continue;\n",
+        "\n",
+        "    {prefix}_entity_t *ent = &e->entities.items.items[i];\n",
+        "    vecs_id_t entity = {{.index = i, .gen = e->entities.gens.items[i]}};\n",
+        "    bool first = true;\n",
+        "\n",
+        "    fprintf(out, \"%s\\n  {{\\\"index\\\": %lu, \\\"gen\\\": %lu\", first_entity ? \"\" : \",\",\n",
+        "            (unsigned long)entity.index, (unsigned long)entity.gen);\n",
+        "    first_entity = false;\n",
+        "\n",
+        "    fputs(\", \\\"components\\\": [\", out);\n",
+      ),
+      entity_method_is_hole = method_name!(&entity_array_t, "is_hole"),
+      prefix = prefix.lower,
+    )?;
+
+    let uses_index = !self.data.nodes.is_empty()
+      || self
+        .data
+        .components
+        .values()
+        .any(|c| !c.typ.fields.is_empty());
+
+    if uses_index {
+      writeln!(f, "    uint32_t index;")?;
+    }
+
+    for component in self.data.components.values() {
+      write!(
+        f,
+        concat!(
+          "    if (match_mask(ent->mask, {component_mask_name})) {{\n",
+          "      fprintf(out, \"%s\\\"{component_name}\\\"\", first ? \"\" : \", \");\n",
+          "      first = false;\n",
+          "    }}\n",
+        ),
+        component_name = component.name(),
+        component_mask_name = ComponentMaskName::new(prefix, component.name()),
+      )?;
+    }
+
+    write!(
+      f,
+      concat!(
+        "    fputs(\"], \\\"nodes\\\": [\", out);\n",
+        "    first = true;\n",
+      )
+    )?;
+
+    // Only the nodes of the current state are kept up to date, so we look at the
+    // actual node indices instead of matching the masks.
+    for node in self.data.nodes.values() {
+      write!(
+        f,
+        concat!(
+          "    if ({entity_to_node_method_get}(&e->entity_to_node_{node_name}, entity, &index)) {{\n",
+          "      fprintf(out, \"%s\\\"{node_name}\\\"\", first ? \"\" : \", \");\n",
+          "      first = false;\n",
+          "    }}\n",
+        ),
+        node_name = node.name,
+        entity_to_node_method_get = method_name!(&index_index_t, "get"),
+      )?;
+    }
+
+    write!(
+      f,
+      concat!(
+        "    fputs(\"], \\\"values\\\": {{\", out);\n",
+        "    first = true;\n",
+      )
+    )?;
+
+    // Disabled components are still in the index, so they will show up here even if
+    // they are not in the mask.
+    for component in self.data.components.values() {
+      if component.typ.fields.is_empty() {
+        continue;
+      }
+
+      let component_name = component.name();
+      let component_t = ComponentStructName::new(prefix, component_name);
+      let component_array = SparseDynArray::new(prefix, component_t.clone());
+      let component_array_t = component_array.get_type();
+
+      write!(
+        f,
+        concat!(
+          "    if ({entity_to_component_method_get}(&e->entity_to_component_{component_name}, entity, &index)) {{\n",
+          "      {component_t} *c = {component_array_method_get_unchecked}(&e->components_{component_name}, index);\n",
+          "      fprintf(out, \"%s\\\"{component_name}\\\": {{\", first ? \"\" : \", \");\n",
+        ),
+        component_name = component_name,
+        component_t = component_t,
+        entity_to_component_method_get = method_name!(&index_index_t, "get"),
+        component_array_method_get_unchecked =
+          method_name!(&component_array_t, "get_unchecked"),
+      )?;
+
+      for (i, field) in component.typ.fields.iter().enumerate() {
+        let sep = if i == 0 { "" } else { ", " };
+
+        match field_format(field) {
+          Some(FieldFormat::Signed) => writeln!(
+            f,
+            "      fprintf(out, \"{sep}\\\"{name}\\\": %lld\", (long long)c->{name});",
+            sep = sep,
+            name = field.name,
+          )?,
+          Some(FieldFormat::Unsigned) => writeln!(
+            f,
+            "      fprintf(out, \"{sep}\\\"{name}\\\": %llu\", (unsigned long long)c->{name});",
+            sep = sep,
+            name = field.name,
+          )?,
+          // JSON has no NaN or infinities.
+          Some(FieldFormat::Float) => writeln!(
+            f,
+            concat!(
+              "      if (isfinite(c->{name}))\n",
+              "        fprintf(out, \"{sep}\\\"{name}\\\": %.17g\", (double)c->{name});\n",
+              "      else\n",
+              "        fputs(\"{sep}\\\"{name}\\\": null\", out);",
+            ),
+            sep = sep,
+            name = field.name,
+          )?,
+          Some(FieldFormat::Bool) => writeln!(
+            f,
+            "      fprintf(out, \"{sep}\\\"{name}\\\": %s\", c->{name} ? \"true\" : \"false\");",
+            sep = sep,
+            name = field.name,
+          )?,
+          // We don't know how to print it.
+          None => writeln!(
+            f,
+            "      fputs(\"{sep}\\\"{name}\\\": null\", out);",
+            sep = sep,
+            name = field.name,
+          )?,
+        }
+      }
+
+      write!(
+        f,
+        concat!(
+          "      fputs(\"}}\", out);\n",
+          "      first = false;\n",
+          "    }}\n",
+        )
+      )?;
+    }
+
+    write!(
+      f,
+      concat!(
+        "    fputs(\"}}}}\", out);\n",
+        "  }}\n",
+        "\n",
+        "  fputs(first_entity ? \"]\\n\" : \"\\n]\\n\", out);\n",
+        "}}\n",
+      )
+    )?;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    generate::dump::{FieldFormat, field_format},
+    parse::data::str::Span,
+    resolve::cst::Field,
+  };
+
+  fn field<'a>(type_components: Vec<&'a str>) -> Field<'a> {
+    Field {
+      span: Span::default(),
+      name: "x",
+      type_components,
+    }
+  }
+
+  #[test]
+  fn test_field_format() {
+    let fmt = field_format(&field(vec!["int32_t"]));
+    assert_eq!(fmt, Some(FieldFormat::Signed));
+
+    let fmt = field_format(&field(vec!["unsigned", "long", "long"]));
+    assert_eq!(fmt, Some(FieldFormat::Unsigned));
+
+    let fmt = field_format(&field(vec!["double"]));
+    assert_eq!(fmt, Some(FieldFormat::Float));
+
+    let fmt = field_format(&field(vec!["bool"]));
+    assert_eq!(fmt, Some(FieldFormat::Bool));
+
+    let fmt = field_format(&field(vec!["struct", "transform"]));
+    assert_eq!(fmt, None);
+  }
+}
@@ -1,13 +1,20 @@
 use std::{fmt::Display, hash::Hash};
 
+use crate::resolve::cst::Prefix;
+
+// Hashes the generics as they are displayed, so that names only depend on the output
+// of the generator and on `StableHasher`.
 macro_rules! hash_internal {
   ($hasher:ident) => {};
   ($hasher:ident; $generic:expr) => {
-    std::hash::Hash::hash(&$generic, &mut $hasher);
+    let _ = std::fmt::Write::write_fmt(
+      &mut display_hash::HashWriter { hasher: &mut $hasher },
+      format_args!("{}", $generic),
+    );
   };
   ($hasher:ident; $generic:expr, $($rest:tt)*) => {
-    std::hash::Hash::hash(&$generic, &mut $hasher);
-    std::hash::Hash::hash(&0xFF, &mut $hasher);
+    crate::generate::generics::common::hash_internal!($hasher; $generic);
+    std::hash::Hasher::write_u8(&mut $hasher, 0xFF);
     crate::generate::generics::common::hash_internal!($hasher; $($rest)*);
   };
 }
@@ -15,9 +22,11 @@ macro_rules! hash_internal {
 pub(crate) use hash_internal;
 
 // Represents the name of a generic struct with name `name` parameterized on
-// `generics`. Names of methods may be constructed from it.
+// `generics`. Names of methods may be constructed from it. `prefix` is the upper case
+// symbol prefix.
 #[derive(Debug, Clone, Hash)]
 pub struct StructName<'a> {
+  pub prefix: &'a str,
   pub name: &'a str,
   pub hash: u64,
 }
@@ -26,7 +35,8 @@ impl<'a> Display for StructName<'a> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     write!(
       f,
-      "_VECSs{:x}_{}{:016x}_t",
+      "_{}s{:x}_{}{:016x}_t",
+      self.prefix,
       self.name.len(),
       self.name,
       self.hash,
@@ -35,13 +45,13 @@ impl<'a> Display for StructName<'a> {
 }
 
 macro_rules! struct_name {
-  ($name:expr) => {{
-    crate::generate::generics::common::StructName { name: $name, hash: 0 }
+  ($prefix:expr, $name:expr) => {{
+    crate::generate::generics::common::StructName { prefix: $prefix, name: $name, hash: 0 }
   }};
-  ($name:expr; $($tt:tt)*) => {{
-    let mut hasher = std::hash::DefaultHasher::new();
+  ($prefix:expr, $name:expr; $($tt:tt)*) => {{
+    let mut hasher = display_hash::StableHasher::new();
     crate::generate::generics::common::hash_internal!(hasher; $($tt)*);
-    crate::generate::generics::common::StructName { name: $name, hash: std::hash::Hasher::finish(&hasher) }
+    crate::generate::generics::common::StructName { prefix: $prefix, name: $name, hash: std::hash::Hasher::finish(&hasher) }
   }};
 }
 
@@ -72,7 +82,7 @@ macro_rules! method_name {
     crate::generate::generics::common::MethodName { strukt: $parent, name: $name, hash: 0 }
   }};
   ($parent:expr, $name:expr; $($tt:tt)*) => {{
-    let mut hasher = std::hash::DefaultHasher::new();
+    let mut hasher = display_hash::StableHasher::new();
     crate::generate::generics::common::hash_internal!(hasher; $($tt)*);
     crate::generate::generics::common::MethodName { strukt: $parent, name: $name, hash: std::hash::Hasher::finish(&hasher) }
   }};
@@ -82,6 +92,7 @@ pub(crate) use method_name;
 
 #[derive(Debug, Clone, Hash)]
 pub struct FunctionName<'a> {
+  pub prefix: &'a str,
   pub name: &'a str,
   pub hash: u64,
 }
@@ -90,7 +101,8 @@ impl<'a> Display for FunctionName<'a> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     write!(
       f,
-      "_VECSf{:x}_{}{:016x}",
+      "_{}f{:x}_{}{:016x}",
+      self.prefix,
       self.name.len(),
       self.name,
       self.hash
@@ -99,13 +111,13 @@ impl<'a> Display for FunctionName<'a> {
 }
 
 macro_rules! function_name {
-  ($name:expr) => {{
-    crate::generate::generics::common::FunctionName { name: $name, hash: 0 }
+  ($prefix:expr, $name:expr) => {{
+    crate::generate::generics::common::FunctionName { prefix: $prefix, name: $name, hash: 0 }
   }};
-  ($name:expr; $($tt:tt)*) => {{
-    let mut hasher = std::hash::DefaultHasher::new();
+  ($prefix:expr, $name:expr; $($tt:tt)*) => {{
+    let mut hasher = display_hash::StableHasher::new();
     crate::generate::generics::common::hash_internal!(hasher; $($tt)*);
-    crate::generate::generics::common::FunctionName { name: $name, hash: std::hash::Hasher::finish(&hasher) }
+    crate::generate::generics::common::FunctionName { prefix: $prefix, name: $name, hash: std::hash::Hasher::finish(&hasher) }
   }};
 }
 
@@ -114,27 +126,154 @@ pub(crate) use function_name;
 /// Used where something's name doesn't matter.
 /// Two Whatevers with equal hashes will format to equal strings.
 #[derive(Debug, Clone, Hash)]
-pub struct Whatever {
+pub struct Whatever<'a> {
+  pub prefix: &'a str,
   pub hash: u64,
 }
 
-impl Display for Whatever {
+impl<'a> Display for Whatever<'a> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "_VECSw_{:x}", self.hash)
+    write!(f, "_{}w_{:x}", self.prefix, self.hash)
   }
 }
 
 macro_rules! whatever_name {
-  ($($tt:tt)*) => {{
-    let mut hasher = std::hash::DefaultHasher::new();
+  ($prefix:expr; $($tt:tt)*) => {{
+    let mut hasher = display_hash::StableHasher::new();
     crate::generate::generics::common::hash_internal!(hasher; $($tt)*);
-    crate::generate::generics::common::Whatever { hash: std::hash::Hasher::finish(&hasher) }
+    crate::generate::generics::common::Whatever { prefix: $prefix, hash: std::hash::Hasher::finish(&hasher) }
   }};
 }
 
 pub(crate) use whatever_name;
 
+// How a type parameter appears in readable aliases: the symbol prefix (or the runtime's
+// `vecs_`) and the `_t` suffix are dropped, pointers become `ptr` and anything else
+// that can't be in an identifier becomes an underscore. E.g. `vecs_component_render_t`
+// becomes `component_render`, and `const struct foo *` becomes `const_struct_foo_ptr`.
+pub fn alias_part(prefix: &Prefix, t: &dyn Display) -> String {
+  let t = t.to_string();
+  let t = t.strip_suffix("_t").unwrap_or(&t);
+  let t = t
+    .strip_prefix(&format!("{}_", prefix.lower))
+    .or_else(|| t.strip_prefix("vecs_"))
+    .unwrap_or(t)
+    .replace('*', " ptr ");
+
+  t.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+    .filter(|w| !w.is_empty())
+    .collect::<Vec<_>>()
+    .join("_")
+}
+
+// Readable aliases of a generic struct and of its methods, so that hand-written C
+// doesn't have to spell out mangled names, e.g. `vecs_dyn_array_component_render_t`
+// and `vecs_dyn_array_component_render_push`. Different generics can have the same
+// alias, e.g. `foo_t` and `foo`, so the aliases are guarded and the first one wins.
+pub struct Aliases<'a> {
+  pub prefix: &'a Prefix,
+  pub strukt: &'a StructName<'a>,
+  pub generics: &'a [&'a dyn Display],
+  pub methods: &'a [&'a str],
+}
+
+impl<'a> Display for Aliases<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let mut alias = format!("{}_{}", self.prefix.lower, self.strukt.name);
+    for generic in self.generics {
+      alias.push('_');
+      alias.push_str(&alias_part(self.prefix, *generic));
+    }
+
+    let guard = format!("{}_ALIASES", alias.to_uppercase());
+    writeln!(f, "#ifndef {}\n#define {}", guard, guard)?;
+    writeln!(f, "typedef {} {}_t;", self.strukt, alias)?;
+    for method in self.methods {
+      writeln!(
+        f,
+        "#define {}_{} {}",
+        alias,
+        method,
+        method_name!(self.strukt, method)
+      )?;
+    }
+
+    writeln!(f, "#endif\n")
+  }
+}
+
 // Clone for composing e.g. maybe A<T> requires B<T>;
 // Display generates the output;
 // Hash used for name mangling.
 pub trait GenericElement = Clone + Display + Hash;
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    fmt::Display,
+    io::Write,
+    process::{Command, Stdio},
+  };
+
+  use super::{Aliases, alias_part};
+  use crate::resolve::cst::Prefix;
+
+  #[test]
+  fn test_alias_part() {
+    let vecs = Prefix::new("vecs");
+    let ui = Prefix::new("ui");
+
+    assert_eq!(
+      alias_part(&vecs, &"vecs_component_render_t"),
+      "component_render"
+    );
+    assert_eq!(alias_part(&vecs, &"uint32_t"), "uint32");
+    assert_eq!(alias_part(&ui, &"ui_node_move_t"), "node_move");
+    assert_eq!(alias_part(&ui, &"vecs_id_t"), "id");
+    assert_eq!(alias_part(&vecs, &"struct foo *"), "struct_foo_ptr");
+    assert_eq!(alias_part(&vecs, &"const foo"), "const_foo");
+  }
+
+  #[test]
+  fn test_alias_collision() {
+    let vecs = Prefix::new("vecs");
+    let a = struct_name!("VECS", "dyn_array"; "foo_t");
+    let b = struct_name!("VECS", "dyn_array"; "foo");
+    let aliases = |strukt, generic: &dyn Display| {
+      Aliases {
+        prefix: &vecs,
+        strukt,
+        generics: &[generic],
+        methods: &["push"],
+      }
+      .to_string()
+    };
+
+    let source = format!(
+      "typedef struct {{ int x; }} {};\ntypedef struct {{ int x; }} {};\n{}{}",
+      a,
+      b,
+      aliases(&a, &"foo_t"),
+      aliases(&b, &"foo")
+    );
+    // Both alias `vecs_dyn_array_foo_t`, which only the first one gets.
+    assert_eq!(source.matches(" vecs_dyn_array_foo_t;").count(), 2);
+    assert_eq!(
+      source.matches("#ifndef VECS_DYN_ARRAY_FOO_ALIASES").count(),
+      2
+    );
+
+    let mut compiler = Command::new(std::env::var("CC").unwrap_or("cc".to_string()))
+      .args(["-fsyntax-only", "-Werror", "-x", "c", "-"])
+      .stdin(Stdio::piped())
+      .spawn()
+      .expect("failed to run the C compiler");
+    compiler
+      .stdin
+      .take()
+      .unwrap()
+      .write_all(source.as_bytes())
+      .expect("write error");
+    assert!(compiler.wait().expect("C compiler error").success());
+  }
+}
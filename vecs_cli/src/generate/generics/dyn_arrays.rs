@@ -1,16 +1,19 @@
 use std::fmt::Display;
 
+use crate::resolve::cst::Prefix;
+
 use crate::generate::generics::common::{
-  GenericElement, StructName, method_name, struct_name,
+  Aliases, GenericElement, StructName, method_name, struct_name,
 };
 
-pub struct DynArray<T: GenericElement> {
+pub struct DynArray<'p, T: GenericElement> {
+  pub prefix: &'p Prefix,
   pub element_t: T,
 }
 
-impl<T: GenericElement> DynArray<T> {
-  pub fn new(element_t: T) -> Self {
-    Self { element_t }
+impl<'p, T: GenericElement> DynArray<'p, T> {
+  pub fn new(prefix: &'p Prefix, element_t: T) -> Self {
+    Self { prefix, element_t }
   }
 
   pub fn header<'a>(&'a self) -> DynArrayHeader<'a, T> {
@@ -22,12 +25,12 @@ impl<T: GenericElement> DynArray<T> {
   }
 
   pub fn get_type<'a>(&'a self) -> StructName<'a> {
-    struct_name!("dyn_array"; self.element_t)
+    struct_name!(&self.prefix.upper, "dyn_array"; self.element_t)
   }
 }
 
-pub struct DynArrayHeader<'a, T: GenericElement>(&'a DynArray<T>);
-pub struct DynArrayImpl<'a, T: GenericElement>(&'a DynArray<T>);
+pub struct DynArrayHeader<'a, T: GenericElement>(&'a DynArray<'a, T>);
+pub struct DynArrayImpl<'a, T: GenericElement>(&'a DynArray<'a, T>);
 
 impl<'a, T: GenericElement> Display for DynArrayHeader<'a, T> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -42,12 +45,13 @@ impl<'a, T: GenericElement> Display for DynArrayHeader<'a, T> {
         "  {element_t} *items;\n",
         "  uint32_t len;\n",
         "  uint32_t cap;\n",
+        "  const vecs_allocator_t *allocator;\n",
         "}} {self_t};\n",
         "\n",
-        "void {method_init}({self_t} *self, uint32_t cap);\n",
-        "void {method_grow}({self_t} *self);\n",
-        "void {method_fit}({self_t} *self, uint32_t len);\n",
-        "uint32_t {method_push}({self_t} *self, {element_t} value);\n",
+        "vecs_status_t {method_init}({self_t} *self, uint32_t cap, const vecs_allocator_t *allocator);\n",
+        "vecs_status_t {method_grow}({self_t} *self);\n",
+        "vecs_status_t {method_fit}({self_t} *self, uint32_t len);\n",
+        "vecs_status_t {method_push}({self_t} *self, {element_t} value, uint32_t *index);\n",
         "{element_t} {method_pop}({self_t} *self);\n",
         "{element_t} {method_swap_remove}({self_t} *self, uint32_t index);\n",
         "void {method_destroy}({self_t} *self);\n",
@@ -62,7 +66,23 @@ impl<'a, T: GenericElement> Display for DynArrayHeader<'a, T> {
       method_pop = method_name!(&self_t, "pop"),
       method_swap_remove = method_name!(&self_t, "swap_remove"),
       method_destroy = method_name!(&self_t, "destroy"),
-    )
+    )?;
+
+    Aliases {
+      prefix: self.0.prefix,
+      strukt: &self_t,
+      generics: &[element_t],
+      methods: &[
+        "init",
+        "grow",
+        "fit",
+        "push",
+        "pop",
+        "swap_remove",
+        "destroy",
+      ],
+    }
+    .fmt(f)
   }
 }
 
@@ -75,33 +95,49 @@ impl<'a, T: GenericElement> Display for DynArrayImpl<'a, T> {
       f,
       concat!(
         "// Dynamic array of `{element_t}`.\n",
-        "void {method_init}({self_t} *self, uint32_t cap) {{\n",
+        "vecs_status_t {method_init}({self_t} *self, uint32_t cap, const vecs_allocator_t *allocator) {{\n",
+        "  self->allocator = allocator;\n",
+        "  self->items = NULL;\n",
+        "  self->len = 0;\n",
+        "  self->cap = 0;\n",
         "  if (cap > 0) {{\n",
-        "    self->items = malloc(cap * sizeof({element_t}));\n",
-        "  }} else {{\n",
-        "    self->items = NULL;\n",
+        "    self->items = vecs_alloc(allocator, cap * sizeof({element_t}));\n",
+        "    if (self->items == NULL)\n",
+        "      return VECS_ERR_OOM;\n",
+        "    self->cap = cap;\n",
         "  }}\n",
-        "  self->len = 0;\n",
-        "  self->cap = cap;\n",
+        "  return VECS_OK;\n",
         "}}\n",
         "\n",
-        "void {method_grow}({self_t} *self) {{\n",
+        "vecs_status_t {method_grow}({self_t} *self) {{\n",
         "  uint32_t new_cap = self->cap + (self->cap >> 1) + 1;\n",
-        "  self->items = realloc(self->items, sizeof({element_t}) * new_cap);\n",
+        "  {element_t} *new_items = vecs_realloc(self->allocator, self->items, sizeof({element_t}) * self->cap,\n",
+        "                                        sizeof({element_t}) * new_cap);\n",
+        "  if (new_items == NULL)\n",
+        "    return VECS_ERR_OOM;\n",
+        "\n",
+        "  self->items = new_items;\n",
         "  self->cap = new_cap;\n",
+        "  return VECS_OK;\n",
         "}}\n",
         "\n",
-        "void {method_fit}({self_t} *self, uint32_t len) {{\n",
+        "vecs_status_t {method_fit}({self_t} *self, uint32_t len) {{\n",
         "  while (self->cap < len)\n",
-        "    {method_grow}(self);\n",
+        "    if ({method_grow}(self) != VECS_OK)\n",
+        "      return VECS_ERR_OOM;\n",
+        "  return VECS_OK;\n",
         "}}\n",
         "\n",
-        "uint32_t {method_push}({self_t} *self, {element_t} value) {{\n",
+        "// `index` may be NULL.\n",
+        "vecs_status_t {method_push}({self_t} *self, {element_t} value, uint32_t *index) {{\n",
         "  uint32_t new_len = self->len + 1;\n",
-        "  {method_fit}(self, new_len);\n",
+        "  if ({method_fit}(self, new_len) != VECS_OK)\n",
+        "    return VECS_ERR_OOM;\n",
         "  self->items[self->len] = value;\n",
+        "  if (index != NULL)\n",
+        "    *index = self->len;\n",
         "  self->len = new_len;\n",
-        "  return new_len - 1;\n",
+        "  return VECS_OK;\n",
         "}}\n",
         "\n",
         "{element_t} {method_pop}({self_t} *self) {{\n",
@@ -119,7 +155,7 @@ impl<'a, T: GenericElement> Display for DynArrayImpl<'a, T> {
         "\n",
         "void {method_destroy}({self_t} *self) {{\n",
         "  if (self->items != NULL)\n",
-        "    free(self->items);\n",
+        "    vecs_free(self->allocator, self->items);\n",
         "\n",
         "  self->items = NULL;\n",
         "  self->len = 0;\n",
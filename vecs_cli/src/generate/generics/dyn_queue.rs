@@ -1,16 +1,19 @@
 use std::fmt::Display;
 
+use crate::resolve::cst::Prefix;
+
 use crate::generate::generics::common::{
-  GenericElement, StructName, method_name, struct_name,
+  Aliases, GenericElement, StructName, method_name, struct_name,
 };
 
-pub struct DynQueue<T: GenericElement> {
+pub struct DynQueue<'p, T: GenericElement> {
+  pub prefix: &'p Prefix,
   pub element_t: T,
 }
 
-impl<T: GenericElement> DynQueue<T> {
-  pub fn new(element_t: T) -> Self {
-    Self { element_t }
+impl<'p, T: GenericElement> DynQueue<'p, T> {
+  pub fn new(prefix: &'p Prefix, element_t: T) -> Self {
+    Self { prefix, element_t }
   }
 
   pub fn header<'a>(&'a self) -> DynQueueHeader<'a, T> {
@@ -22,12 +25,12 @@ impl<T: GenericElement> DynQueue<T> {
   }
 
   pub fn get_type<'a>(&'a self) -> StructName<'a> {
-    struct_name!("dyn_queue"; self.element_t)
+    struct_name!(&self.prefix.upper, "dyn_queue"; self.element_t)
   }
 }
 
-pub struct DynQueueHeader<'a, T: GenericElement>(&'a DynQueue<T>);
-pub struct DynQueueImpl<'a, T: GenericElement>(&'a DynQueue<T>);
+pub struct DynQueueHeader<'a, T: GenericElement>(&'a DynQueue<'a, T>);
+pub struct DynQueueImpl<'a, T: GenericElement>(&'a DynQueue<'a, T>);
 
 impl<'a, T: GenericElement> Display for DynQueueHeader<'a, T> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -43,12 +46,13 @@ impl<'a, T: GenericElement> Display for DynQueueHeader<'a, T> {
         "  uint32_t len;\n",
         "  uint32_t cap;\n",
         "  uint32_t head;\n",
+        "  const vecs_allocator_t *allocator;\n",
         "}} {self_t};\n",
         "\n",
-        "void {method_init}({self_t} *self, uint32_t cap);\n",
-        "void {method_grow}({self_t} *self);\n",
-        "void {method_fit}({self_t} *self, uint32_t len);\n",
-        "void {method_enqueue}({self_t} *self, {element_t} value);\n",
+        "vecs_status_t {method_init}({self_t} *self, uint32_t cap, const vecs_allocator_t *allocator);\n",
+        "vecs_status_t {method_grow}({self_t} *self);\n",
+        "vecs_status_t {method_fit}({self_t} *self, uint32_t len);\n",
+        "vecs_status_t {method_enqueue}({self_t} *self, {element_t} value);\n",
         "{element_t} {method_dequeue}({self_t} *self);\n",
         "void {method_destroy}({self_t} *self);\n",
         "\n",
@@ -61,7 +65,15 @@ impl<'a, T: GenericElement> Display for DynQueueHeader<'a, T> {
       method_enqueue = method_name!(&self_t, "enqueue"),
       method_dequeue = method_name!(&self_t, "dequeue"),
       method_destroy = method_name!(&self_t, "destroy"),
-    )
+    )?;
+
+    Aliases {
+      prefix: self.0.prefix,
+      strukt: &self_t,
+      generics: &[&self.0.element_t],
+      methods: &["init", "grow", "fit", "enqueue", "dequeue", "destroy"],
+    }
+    .fmt(f)
   }
 }
 
@@ -74,43 +86,55 @@ impl<'a, T: GenericElement> Display for DynQueueImpl<'a, T> {
       f,
       concat!(
         "// Dynamic queue of `{element_t}`.\n",
-        "void {method_init}({self_t} *self, uint32_t cap) {{\n",
-        "  if (cap > 0) {{\n",
-        "    self->items = malloc(cap * sizeof({element_t}));\n",
-        "  }} else {{\n",
-        "    self->items = NULL;\n",
-        "  }}\n",
+        "vecs_status_t {method_init}({self_t} *self, uint32_t cap, const vecs_allocator_t *allocator) {{\n",
+        "  self->allocator = allocator;\n",
+        "  self->items = NULL;\n",
         "  self->len = 0;\n",
-        "  self->cap = cap;\n",
+        "  self->cap = 0;\n",
         "  self->head = 0;\n",
+        "  if (cap > 0) {{\n",
+        "    self->items = vecs_alloc(allocator, cap * sizeof({element_t}));\n",
+        "    if (self->items == NULL)\n",
+        "      return VECS_ERR_OOM;\n",
+        "    self->cap = cap;\n",
+        "  }}\n",
+        "  return VECS_OK;\n",
         "}}\n",
         "\n",
-        "void {method_grow}({self_t} *self) {{\n",
+        "vecs_status_t {method_grow}({self_t} *self) {{\n",
         "  uint32_t new_cap = self->cap + (self->cap >> 1) + 1;\n",
-        "  {element_t} *new_items = malloc(sizeof({element_t}) * new_cap);\n",
+        "  {element_t} *new_items = vecs_alloc(self->allocator, sizeof({element_t}) * new_cap);\n",
+        "  if (new_items == NULL)\n",
+        "    return VECS_ERR_OOM;\n",
         "\n",
         "  for (uint32_t i = 0; i < self->len; ++i) {{\n",
         "    uint32_t j = (self->head + i) % self->cap;\n",
         "    new_items[i] = self->items[j];\n",
         "  }}\n",
         "\n",
-        "  free(self->items);\n",
+        "  if (self->items != NULL)\n",
+        "    vecs_free(self->allocator, self->items);\n",
         "  self->items = new_items;\n",
         "  self->cap = new_cap;\n",
         "  self->head = 0;\n",
+        "  return VECS_OK;\n",
         "}}\n",
         "\n",
-        "void {method_fit}({self_t} *self, uint32_t len) {{\n",
+        "vecs_status_t {method_fit}({self_t} *self, uint32_t len) {{\n",
         "  while (self->cap < len)\n",
-        "    {method_grow}(self);\n",
+        "    if ({method_grow}(self) != VECS_OK)\n",
+        "      return VECS_ERR_OOM;\n",
+        "  return VECS_OK;\n",
         "}}\n",
         "\n",
-        "void {method_enqueue}({self_t} *self, {element_t} value) {{\n",
+        "vecs_status_t {method_enqueue}({self_t} *self, {element_t} value) {{\n",
         "  uint32_t new_len = self->len + 1;\n",
-        "  {method_fit}(self, new_len);\n",
+        "  if ({method_fit}(self, new_len) != VECS_OK)\n",
+        "    return VECS_ERR_OOM;\n",
         "  uint32_t j = (self->head + self->len) % self->cap;\n",
         "  self->items[j] = value;\n",
         "  self->len = new_len;\n",
+        "  return VECS_OK;\n",
         "}}\n",
         "\n",
         "{element_t} {method_dequeue}({self_t} *self) {{\n",
@@ -122,7 +146,7 @@ impl<'a, T: GenericElement> Display for DynQueueImpl<'a, T> {
         "\n",
         "void {method_destroy}({self_t} *self) {{\n",
         "  if (self->items != NULL)\n",
-        "    free(self->items);\n",
+        "    vecs_free(self->allocator, self->items);\n",
         "\n",
         "  self->items = NULL;\n",
         "  self->len = 0;\n",
@@ -1,20 +1,27 @@
 use std::fmt::Display;
 
+use crate::resolve::cst::Prefix;
+
 use crate::generate::generics::common::{
-  GenericElement, StructName, Whatever, function_name, method_name, struct_name,
-  whatever_name,
+  Aliases, GenericElement, StructName, Whatever, function_name, method_name,
+  struct_name, whatever_name,
 };
 
 // Entry of the skip list, containing the values of the next entry (the one to the
 // right) and pointers to entries to the right and below.
-pub struct SkipListEntry<K: GenericElement, V: GenericElement> {
+pub struct SkipListEntry<'p, K: GenericElement, V: GenericElement> {
+  pub prefix: &'p Prefix,
   pub key_t: K,
   pub element_t: V,
 }
 
-impl<K: GenericElement, V: GenericElement> SkipListEntry<K, V> {
-  pub fn new(key_t: K, element_t: V) -> Self {
-    Self { key_t, element_t }
+impl<'p, K: GenericElement, V: GenericElement> SkipListEntry<'p, K, V> {
+  pub fn new(prefix: &'p Prefix, key_t: K, element_t: V) -> Self {
+    Self {
+      prefix,
+      key_t,
+      element_t,
+    }
   }
 
   pub fn header<'a>(&'a self) -> SkipListEntryHeader<'a, K, V> {
@@ -26,16 +33,16 @@ impl<K: GenericElement, V: GenericElement> SkipListEntry<K, V> {
   }
 
   pub fn get_type<'a>(&'a self) -> StructName<'a> {
-    struct_name!("skip_list_entry"; self.key_t, self.element_t)
+    struct_name!(&self.prefix.upper, "skip_list_entry"; self.key_t, self.element_t)
   }
 
-  pub fn get_whatever<'a>(&'a self) -> Whatever {
-    whatever_name!("skip_list_entry", self.key_t, self.element_t)
+  pub fn get_whatever<'a>(&'a self) -> Whatever<'a> {
+    whatever_name!(&self.prefix.upper; "skip_list_entry", self.key_t, self.element_t)
   }
 }
 
 pub struct SkipListEntryHeader<'a, K: GenericElement, V: GenericElement>(
-  &'a SkipListEntry<K, V>,
+  &'a SkipListEntry<'a, K, V>,
 );
 
 pub struct SkipListEntryImpl();
@@ -61,7 +68,15 @@ impl<'a, K: GenericElement, V: GenericElement> Display
       self_t = self_t,
       key_t = self.0.key_t,
       element_t = self.0.element_t,
-    )
+    )?;
+
+    Aliases {
+      prefix: self.0.prefix,
+      strukt: &self_t,
+      generics: &[&self.0.key_t, &self.0.element_t],
+      methods: &[],
+    }
+    .fmt(f)
   }
 }
 
@@ -72,18 +87,20 @@ impl Display for SkipListEntryImpl {
 }
 
 // The actual skip list.
-pub struct SkipList<K: GenericElement, V: GenericElement> {
+pub struct SkipList<'p, K: GenericElement, V: GenericElement> {
+  pub prefix: &'p Prefix,
   pub key_t: K,
   pub element_t: V,
-  pub entry: SkipListEntry<K, V>,
+  pub entry: SkipListEntry<'p, K, V>,
 }
 
-impl<K: GenericElement, V: GenericElement> SkipList<K, V> {
-  pub fn new(key_t: K, element_t: V) -> Self {
+impl<'p, K: GenericElement, V: GenericElement> SkipList<'p, K, V> {
+  pub fn new(prefix: &'p Prefix, key_t: K, element_t: V) -> Self {
     Self {
+      prefix,
       key_t: key_t.clone(),
       element_t: element_t.clone(),
-      entry: SkipListEntry::new(key_t.clone(), element_t.clone()),
+      entry: SkipListEntry::new(prefix, key_t.clone(), element_t.clone()),
     }
   }
 
@@ -96,18 +113,20 @@ impl<K: GenericElement, V: GenericElement> SkipList<K, V> {
   }
 
   pub fn get_type<'a>(&'a self) -> StructName<'a> {
-    struct_name!("skip_list"; self.key_t, self.element_t)
+    struct_name!(&self.prefix.upper, "skip_list"; self.key_t, self.element_t)
   }
 
-  pub fn get_whatever<'a>(&'a self) -> Whatever {
-    whatever_name!("skip_list", self.key_t, self.element_t)
+  pub fn get_whatever<'a>(&'a self) -> Whatever<'a> {
+    whatever_name!(&self.prefix.upper; "skip_list", self.key_t, self.element_t)
   }
 }
 
 pub struct SkipListHeader<'a, K: GenericElement, V: GenericElement>(
-  &'a SkipList<K, V>,
+  &'a SkipList<'a, K, V>,
+);
+pub struct SkipListImpl<'a, K: GenericElement, V: GenericElement>(
+  &'a SkipList<'a, K, V>,
 );
-pub struct SkipListImpl<'a, K: GenericElement, V: GenericElement>(&'a SkipList<K, V>);
 pub struct SkipListImplInit();
 
 impl<'a, K: GenericElement, V: GenericElement> Display for SkipListHeader<'a, K, V> {
@@ -131,10 +150,11 @@ impl<'a, K: GenericElement, V: GenericElement> Display for SkipListHeader<'a, K,
         "  // This can't be in-place because it's self-referential.\n",
         "  {entry_t} *header;\n",
         "  uint8_t height;\n",
+        "  const vecs_allocator_t *allocator;\n",
         "}} {self_t};\n",
         "\n",
-        "void {method_init}({self_t} *self);\n",
-        "void {method_add}({self_t} *self, {key_t} key, {element_t} value);\n",
+        "vecs_status_t {method_init}({self_t} *self, const vecs_allocator_t *allocator);\n",
+        "vecs_status_t {method_add}({self_t} *self, {key_t} key, {element_t} value);\n",
         "bool {method_get}({self_t} *self, {key_t} key, {element_t} *result);\n",
         "bool {method_remove}({self_t} *self, {key_t} key, {element_t} *result);\n",
         "void {method_destroy}({self_t} *self);\n",
@@ -151,7 +171,15 @@ impl<'a, K: GenericElement, V: GenericElement> Display for SkipListHeader<'a, K,
       method_get = method_name!(&self_t, "get"),
       method_remove = method_name!(&self_t, "remove"),
       method_destroy = method_name!(&self_t, "destroy"),
-    )
+    )?;
+
+    Aliases {
+      prefix: self.0.prefix,
+      strukt: &self_t,
+      generics: &[key_t, element_t],
+      methods: &["init", "add", "get", "remove", "destroy"],
+    }
+    .fmt(f)
   }
 }
 
@@ -167,9 +195,9 @@ impl<'a, K: GenericElement, V: GenericElement> Display for SkipListImpl<'a, K, V
     let entry = &self.0.entry;
     let entry_t = entry.get_type();
 
-    let max_key_fn_name = function_name!("max"; key_t);
-    let key_eq_fn_name = function_name!("eq"; key_t);
-    let key_cmp_fn_name = function_name!("cmp"; key_t);
+    let max_key_fn_name = function_name!(&self.0.prefix.upper, "max"; key_t);
+    let key_eq_fn_name = function_name!(&self.0.prefix.upper, "eq"; key_t);
+    let key_cmp_fn_name = function_name!(&self.0.prefix.upper, "cmp"; key_t);
 
     write!(
       f,
@@ -177,9 +205,12 @@ impl<'a, K: GenericElement, V: GenericElement> Display for SkipListImpl<'a, K, V
         "// Skip list of `{element_t}` with key `{key_t}`.\n",
         "{entry_impl}",
         "\n",
-        "void {method_init}({self_t} *self) {{\n",
+        "vecs_status_t {method_init}({self_t} *self, const vecs_allocator_t *allocator) {{\n",
         "  const size_t size = {MAX_HEIGHT} * sizeof self->header[0];\n",
-        "  self->header = ({entry_t} *)malloc(size);\n",
+        "  self->allocator = allocator;\n",
+        "  self->header = ({entry_t} *)vecs_alloc(allocator, size);\n",
+        "  if (self->header == NULL)\n",
+        "    return VECS_ERR_OOM;\n",
         "  self->height = 1;\n",
         "\n",
         "  self->header[0].next_key = {max_key_fn_name}();\n",
@@ -193,9 +224,10 @@ impl<'a, K: GenericElement, V: GenericElement> Display for SkipListImpl<'a, K, V
         "    self->header[i].right = NULL;\n",
         "    self->header[i].down = &self->header[i - 1];\n",
         "  }}\n",
+        "  return VECS_OK;\n",
         "}}\n",
         "\n",
-        "void {method_add}({self_t} *self, {key_t} key, {element_t} value) {{\n",
+        "vecs_status_t {method_add}({self_t} *self, {key_t} key, {element_t} value) {{\n",
         "  uint8_t ceiling_index = self->height - 1;\n",
         "  uint8_t level = ceiling_index;\n",
         "\n",
@@ -218,7 +250,9 @@ impl<'a, K: GenericElement, V: GenericElement> Display for SkipListImpl<'a, K, V
         "\n",
         "  uint8_t block_height = skiplist_random_level();\n",
         "  {entry_t} *block =\n",
-        "      ({entry_t} *)malloc(block_height * sizeof({entry_t}));\n",
+        "      ({entry_t} *)vecs_alloc(self->allocator, block_height * sizeof({entry_t}));\n",
+        "  if (block == NULL)\n",
+        "    return VECS_ERR_OOM;\n",
         "\n",
         "  if (block_height > self->height) {{\n",
         "    uint8_t old_height = self->height;\n",
@@ -266,6 +300,7 @@ impl<'a, K: GenericElement, V: GenericElement> Display for SkipListImpl<'a, K, V
         "  rightmosts[0]->right = &block[0];\n",
         "  rightmosts[0]->next_key = key;\n",
         "  rightmosts[0]->next_value = value;\n",
+        "  return VECS_OK;\n",
         "}}\n",
         "bool {method_get}({self_t} *self, {key_t} key, {element_t} *result) {{\n",
         "  {entry_t} *current = &self->header[self->height - 1];\n",
@@ -309,7 +344,7 @@ impl<'a, K: GenericElement, V: GenericElement> Display for SkipListImpl<'a, K, V
         "        current = current->down;\n",
         "      }} else {{\n",
         "        *result = next_value;\n",
-        "        free(next);\n",
+        "        vecs_free(self->allocator, next);\n",
         "        return true;\n",
         "      }}\n",
         "    }} else {{\n",
@@ -320,15 +355,20 @@ impl<'a, K: GenericElement, V: GenericElement> Display for SkipListImpl<'a, K, V
         "  return false;\n",
         "}}\n",
         "void {method_destroy}({self_t} *self) {{\n",
+        "  // Never initialized or failed to.\n",
+        "  if (self->header == NULL)\n",
+        "    return;\n",
+        "\n",
         "  {entry_t} *header = self->header, *current = header[0].right;\n",
         "\n",
         "  while (current != NULL) {{\n",
         "    {entry_t} *to_remove = current;\n",
         "    current = current->right;\n",
-        "    free(to_remove);\n",
+        "    vecs_free(self->allocator, to_remove);\n",
         "  }}\n",
         "\n",
-        "  free(header);\n",
+        "  vecs_free(self->allocator, header);\n",
+        "  self->header = NULL;\n",
         "}}\n",
         "\n",
       ),
@@ -360,7 +400,7 @@ impl Display for SkipListImplInit {
         "  return (x << k) | (x >> (64 - k));\n",
         "}}\n",
         "\n",
-        "uint64_t s[4] = {{2611686018427387905, 8305843009213693953, 9152921504606846977, 476460752303423488}};\n",
+        "static uint64_t s[4] = {{2611686018427387905, 8305843009213693953, 9152921504606846977, 476460752303423488}};\n",
         "\n",
         "// xoshiro256+\n",
         "static inline uint64_t rng_next(void) {{\n",
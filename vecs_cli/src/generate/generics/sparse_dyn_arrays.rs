@@ -1,19 +1,22 @@
 use std::fmt::Display;
 
+use crate::resolve::cst::Prefix;
+
 use crate::generate::generics::common::{
-  GenericElement, StructName, method_name, struct_name,
+  Aliases, GenericElement, StructName, method_name, struct_name,
 };
 use crate::generate::generics::dyn_queue::DynQueue;
 
 use super::dyn_arrays::DynArray;
 
-pub struct SparseDynArray<T: GenericElement> {
+pub struct SparseDynArray<'p, T: GenericElement> {
+  pub prefix: &'p Prefix,
   pub element_t: T,
 }
 
-impl<T: GenericElement> SparseDynArray<T> {
-  pub fn new(element_t: T) -> Self {
-    Self { element_t }
+impl<'p, T: GenericElement> SparseDynArray<'p, T> {
+  pub fn new(prefix: &'p Prefix, element_t: T) -> Self {
+    Self { prefix, element_t }
   }
 
   pub fn header<'a>(&'a self) -> SparseDynArrayHeader<'a, T> {
@@ -25,28 +28,28 @@ impl<T: GenericElement> SparseDynArray<T> {
   }
 
   pub fn get_type<'a>(&'a self) -> StructName<'a> {
-    struct_name!("sparse_dyn_array"; self.element_t)
+    struct_name!(&self.prefix.upper, "sparse_dyn_array"; self.element_t)
   }
 }
 
-pub struct SparseDynArrayHeader<'a, T: GenericElement>(&'a SparseDynArray<T>);
-pub struct SparseDynArrayImpl<'a, T: GenericElement>(&'a SparseDynArray<T>);
+pub struct SparseDynArrayHeader<'a, T: GenericElement>(&'a SparseDynArray<'a, T>);
+pub struct SparseDynArrayImpl<'a, T: GenericElement>(&'a SparseDynArray<'a, T>);
 
 impl<'a, T: GenericElement> Display for SparseDynArrayHeader<'a, T> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     let element_t = &self.0.element_t;
     let self_t = self.0.get_type();
 
-    let element_dyn_arr = DynArray::new(element_t.clone());
+    let element_dyn_arr = DynArray::new(self.0.prefix, element_t.clone());
     let element_dyn_arr_t = element_dyn_arr.get_type();
 
-    let uint32_dyn_arr = DynArray::new("uint32_t");
-    let uint32_dyn_queue = DynQueue::new("uint32_t");
+    let uint32_dyn_arr = DynArray::new(self.0.prefix, "uint32_t");
+    let uint32_dyn_queue = DynQueue::new(self.0.prefix, "uint32_t");
 
     let gen_dyn_arr_t = uint32_dyn_arr.get_type();
     let hole_indices_dyn_queue_t = uint32_dyn_queue.get_type();
 
-    let uint64_dyn_arr = DynArray::new("uint64_t");
+    let uint64_dyn_arr = DynArray::new(self.0.prefix, "uint64_t");
     let holes_dyn_arr_t = uint64_dyn_arr.get_type();
 
     write!(
@@ -72,10 +75,11 @@ impl<'a, T: GenericElement> Display for SparseDynArrayHeader<'a, T> {
         "  uint32_t len;\n",
         "}} {self_t};\n",
         "\n",
-        "void {method_init}({self_t} *self, uint32_t cap);\n",
+        "vecs_status_t {method_init}({self_t} *self, uint32_t cap, const vecs_allocator_t *allocator);\n",
         "bool {method_is_hole}({self_t} *self, uint32_t index);\n",
         "{element_t} *{method_get}({self_t} *self, uint32_t index, uint32_t gen);\n",
         "{element_t} *{method_get_unchecked}({self_t} *self, uint32_t index);\n",
+        "// Returns NULL if out of memory.\n",
         "{element_t} *{method_push}({self_t} *self, {element_t} value, uint32_t *index, uint32_t *gen);\n",
         "bool {method_remove}({self_t} *self, uint32_t index, uint32_t gen, {element_t} *result);\n",
         "bool {method_remove_unchecked}({self_t} *self, uint32_t index, {element_t} *result);\n",
@@ -96,7 +100,24 @@ impl<'a, T: GenericElement> Display for SparseDynArrayHeader<'a, T> {
       method_remove = method_name!(&self_t, "remove"),
       method_remove_unchecked = method_name!(&self_t, "remove_unchecked"),
       method_destroy = method_name!(&self_t, "destroy"),
-    )
+    )?;
+
+    Aliases {
+      prefix: self.0.prefix,
+      strukt: &self_t,
+      generics: &[element_t],
+      methods: &[
+        "init",
+        "is_hole",
+        "get",
+        "get_unchecked",
+        "push",
+        "remove",
+        "remove_unchecked",
+        "destroy",
+      ],
+    }
+    .fmt(f)
   }
 }
 
@@ -105,37 +126,43 @@ impl<'a, T: GenericElement> Display for SparseDynArrayImpl<'a, T> {
     let element_t = &self.0.element_t;
     let self_t = self.0.get_type();
 
-    let element_dyn_arr = DynArray::new(element_t.clone());
+    let element_dyn_arr = DynArray::new(self.0.prefix, element_t.clone());
     let element_dyn_arr_t = element_dyn_arr.get_type();
 
-    let uint32_dyn_arr = DynArray::new("uint32_t");
-    let uint32_dyn_queue = DynQueue::new("uint32_t");
+    let uint32_dyn_arr = DynArray::new(self.0.prefix, "uint32_t");
+    let uint32_dyn_queue = DynQueue::new(self.0.prefix, "uint32_t");
 
     let gen_dyn_arr_t = uint32_dyn_arr.get_type();
     let hole_indices_dyn_queue_t = uint32_dyn_queue.get_type();
 
-    let holes_dyn_arr = DynArray::new("uint64_t");
+    let holes_dyn_arr = DynArray::new(self.0.prefix, "uint64_t");
     let holes_dyn_arr_t = holes_dyn_arr.get_type();
 
     write!(
       f,
       concat!(
         "// Sparse dynamic array of `{element_t}`.\n",
-        "void {method_init}({self_t} *self, uint32_t cap) {{\n",
-        "  {element_method_init}(&self->items, cap);\n",
-        "  {holes_method_init}(&self->holes, cap);\n",
-        "  {hole_indices_method_init}(&self->hole_indices, cap);\n",
-        "  {gen_method_init}(&self->gens, cap);\n",
+        "vecs_status_t {method_init}({self_t} *self, uint32_t cap, const vecs_allocator_t *allocator) {{\n",
         "  self->len = 0;\n",
+        "  vecs_status_t status = {element_method_init}(&self->items, cap, allocator);\n",
+        "  if ({holes_method_init}(&self->holes, cap, allocator) != VECS_OK)\n",
+        "    status = VECS_ERR_OOM;\n",
+        "  if ({hole_indices_method_init}(&self->hole_indices, cap, allocator) != VECS_OK)\n",
+        "    status = VECS_ERR_OOM;\n",
+        "  if ({gen_method_init}(&self->gens, cap, allocator) != VECS_OK)\n",
+        "    status = VECS_ERR_OOM;\n",
+        "  return status;\n",
         "}}\n",
         "\n",
-        "void {method_add_holes}({self_t} *self, uint32_t len) {{\n",
+        "vecs_status_t {method_add_holes}({self_t} *self, uint32_t len) {{\n",
         "  uint32_t old_len = self->holes.len;\n",
         "  uint32_t new_len = (len / 64) + 1;\n",
         "\n",
         "  for (uint32_t i = old_len; i < new_len; ++i) {{\n",
-        "    {holes_method_push}(&self->holes, 0);\n",
+        "    if ({holes_method_push}(&self->holes, 0, NULL) != VECS_OK)\n",
+        "      return VECS_ERR_OOM;\n",
         "  }}\n",
+        "  return VECS_OK;\n",
         "}}\n",
         "\n",
         "{element_t} *{method_get}({self_t} *self, uint32_t index, uint32_t gen) {{\n",
@@ -165,7 +192,6 @@ impl<'a, T: GenericElement> Display for SparseDynArrayImpl<'a, T> {
         "}}\n",
         "\n",
         "{element_t} *{method_push}({self_t} *self, {element_t} value, uint32_t *index, uint32_t *gen) {{\n",
-        "  self->len += 1;\n",
         "  if (self->hole_indices.len > 0) {{\n",
         "    uint32_t hole_i = {hole_indices_method_dequeue}(&self->hole_indices);\n",
         "    uint32_t hole_bitmap = self->holes.items[hole_i];\n",
@@ -178,25 +204,28 @@ impl<'a, T: GenericElement> Display for SparseDynArrayImpl<'a, T> {
         "    self->gens.items[hole] += 1;\n",
         "\n",
         "    *gen = self->gens.items[hole];\n",
+        "    self->len += 1;\n",
         "    return &self->items.items[hole];\n",
         "  }} else {{\n",
-        "    {element_t} *result;\n",
-        "    {method_add_holes}(self, self->items.len + 1);\n",
+        "    if ({method_add_holes}(self, self->items.len + 1) != VECS_OK)\n",
+        "      return NULL;\n",
         "\n",
+        "    // A gen without an item is fine, so it goes first.\n",
         "    if (self->gens.len == self->items.len) {{\n",
-        "      *index = self->items.len;\n",
-        "      {gen_method_push}(&self->gens, 0);\n",
-        "      {element_method_push}(&self->items, value);\n",
+        "      if ({gen_method_push}(&self->gens, 0, NULL) != VECS_OK)\n",
+        "        return NULL;\n",
+        "      if ({element_method_push}(&self->items, value, index) != VECS_OK)\n",
+        "        return NULL;\n",
         "      *gen = 0;\n",
-        "      result = &self->items.items[*index];\n",
         "    }} else {{\n",
-        "      *index = self->items.len;\n",
-        "      self->gens.items[self->items.len] += 1;\n",
-        "      {element_method_push}(&self->items, value);\n",
-        "      *gen = self->gens.items[self->items.len];\n",
-        "      result = &self->items.items[*index];\n",
+        "      uint32_t new_gen = self->gens.items[self->items.len] + 1;\n",
+        "      if ({element_method_push}(&self->items, value, index) != VECS_OK)\n",
+        "        return NULL;\n",
+        "      self->gens.items[*index] = new_gen;\n",
+        "      *gen = new_gen;\n",
         "    }}\n",
-        "    return result;\n",
+        "    self->len += 1;\n",
+        "    return &self->items.items[*index];\n",
         "  }}\n",
         "}}\n",
         "\n",
@@ -219,7 +248,8 @@ impl<'a, T: GenericElement> Display for SparseDynArrayImpl<'a, T> {
         "    uint32_t hole_i = index / 64;\n",
         "    uint32_t hole_j = index % 64;\n",
         "    self->holes.items[hole_i] |= 1 << hole_j;\n",
-        "    {hole_indices_method_enqueue}(&self->hole_indices, hole_i);\n",
+        "    // If this fails the hole is simply never reused.\n",
+        "    (void){hole_indices_method_enqueue}(&self->hole_indices, hole_i);\n",
         "    *result = self->items.items[index];\n",
         "  }}\n",
         "  self->len -= 1;\n",
@@ -0,0 +1,230 @@
+use std::fmt::Display;
+
+use crate::resolve::cst::Cst;
+
+// The schema as a Graphviz digraph: components, the nodes requiring them, the systems
+// with their events and nodes, and the states with their layers of systems. Every
+// state can transition to every other one, so each pair is linked both ways.
+pub struct Graph<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> Display for Graph<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      concat!(
+        "digraph vecs {{\n",
+        "  rankdir=LR;\n",
+        "  node [fontname=\"monospace\"];\n",
+      ),
+    )?;
+
+    writeln!(f, "\n  // Components:")?;
+    for component in self.data.components.values() {
+      // Markers have no data.
+      let style = if component.is_empty() {
+        "dashed"
+      } else {
+        "solid"
+      };
+
+      writeln!(
+        f,
+        "  \"component:{name}\" [label=\"{name}\", shape=box, style={style}];",
+        name = component.name(),
+        style = style,
+      )?;
+    }
+
+    writeln!(f, "\n  // Events:")?;
+    for event in self.data.events.values() {
+      writeln!(
+        f,
+        "  \"event:{name}\" [label=\"{name}\", shape=diamond];",
+        name = event.name,
+      )?;
+    }
+
+    writeln!(f, "\n  // Nodes:")?;
+    for node in self.data.nodes.values() {
+      writeln!(
+        f,
+        "  \"node:{name}\" [label=\"{name}\", shape=ellipse];",
+        name = node.name,
+      )?;
+
+      for component_name in node.components.iter() {
+        writeln!(
+          f,
+          "  \"node:{}\" -> \"component:{}\";",
+          node.name, component_name
+        )?;
+      }
+    }
+
+    writeln!(f, "\n  // Systems:")?;
+    for system in self.data.systems.values() {
+      writeln!(
+        f,
+        "  \"system:{name}\" [label=\"{name}\", shape=component];",
+        name = system.name,
+      )?;
+      writeln!(
+        f,
+        "  \"event:{}\" -> \"system:{}\";",
+        system.event, system.name
+      )?;
+
+      if let Some(node) = system.node {
+        writeln!(f, "  \"system:{}\" -> \"node:{}\";", system.name, node)?;
+      }
+    }
+
+    writeln!(f, "\n  // States:")?;
+    for state in self.data.states.values() {
+      write!(
+        f,
+        concat!(
+          "  subgraph \"cluster_{name}\" {{\n",
+          "    label=\"state {name}\";\n",
+          "    \"state:{name}\" [label=\"{name}\", shape=doubleoctagon];\n",
+        ),
+        name = state.name,
+      )?;
+
+      // Each layer is a record with one field per system, so edges can start from
+      // the system they stand for.
+      for (i, layer) in state.systems.iter().enumerate() {
+        let fields = layer
+          .iter()
+          .map(|s| format!("<{0}> {0}", s))
+          .collect::<Vec<_>>()
+          .join(" | ");
+
+        writeln!(
+          f,
+          "    \"state:{}:{}\" [label=\"{}\", shape=record];",
+          state.name, i, fields
+        )?;
+
+        if i == 0 {
+          writeln!(
+            f,
+            "    \"state:{}\" -> \"state:{}:0\";",
+            state.name, state.name
+          )?;
+        } else {
+          writeln!(
+            f,
+            "    \"state:{name}:{}\" -> \"state:{name}:{}\";",
+            i - 1,
+            i,
+            name = state.name,
+          )?;
+        }
+      }
+
+      writeln!(f, "  }}")?;
+
+      for (i, layer) in state.systems.iter().enumerate() {
+        for system_name in layer {
+          writeln!(
+            f,
+            "  \"state:{}:{}\":{} -> \"system:{}\" [style=dotted];",
+            state.name, i, system_name, system_name
+          )?;
+        }
+      }
+
+      for node_name in state.nodes.iter() {
+        writeln!(
+          f,
+          "  \"state:{}\" -> \"node:{}\" [style=dotted];",
+          state.name, node_name
+        )?;
+      }
+    }
+
+    writeln!(f, "\n  // Transitions:")?;
+    let states = self.data.states.values().collect::<Vec<_>>();
+    for (i, state) in states.iter().enumerate() {
+      for other_state in states[i + 1..].iter() {
+        writeln!(
+          f,
+          "  \"state:{}\" -> \"state:{}\" [dir=both, style=dashed];",
+          state.name, other_state.name
+        )?;
+      }
+    }
+
+    writeln!(f, "}}")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Graph;
+  use crate::spec::Spec;
+
+  #[test]
+  fn test_graph() {
+    let text = concat!(
+      "component tag;\n",
+      "component pos { float x };\n",
+      "system tick;\n",
+      "system move { pos; tag };\n",
+      "state main { systems { { tick }; { move } } };\n",
+      "state menu { systems {} };\n",
+    );
+    let spec = Spec::new("graph.vecs", text.to_string()).expect("spec error");
+    let cst = spec.resolve().expect("resolve error");
+
+    let expected = concat!(
+      "digraph vecs {\n",
+      "  rankdir=LR;\n",
+      "  node [fontname=\"monospace\"];\n",
+      "\n",
+      "  // Components:\n",
+      "  \"component:tag\" [label=\"tag\", shape=box, style=dashed];\n",
+      "  \"component:pos\" [label=\"pos\", shape=box, style=solid];\n",
+      "\n",
+      "  // Events:\n",
+      "  \"event:frame\" [label=\"frame\", shape=diamond];\n",
+      "\n",
+      "  // Nodes:\n",
+      "  \"node:move\" [label=\"move\", shape=ellipse];\n",
+      "  \"node:move\" -> \"component:pos\";\n",
+      "  \"node:move\" -> \"component:tag\";\n",
+      "\n",
+      "  // Systems:\n",
+      "  \"system:tick\" [label=\"tick\", shape=component];\n",
+      "  \"event:frame\" -> \"system:tick\";\n",
+      "  \"system:move\" [label=\"move\", shape=component];\n",
+      "  \"event:frame\" -> \"system:move\";\n",
+      "  \"system:move\" -> \"node:move\";\n",
+      "\n",
+      "  // States:\n",
+      "  subgraph \"cluster_main\" {\n",
+      "    label=\"state main\";\n",
+      "    \"state:main\" [label=\"main\", shape=doubleoctagon];\n",
+      "    \"state:main:0\" [label=\"<tick> tick\", shape=record];\n",
+      "    \"state:main\" -> \"state:main:0\";\n",
+      "    \"state:main:1\" [label=\"<move> move\", shape=record];\n",
+      "    \"state:main:0\" -> \"state:main:1\";\n",
+      "  }\n",
+      "  \"state:main:0\":tick -> \"system:tick\" [style=dotted];\n",
+      "  \"state:main:1\":move -> \"system:move\" [style=dotted];\n",
+      "  \"state:main\" -> \"node:move\" [style=dotted];\n",
+      "  subgraph \"cluster_menu\" {\n",
+      "    label=\"state menu\";\n",
+      "    \"state:menu\" [label=\"menu\", shape=doubleoctagon];\n",
+      "  }\n",
+      "\n",
+      "  // Transitions:\n",
+      "  \"state:main\" -> \"state:menu\" [dir=both, style=dashed];\n",
+      "}\n",
+    );
+    assert_eq!(Graph { data: &cst }.to_string(), expected);
+  }
+}
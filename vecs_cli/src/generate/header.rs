@@ -4,15 +4,18 @@ use crate::{
   generate::{
     common::{
       ComponentOpAddStructName, ComponentOpAddTmpStructName,
-      ComponentOpUpdateStructName, ComponentTmpOps,
+      ComponentOpUpdateStructName, ComponentTmpOps, DocComment,
     },
+    dump::DumpJsonHeader,
     generics::skip_lists::SkipList,
+    profile::{ProfileEngineField, ProfileHeader, ProfileMethodsHeader},
+    reflection::ReflectionHeader,
   },
-  resolve::cst::Cst,
+  resolve::cst::{Cst, TypeName},
 };
 
 use super::{
-  common::{ComponentStructName, EventStructName, NodeStructName},
+  common::{ComponentStructName, EngineStructName, EventStructName, NodeStructName},
   constants::{
     ComponentMask, ComponentMaskName, NodeMask, NodeMaskName, StateIdName,
   },
@@ -22,6 +25,9 @@ use super::{
   },
 };
 
+// `include/vecs.h`, inlined in single headers.
+pub const RUNTIME_HEADER: &str = include_str!("../../../include/vecs.h");
+
 fn write_iterator<W: std::fmt::Write, I: Iterator<Item = T>, T: Display>(
   w: &mut W,
   mut iter: I,
@@ -42,69 +48,89 @@ fn write_iterator<W: std::fmt::Write, I: Iterator<Item = T>, T: Display>(
   return Ok(());
 }
 
+// Writes the C type of a component or event (without the name of the typedef).
+fn write_type<W: std::fmt::Write>(w: &mut W, typ: &TypeName) -> std::fmt::Result {
+  if !typ.fields.is_empty() {
+    writeln!(w, "struct {{")?;
+
+    for field in typ.fields.iter() {
+      write!(w, "  ")?;
+      write_iterator(w, field.type_components.iter())?;
+      writeln!(w, " {};", field.name)?;
+    }
+
+    write!(w, "}}")
+  } else if !typ.type_components.is_empty() {
+    write_iterator(w, typ.type_components.iter())
+  } else {
+    write!(w, "struct {{}}")
+  }
+}
+
 pub struct Header<'a> {
   pub data: &'a Cst<'a>,
 }
 
 impl<'a> Display for Header<'a> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
     write!(
       f,
       concat!(
-        "#ifndef VECS_ECS_H\n",
-        "#define VECS_ECS_H\n",
+        "#ifndef {PREFIX}_ECS_H\n",
+        "#define {PREFIX}_ECS_H\n",
         "\n",
         "#include <stdbool.h>\n",
         "#include <stddef.h>\n",
         "#include <stdint.h>\n",
-        "#include <vecs.h>\n",
-        "\n",
-      )
+        "#include <stdio.h>\n",
+      ),
+      PREFIX = prefix.upper,
     )?;
 
+    if self.data.settings.single_header {
+      writeln!(f, "\n{}", RUNTIME_HEADER)?;
+    } else {
+      writeln!(f, "#include <vecs.h>\n")?;
+    }
+
     for include in self.data.includes.iter() {
-      write!(f, "#include {}\n", include)?;
+      writeln!(f, "#include {}", include.path)?;
     }
     write!(f, "\n")?;
 
-    write!(
-      f,
-      concat!(
-        "// TODO: Remove this.\n",
-        "typedef struct vecs_frame {{\n",
-        "  float delta;\n",
-        "  double runtime;\n",
-        "  uint64_t frame;\n",
-        "}} vecs_frame_t;\n",
-      ),
-    )?;
+    // For the C++ wrapper.
+    writeln!(f, "#ifdef __cplusplus\nextern \"C\" {{\n#endif\n")?;
 
     // Used in every SparseDynArray.
-    DynArray::new("uint32_t").header().fmt(f)?;
-    DynArray::new("uint64_t").header().fmt(f)?;
-    DynQueue::new("uint32_t").header().fmt(f)?;
+    DynArray::new(prefix, "uint32_t").header().fmt(f)?;
+    DynArray::new(prefix, "uint64_t").header().fmt(f)?;
+    DynQueue::new(prefix, "uint32_t").header().fmt(f)?;
 
     for event in self.data.events.values() {
       // Event struct:
-      let event_t = EventStructName::new(event.name);
+      let event_t = EventStructName::new(prefix, event.name);
 
-      write!(f, "// Event `{}`.\ntypedef ", event.name,)?;
-
-      if !event.is_empty() {
-        let event_type_name_iter = event.type_components.iter();
-        write_iterator(f, event_type_name_iter)?;
-      } else {
-        write!(f, "struct {{}}")?;
-      }
+      write!(
+        f,
+        "// Event `{}`.\n{}typedef ",
+        event.name,
+        DocComment {
+          lines: &event.doc,
+          indent: 0
+        },
+      )?;
+      write_type(f, event)?;
 
       write!(f, " {};\n\n", event_t)?;
 
       // Event queue:
-      DynQueue::new(event_t).header().fmt(f)?;
+      DynQueue::new(prefix, event_t).header().fmt(f)?;
     }
 
     // Used to access things from other things faster (index):
-    let index_index = SkipList::new("vecs_id_t", "uint32_t");
+    let index_index = SkipList::new(prefix, "vecs_id_t", "uint32_t");
 
     let index_index_t = index_index.get_type();
     index_index.header().fmt(f)?;
@@ -112,39 +138,42 @@ impl<'a> Display for Header<'a> {
     write!(
       f,
       concat!(
-        "typedef struct vecs_op_store_entity {{\n",
+        "typedef struct {prefix}_op_store_entity {{\n",
         "  vecs_tmp_id_t tmp_entity;\n",
         "  ptrdiff_t location_offset;\n",
-        "}} vecs_op_store_entity_t;\n",
-        "typedef struct vecs_op_store_component {{\n",
+        "}} {prefix}_op_store_entity_t;\n",
+        "typedef struct {prefix}_op_store_component {{\n",
         "  vecs_tmp_id_t tmp_component;\n",
         "  ptrdiff_t location_offset;\n",
-        "}} vecs_op_store_component_t;\n",
-        "typedef struct vecs_op_enable_component {{\n",
+        "}} {prefix}_op_store_component_t;\n",
+        "typedef struct {prefix}_op_enable_component {{\n",
         "  vecs_id_t entity;\n",
-        "}} vecs_op_enable_component_t;\n",
-        "typedef struct vecs_op_remove_component {{\n",
+        "}} {prefix}_op_enable_component_t;\n",
+        "typedef struct {prefix}_op_remove_component {{\n",
         "  vecs_id_t entity;\n",
-        "}} vecs_op_remove_component_t;\n",
-        "typedef struct vecs_op_disable_component {{\n",
+        "}} {prefix}_op_remove_component_t;\n",
+        "typedef struct {prefix}_op_disable_component {{\n",
         "  vecs_id_t entity;\n",
-        "}} vecs_op_disable_component_t;\n\n",
+        "}} {prefix}_op_disable_component_t;\n\n",
       ),
+      prefix = prefix.lower,
     )?;
 
     for component in self.data.components.values() {
       // Component struct:
       let component_name = component.name();
-      let component_t = ComponentStructName::new(component_name);
-
-      write!(f, "// Component `{}`.\ntypedef ", component_name)?;
+      let component_t = ComponentStructName::new(prefix, component_name);
 
-      if !component.is_empty() {
-        let component_type_name_iter = component.typ.type_components.iter();
-        write_iterator(f, component_type_name_iter)?;
-      } else {
-        write!(f, "struct {{}}")?;
-      }
+      write!(
+        f,
+        "// Component `{}`.\n{}typedef ",
+        component_name,
+        DocComment {
+          lines: &component.typ.doc,
+          indent: 0
+        },
+      )?;
+      write_type(f, &component.typ)?;
 
       write!(f, " {};\n\n", component_t)?;
 
@@ -152,33 +181,35 @@ impl<'a> Display for Header<'a> {
       write!(
         f,
         "static const uint64_t {}[{}] = {};\n\n",
-        ComponentMaskName::new(component_name),
+        ComponentMaskName::new(prefix, component_name),
         self.data.node_mask_arr_size,
         ComponentMask::from_component(component, self.data.node_mask_arr_size),
       )?;
 
       if !component.is_empty() {
         // Component sparse array:
-        DynArray::new(component_t.clone()).header().fmt(f)?;
-        SparseDynArray::new(component_t.clone()).header().fmt(f)?;
+        DynArray::new(prefix, component_t.clone()).header().fmt(f)?;
+        SparseDynArray::new(prefix, component_t.clone())
+          .header()
+          .fmt(f)?;
       }
 
       // Temporary component operations:
-      let ops = ComponentTmpOps::new(component_name);
+      let ops = ComponentTmpOps::new(prefix, component_name);
 
       if !component.is_empty() {
         write!(
           f,
           concat!(
-            "typedef struct vecs_op_add_component_{component_name} {{\n",
+            "typedef struct {prefix}_op_add_component_{component_name} {{\n",
             "  vecs_id_t entity;\n",
             "  {component_t} component;\n",
             "}} {component_add_t};\n",
-            "typedef struct vecs_op_tmp_add_component_{component_name} {{\n",
+            "typedef struct {prefix}_op_tmp_add_component_{component_name} {{\n",
             "  vecs_tmp_id_t tmp_entity;\n",
             "  {component_t} component;\n",
             "}} {component_add_tmp_t};\n",
-            "typedef struct vecs_op_update_component_{component_name} {{\n",
+            "typedef struct {prefix}_op_update_component_{component_name} {{\n",
             "  vecs_id_t entity;\n",
             "  {component_t} component;\n",
             "}} {component_update_t};\n",
@@ -188,36 +219,38 @@ impl<'a> Display for Header<'a> {
           component_add_t = ops.add_t,
           component_add_tmp_t = ops.add_tmp_t,
           component_update_t = ops.update_t,
+          prefix = prefix.lower,
         )?;
       } else {
         write!(
           f,
           concat!(
-            "typedef struct vecs_op_add_component_{component_name} {{\n",
+            "typedef struct {prefix}_op_add_component_{component_name} {{\n",
             "  vecs_id_t entity;\n",
             "}} {component_add_t};\n",
-            "typedef struct vecs_op_tmp_add_component_{component_name} {{\n",
+            "typedef struct {prefix}_op_tmp_add_component_{component_name} {{\n",
             "  vecs_tmp_id_t tmp_entity;\n",
             "}} {component_add_tmp_t};\n",
           ),
           component_name = component_name,
           component_add_t = ops.add_t,
           component_add_tmp_t = ops.add_tmp_t,
+          prefix = prefix.lower,
         )?;
       }
     }
 
     // State enum:
-    write!(f, "typedef enum vecs_state {{\n",)?;
-    write!(f, "  VECS_STATE_NONE,\n",)?;
+    writeln!(f, "typedef enum {prefix}_state {{", prefix = prefix.lower)?;
+    writeln!(f, "  {PREFIX}_STATE_NONE,", PREFIX = prefix.upper)?;
     for state in self.data.states.values() {
-      let state_id = StateIdName::new(state.name);
+      let state_id = StateIdName::new(prefix, state.name);
       write!(f, "  {},\n", state_id)?;
     }
-    write!(f, "}} vecs_state_t;\n\n",)?;
+    write!(f, "}} {prefix}_state_t;\n\n", prefix = prefix.lower)?;
 
     // Forward declare the engine struct so the operations can refer to it
-    write!(f, "struct vecs_engine;\n\n",)?;
+    write!(f, "struct {prefix}_engine;\n\n", prefix = prefix.lower)?;
 
     // Deferred operation structs:
 
@@ -225,16 +258,17 @@ impl<'a> Display for Header<'a> {
     write!(
       f,
       concat!(
-        "typedef struct vecs_op_union_add_component {{\n",
-        "  vecs_id_t (*apply)(struct vecs_engine *, vecs_id_t *new_entities, struct vecs_op_union_add_component);\n",
+        "typedef struct {prefix}_op_union_add_component {{\n",
+        "  vecs_status_t (*apply)(struct {prefix}_engine *, vecs_id_t *new_entities, struct {prefix}_op_union_add_component, vecs_id_t *result);\n",
         "  union {{\n",
-      )
+      ),
+      prefix = prefix.lower,
     )?;
 
     for component in self.data.components.values() {
       let component_name = component.name();
-      let add_t = ComponentOpAddStructName::new(component_name);
-      let add_tmp_t = ComponentOpAddTmpStructName::new(component_name);
+      let add_t = ComponentOpAddStructName::new(prefix, component_name);
+      let add_tmp_t = ComponentOpAddTmpStructName::new(prefix, component_name);
 
       write!(
         f,
@@ -249,7 +283,8 @@ impl<'a> Display for Header<'a> {
     }
     write!(
       f,
-      concat!("  }};\n", "}} vecs_op_union_add_component_t;\n\n")
+      concat!("  }};\n", "}} {prefix}_op_union_add_component_t;\n\n"),
+      prefix = prefix.lower,
     )?;
 
     // These operations should be applied after start and before end, in no specified
@@ -257,21 +292,22 @@ impl<'a> Display for Header<'a> {
     write!(
       f,
       concat!(
-        "typedef struct vecs_op_union_other {{\n",
-        "  void (*apply)(struct vecs_engine *, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, struct vecs_op_union_other);\n",
+        "typedef struct {prefix}_op_union_other {{\n",
+        "  vecs_status_t (*apply)(struct {prefix}_engine *, vecs_id_t *new_entities, vecs_id_t *new_components, struct {prefix}_op_union_other);\n",
         "  union {{\n",
-        "    vecs_op_store_entity_t store_entity;\n",
-        "    vecs_op_store_component_t store_component;\n",
-        "    vecs_op_enable_component_t enable;\n",
-        "    vecs_op_disable_component_t disable;\n",
-      )
+        "    {prefix}_op_store_entity_t store_entity;\n",
+        "    {prefix}_op_store_component_t store_component;\n",
+        "    {prefix}_op_enable_component_t enable;\n",
+        "    {prefix}_op_disable_component_t disable;\n",
+      ),
+      prefix = prefix.lower,
     )?;
 
     for component in self.data.components.values() {
       let component_name = component.name();
 
       if !component.is_empty() {
-        let update_t = ComponentOpUpdateStructName::new(component_name);
+        let update_t = ComponentOpUpdateStructName::new(prefix, component_name);
 
         write!(
           f,
@@ -281,40 +317,59 @@ impl<'a> Display for Header<'a> {
         )?;
       }
     }
-    write!(f, concat!("  }};\n", "}} vecs_op_union_other_t;\n\n"))?;
+    write!(
+      f,
+      concat!("  }};\n", "}} {prefix}_op_union_other_t;\n\n"),
+      prefix = prefix.lower,
+    )?;
 
     // These operations should be applied last
     write!(
       f,
       concat!(
-        "typedef struct vecs_op_union_remove_component {{\n",
-        "  void (*apply)(struct vecs_engine *, struct vecs_op_union_remove_component);\n",
+        "typedef struct {prefix}_op_union_remove_component {{\n",
+        "  void (*apply)(struct {prefix}_engine *, struct {prefix}_op_union_remove_component);\n",
         "  union {{\n",
-        "    vecs_op_remove_component_t remove;\n",
+        "    {prefix}_op_remove_component_t remove;\n",
         "  }};\n",
-        "}} vecs_op_union_remove_component_t;\n\n"
-      )
+        "}} {prefix}_op_union_remove_component_t;\n\n"
+      ),
+      prefix = prefix.lower,
     )?;
 
-    let op_add_component_queue = DynQueue::new("vecs_op_union_add_component_t");
+    let op_add_component_queue = DynQueue::new(
+      prefix,
+      EngineStructName::new(prefix, "op_union_add_component"),
+    );
     let op_add_component_queue_t = op_add_component_queue.get_type();
     op_add_component_queue.header().fmt(f)?;
 
-    let op_other_queue = DynQueue::new("vecs_op_union_other_t");
+    let op_other_queue =
+      DynQueue::new(prefix, EngineStructName::new(prefix, "op_union_other"));
     let op_other_queue_t = op_other_queue.get_type();
     op_other_queue.header().fmt(f)?;
 
-    let op_remove_component_queue = DynQueue::new("vecs_op_union_remove_component_t");
+    let op_remove_component_queue = DynQueue::new(
+      prefix,
+      EngineStructName::new(prefix, "op_union_remove_component"),
+    );
     let op_remove_component_queue_t = op_remove_component_queue.get_type();
     op_remove_component_queue.header().fmt(f)?;
 
     for node in self.data.nodes.values() {
       // Node struct:
-      let node_t = NodeStructName::new(node.name);
+      let node_t = NodeStructName::new(prefix, node.name);
 
       // TODO: Add entity ID
-      write!(f, "// Node `{}`.\n\n", node.name)?;
-      write!(f, "typedef struct {{\n",)?;
+      write!(
+        f,
+        "// Node `{}`.\n\n{}typedef struct {{\n",
+        node.name,
+        DocComment {
+          lines: &node.doc,
+          indent: 0
+        },
+      )?;
 
       for component_name in node.components.iter() {
         let component = self
@@ -333,28 +388,30 @@ impl<'a> Display for Header<'a> {
       write!(
         f,
         "static const uint64_t {}[{}] = {};\n\n",
-        NodeMaskName::new(node.name),
+        NodeMaskName::new(prefix, node.name),
         self.data.node_mask_arr_size,
         NodeMask::from_node(node, self.data.node_mask_arr_size),
       )?;
 
       // Array of node:
-      DynArray::new(node_t).header().fmt(f)?;
+      DynArray::new(prefix, node_t).header().fmt(f)?;
     }
 
     // Entity struct:
     write!(
       f,
       concat!(
-        "typedef struct vecs_entity {{\n",
+        "typedef struct {prefix}_entity {{\n",
         "  uint64_t mask[{mask_size}];\n",
-        "}} vecs_entity_t;\n",
+        "}} {prefix}_entity_t;\n",
       ),
       mask_size = self.data.node_mask_arr_size,
+      prefix = prefix.lower,
     )?;
 
-    DynArray::new("vecs_entity_t").header().fmt(f)?;
-    let entity_array = SparseDynArray::new("vecs_entity_t");
+    let entity_t = EngineStructName::new(prefix, "entity");
+    DynArray::new(prefix, entity_t.clone()).header().fmt(f)?;
+    let entity_array = SparseDynArray::new(prefix, entity_t);
     let entity_array_t = entity_array.get_type();
 
     entity_array.header().fmt(f)?;
@@ -363,17 +420,29 @@ impl<'a> Display for Header<'a> {
 
     write!(
       f,
-      concat!("// Globals.\n", "typedef struct vecs_globals {{\n",),
+      concat!("// Globals.\n", "typedef struct {prefix}_globals {{\n",),
+      prefix = prefix.lower,
     )?;
 
     for global in self.data.globals.values() {
-      write!(f, "  ")?;
+      write!(
+        f,
+        "{}  ",
+        DocComment {
+          lines: &global.doc,
+          indent: 2
+        }
+      )?;
       let event_type_name_iter = global.type_components.iter();
       write_iterator(f, event_type_name_iter)?;
       write!(f, " {};\n", global.name)?;
     }
 
-    write!(f, "}} vecs_globals_t;\n\n")?;
+    write!(f, "}} {prefix}_globals_t;\n\n", prefix = prefix.lower)?;
+
+    if self.data.settings.profile {
+      ProfileHeader { data: self.data }.fmt(f)?;
+    }
 
     // Main "engine" struct:
 
@@ -381,13 +450,16 @@ impl<'a> Display for Header<'a> {
       f,
       concat!(
         "// Engine.\n",
-        "typedef struct vecs_engine {{\n",
-        "  vecs_state_t state;\n",
-        "  vecs_globals_t globals;\n",
+        "typedef struct {prefix}_engine {{\n",
+        "  {prefix}_state_t state;\n",
+        "  {prefix}_globals_t globals;\n",
+        "\n",
+        "  // Every container points to this\n",
+        "  vecs_allocator_t allocator;\n",
         "\n",
         "  // Deferred operations\n",
         "  uint32_t entities_to_add;\n",
-        "  vecs_state_t next_state;\n",
+        "  {prefix}_state_t next_state;\n",
         "  {op_add_component_queue_t} ops_add_component;\n",
         "  {op_remove_component_queue_t} ops_remove_component;\n",
         "  {op_other_queue_t} ops_other;\n",
@@ -403,14 +475,15 @@ impl<'a> Display for Header<'a> {
       op_add_component_queue_t = op_add_component_queue_t,
       op_other_queue_t = op_other_queue_t,
       op_remove_component_queue_t = op_remove_component_queue_t,
+      prefix = prefix.lower,
     )?;
 
     for component in self.data.components.values() {
       if !component.is_empty() {
         let component_name = component.name();
-        let component_t = ComponentStructName::new(component_name);
+        let component_t = ComponentStructName::new(prefix, component_name);
 
-        let dyn_array = SparseDynArray::new(component_t.clone());
+        let dyn_array = SparseDynArray::new(prefix, component_t.clone());
         let dyn_array_t = dyn_array.get_type();
 
         write!(f, "  {} components_{};\n", dyn_array_t, component_name)?;
@@ -423,8 +496,8 @@ impl<'a> Display for Header<'a> {
     }
 
     for node in self.data.nodes.values() {
-      let node_t = NodeStructName::new(node.name);
-      let dyn_array = DynArray::new(node_t);
+      let node_t = NodeStructName::new(prefix, node.name);
+      let dyn_array = DynArray::new(prefix, node_t);
       let dyn_array_t = dyn_array.get_type();
 
       write!(f, "  {} nodes_{};\n", dyn_array_t, node.name)?;
@@ -432,18 +505,22 @@ impl<'a> Display for Header<'a> {
     }
 
     for event in self.data.events.values() {
-      let event_t = EventStructName::new(event.name);
-      let dyn_queue = DynQueue::new(event_t);
+      let event_t = EventStructName::new(prefix, event.name);
+      let dyn_queue = DynQueue::new(prefix, event_t);
       let dyn_queue_t = dyn_queue.get_type();
 
       write!(f, "  {} events_{};\n", dyn_queue_t, event.name)?;
     }
 
-    write!(f, "}} vecs_engine_t;\n\n")?;
+    if self.data.settings.profile {
+      ProfileEngineField { data: self.data }.fmt(f)?;
+    }
+
+    write!(f, "}} {prefix}_engine_t;\n\n", prefix = prefix.lower)?;
 
     // Component getters:
     for node in self.data.nodes.values() {
-      let node_t = NodeStructName::new(node.name);
+      let node_t = NodeStructName::new(prefix, node.name);
 
       for component_name in node.components.iter() {
         let component = self
@@ -453,14 +530,14 @@ impl<'a> Display for Header<'a> {
           .expect("component not found");
 
         if !component.is_empty() {
-          let component_t = ComponentStructName::new(component_name);
-          let component_array = SparseDynArray::new(component_t.clone());
+          let component_t = ComponentStructName::new(prefix, component_name);
+          let component_array = SparseDynArray::new(prefix, component_t.clone());
           let component_array_t = component_array.get_type();
 
           write!(
             f,
             concat!(
-              "static inline {component_t} *vecs_node_{node_name}_get_{component_name}(vecs_engine_t *e, {node_t} node) {{\n",
+              "static inline {component_t} *{prefix}_node_{node_name}_get_{component_name}({prefix}_engine_t *e, {node_t} node) {{\n",
               "  return {component_array_method_get_unchecked}(&e->components_{component_name}, node.{component_name}_index);\n",
               "}}\n",
             ),
@@ -470,6 +547,7 @@ impl<'a> Display for Header<'a> {
             node_t = node_t,
             component_array_method_get_unchecked =
               method_name!(&component_array_t, "get_unchecked"),
+            prefix = prefix.lower,
           )?;
         }
       }
@@ -477,30 +555,42 @@ impl<'a> Display for Header<'a> {
 
     for system in self.data.systems.values() {
       // System function:
-      let event_t = EventStructName::new(system.event);
+      let event_t = EventStructName::new(prefix, system.event);
 
       if let Some(node) = system.node {
-        let node_t = NodeStructName::new(node);
+        let node_t = NodeStructName::new(prefix, node);
 
         write!(
           f,
           concat!(
             "// System `{system_name}`.\n",
-            "void {system_name}(vecs_engine_t *engine, {node_t} node, {event_t} event);\n"
+            "{doc}",
+            "void {system_name}({prefix}_engine_t *engine, {node_t} node, {event_t} event);\n"
           ),
           system_name = system.name,
+          doc = DocComment {
+            lines: &system.doc,
+            indent: 0
+          },
           node_t = node_t,
           event_t = event_t,
+          prefix = prefix.lower,
         )?;
       } else {
         write!(
           f,
           concat!(
             "// System singleton `{system_name}`.\n",
-            "void {system_name}(vecs_engine_t *engine, {event_t} event);\n"
+            "{doc}",
+            "void {system_name}({prefix}_engine_t *engine, {event_t} event);\n"
           ),
           system_name = system.name,
+          doc = DocComment {
+            lines: &system.doc,
+            indent: 0
+          },
           event_t = event_t,
+          prefix = prefix.lower,
         )?;
       }
     }
@@ -510,55 +600,62 @@ impl<'a> Display for Header<'a> {
     write!(
       f,
       concat!(
-        "void vecs_init(vecs_engine_t *e);\n",
-        "void vecs_destroy(vecs_engine_t *e);\n",
-        "vecs_id_t vecs_add_entity(vecs_engine_t *e);\n",
-        "vecs_tmp_id_t vecs_schedule_add_entity(vecs_engine_t *e);\n",
+        "// `allocator` is copied into the engine. If NULL, the standard library is used.\n",
+        "// The engine must not be moved after this. On failure there is nothing to destroy.\n",
+        "vecs_status_t {prefix}_init({prefix}_engine_t *e, const vecs_allocator_t *allocator);\n",
+        "void {prefix}_destroy({prefix}_engine_t *e);\n",
+        "vecs_status_t {prefix}_add_entity({prefix}_engine_t *e, vecs_id_t *entity);\n",
+        "vecs_tmp_id_t {prefix}_schedule_add_entity({prefix}_engine_t *e);\n",
       ),
+      prefix = prefix.lower,
     )?;
 
     // Component manipulation:
     for component in self.data.components.values() {
       let component_name = component.name();
-      let component_t = ComponentStructName::new(component_name);
+      let component_t = ComponentStructName::new(prefix, component_name);
 
       for state in self.data.states.values() {
         // Immediate methods
         write!(
           f,
           concat!(
-            "bool vecs_has_component_{component_name}(vecs_engine_t *e, vecs_id_t entity);\n",
-            "void vecs_{state_name}_disable_component_{component_name}(vecs_engine_t *e, vecs_id_t entity);\n",
-            "void vecs_{state_name}_enable_component_{component_name}(vecs_engine_t *e, vecs_id_t entity);\n",
+            "bool {prefix}_has_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity);\n",
+            "void {prefix}_{state_name}_disable_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity);\n",
+            "vecs_status_t {prefix}_{state_name}_enable_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity);\n",
           ),
           state_name = state.name,
           component_name = component_name,
+          prefix = prefix.lower,
         )?;
 
         if !component.is_empty() {
           write!(
             f,
             concat!(
-              "{component_t} *vecs_get_component_{component_name}(vecs_engine_t *e, vecs_id_t component_id);\n",
-              "vecs_id_t vecs_{state_name}_add_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component);\n",
-              "vecs_id_t vecs_{state_name}_upsert_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component);\n",
-              "vecs_id_t vecs_{state_name}_update_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component);\n",
-              "bool vecs_{state_name}_remove_component_{component_name}(vecs_engine_t *e, vecs_id_t entity);\n",
+              "{component_t} *{prefix}_get_component_{component_name}({prefix}_engine_t *e, vecs_id_t component_id);\n",
+              "// `add` and `upsert` return `vecs_id_invalid` if out of memory.\n",
+              "vecs_id_t {prefix}_{state_name}_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component);\n",
+              "vecs_id_t {prefix}_{state_name}_upsert_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component);\n",
+              "vecs_id_t {prefix}_{state_name}_update_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component);\n",
+              "bool {prefix}_{state_name}_remove_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity);\n",
             ),
             state_name = state.name,
             component_name = component_name,
             component_t = component_t,
+            prefix = prefix.lower,
           )?;
         } else {
           // Component is empty
           write!(
             f,
             concat!(
-              "void vecs_{state_name}_add_component_{component_name}(vecs_engine_t *e, vecs_id_t entity);\n",
-              "void vecs_{state_name}_remove_component_{component_name}(vecs_engine_t *e, vecs_id_t entity);\n",
+              "vecs_status_t {prefix}_{state_name}_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity);\n",
+              "void {prefix}_{state_name}_remove_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity);\n",
             ),
             state_name = state.name,
             component_name = component_name,
+            prefix = prefix.lower,
           )?;
         }
       }
@@ -567,64 +664,76 @@ impl<'a> Display for Header<'a> {
       write!(
         f,
         concat!(
-          "void vecs_schedule_store_entity_in_{component_name}(vecs_engine_t *e, vecs_tmp_id_t tmp_entity, vecs_id_t *location);\n",
-          "void vecs_schedule_store_component_{component_name}(vecs_engine_t *e, vecs_tmp_id_t tmp_component, vecs_id_t *location);\n",
-          "void vecs_schedule_remove_component_{component_name}(vecs_engine_t *e, vecs_id_t entity);\n",
-          "void vecs_schedule_disable_component_{component_name}(vecs_engine_t *e, vecs_id_t entity);\n",
-          "void vecs_schedule_enable_component_{component_name}(vecs_engine_t *e, vecs_id_t entity);\n",
+          "vecs_status_t {prefix}_schedule_store_entity_in_{component_name}({prefix}_engine_t *e, vecs_tmp_id_t tmp_entity, vecs_id_t *location);\n",
+          "vecs_status_t {prefix}_schedule_store_component_{component_name}({prefix}_engine_t *e, vecs_tmp_id_t tmp_component, vecs_id_t *location);\n",
+          "vecs_status_t {prefix}_schedule_remove_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity);\n",
+          "vecs_status_t {prefix}_schedule_disable_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity);\n",
+          "vecs_status_t {prefix}_schedule_enable_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity);\n",
         ),
         component_name = component_name,
+        prefix = prefix.lower,
       )?;
 
       if !component.is_empty() {
         write!(
           f,
           concat!(
-            "vecs_tmp_id_t vecs_schedule_add_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component);\n",
-            "vecs_tmp_id_t vecs_schedule_tmp_add_component_{component_name}(vecs_engine_t *e, vecs_tmp_id_t entity, {component_t} component);\n",
-            "void vecs_schedule_upsert_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component);\n",
-            "void vecs_schedule_update_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component);\n",
+            "// These return `vecs_tmp_id_invalid` if out of memory.\n",
+            "vecs_tmp_id_t {prefix}_schedule_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component);\n",
+            "vecs_tmp_id_t {prefix}_schedule_tmp_add_component_{component_name}({prefix}_engine_t *e, vecs_tmp_id_t entity, {component_t} component);\n",
+            "vecs_status_t {prefix}_schedule_upsert_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component);\n",
+            "vecs_status_t {prefix}_schedule_update_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component);\n",
           ),
           component_name = component_name,
           component_t = component_t,
+          prefix = prefix.lower,
         )?;
       } else {
         // Component is empty
         write!(
           f,
           concat!(
-            "void vecs_schedule_add_component_{component_name}(vecs_engine_t *e, vecs_id_t entity);\n",
-            "void vecs_schedule_tmp_add_component_{component_name}(vecs_engine_t *e, vecs_tmp_id_t entity);\n",
+            "vecs_status_t {prefix}_schedule_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity);\n",
+            "vecs_status_t {prefix}_schedule_tmp_add_component_{component_name}({prefix}_engine_t *e, vecs_tmp_id_t entity);\n",
           ),
           component_name = component_name,
+          prefix = prefix.lower,
         )?;
       }
     }
 
     // Node getters:
     for node in self.data.nodes.values() {
-      let node_t = NodeStructName::new(node.name);
-      let dyn_arr = DynArray::new(node_t);
+      let node_t = NodeStructName::new(prefix, node.name);
+      let dyn_arr = DynArray::new(prefix, node_t);
       let dyn_arr_t = dyn_arr.get_type();
 
       write!(
         f,
-        "typedef {} vecs_node_{}_array_t;\n",
-        dyn_arr_t, node.name
+        "typedef {} {prefix}_node_{}_array_t;\n",
+        dyn_arr_t,
+        node.name,
+        prefix = prefix.lower,
       )?;
 
       write!(
         f,
-        "vecs_node_{node_name}_array_t vecs_nodes_{node_name}(vecs_engine_t *e);\n",
+        "{prefix}_node_{node_name}_array_t {prefix}_nodes_{node_name}({prefix}_engine_t *e);\n",
         node_name = node.name,
+        prefix = prefix.lower,
       )?;
     }
 
     for state in self.data.states.values() {
       write!(
         f,
-        "void vecs_schedule_state_to_{}(vecs_engine_t *e);\n",
+        "{}void {prefix}_schedule_state_to_{}({prefix}_engine_t *e);\n",
+        DocComment {
+          lines: &state.doc,
+          indent: 0
+        },
         state.name,
+        prefix = prefix.lower,
       )?;
 
       // State transitions:
@@ -635,31 +744,104 @@ impl<'a> Display for Header<'a> {
 
         write!(
           f,
-          "void vecs_state_{}_to_{}(vecs_engine_t *e);\n",
-          other_state.name, state.name,
+          "vecs_status_t {prefix}_state_{}_to_{}({prefix}_engine_t *e);\n",
+          other_state.name,
+          state.name,
+          prefix = prefix.lower,
         )?;
       }
 
       // State loops:
       write!(
         f,
-        "void vecs_run_state_{}(vecs_engine_t *e);\n\n",
+        concat!(
+          "// Pending events are processed in declaration order, starting with `frame`.\n",
+          "// Each event is handled by the state's systems in the order they are listed.\n",
+          "// If this fails, the deferred operations of the run may have been only\n",
+          "// partially applied. The rest are dropped.\n",
+          "vecs_status_t {prefix}_run_state_{}({prefix}_engine_t *e);\n\n",
+        ),
         state.name,
+        prefix = prefix.lower,
       )?;
     }
 
     // Event emition:
     for event in self.data.events.values() {
-      let event_t = EventStructName::new(event.name);
+      let event_t = EventStructName::new(prefix, event.name);
       write!(
         f,
-        "void vecs_emit_{}(vecs_engine_t *e, {} ev);\n",
-        event.name, event_t,
+        "vecs_status_t {prefix}_emit_{}({prefix}_engine_t *e, {} ev);\n",
+        event.name,
+        event_t,
+        prefix = prefix.lower,
       )?;
     }
 
-    write!(f, "\n#endif // !VECS_ECS_H\n")?;
+    writeln!(f)?;
+    ReflectionHeader { data: self.data }.fmt(f)?;
+
+    if self.data.settings.profile {
+      writeln!(f)?;
+      ProfileMethodsHeader { data: self.data }.fmt(f)?;
+    }
+
+    // Debugging:
+    DumpJsonHeader { data: self.data }.fmt(f)?;
+
+    writeln!(f, "\n#ifdef __cplusplus\n}}\n#endif")?;
+    write!(f, "\n#endif // !{PREFIX}_ECS_H\n", PREFIX = prefix.upper)?;
 
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::{fs, process::Command};
+
+  use crate::{
+    generate::{RUNTIME_HEADER, generate_header},
+    spec::Spec,
+  };
+
+  #[test]
+  fn test_doc_comments_compile() {
+    let text = concat!(
+      "/// Position, see C:\\\n",
+      "component pos float;\n",
+      "/// Moves, see D:\\ \n",
+      "node moving { pos };\n",
+    );
+
+    let spec = Spec::new("doc.vecs", text.to_string()).expect("spec error");
+    let cst = spec.resolve().expect("resolve error");
+    let mut header = Vec::new();
+    generate_header(&cst, &mut header).expect("generate error");
+
+    let header = String::from_utf8(header).expect("header is not UTF-8");
+    assert!(header.contains("// Position, see C:\ntypedef float"));
+    assert!(header.contains("// Moves, see D:\ntypedef struct {"));
+
+    let dir =
+      std::env::temp_dir().join(format!("vecs_doc_test_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("failed to create the test directory");
+    fs::write(dir.join("vecs.h"), RUNTIME_HEADER).expect("write error");
+    fs::write(dir.join("doc.h"), &header).expect("write error");
+    fs::write(
+      dir.join("doc.c"),
+      "#include \"doc.h\"\nvecs_component_pos_t p;\nvecs_node_moving_t n;\n",
+    )
+    .expect("write error");
+
+    let status = Command::new(std::env::var("CC").unwrap_or("cc".to_string()))
+      .args(["-fsyntax-only", "-I"])
+      .arg(&dir)
+      .arg(dir.join("doc.c"))
+      .status()
+      .expect("failed to run the C compiler");
+
+    fs::remove_dir_all(&dir).expect("failed to remove the test directory");
+    assert!(status.success());
+  }
+}
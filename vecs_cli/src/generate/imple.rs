@@ -1,16 +1,24 @@
-use std::{collections::HashSet, fmt::Display};
+use std::fmt::Display;
+
+use indexmap::IndexSet;
 
 use crate::{
   generate::{
     common::ComponentTmpOps,
     constants::StateIdName,
+    dump::DumpJsonImpl,
     generics::skip_lists::{SkipList, SkipListImplInit},
+    profile::{
+      self, ProfileEnd, ProfileHelpersImpl, ProfileInitImpl, ProfileMethodsImpl,
+      ProfilePosixSource, ProfileStart,
+    },
+    reflection::ReflectionImpl,
   },
   resolve::cst::Cst,
 };
 
 use super::{
-  common::{ComponentStructName, EventStructName, NodeStructName},
+  common::{ComponentStructName, EngineStructName, EventStructName, NodeStructName},
   constants::{ComponentMaskName, NodeMaskName},
   generics::{
     common::{function_name, method_name},
@@ -27,23 +35,48 @@ pub struct Impl<'a> {
 
 impl<'a> Display for Impl<'a> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    let profile = self.data.settings.profile;
+
+    let single_header = self.data.settings.single_header;
+
+    // In a single header this is up to `SingleHeader`, since the includes of the
+    // declarations come first.
+    if profile && !single_header {
+      ProfilePosixSource.fmt(f)?;
+    }
+
     write!(f, "#include <assert.h>\n")?;
+    writeln!(f, "#include <math.h>")?;
     write!(f, "#include <stdlib.h>\n")?;
     write!(f, "#include <string.h>\n")?;
-    write!(f, "#include \"{}\"\n\n", self.header_name)?;
 
-    DynArray::new("uint32_t").imple().fmt(f)?;
-    DynArray::new("uint64_t").imple().fmt(f)?;
-    DynQueue::new("uint32_t").imple().fmt(f)?;
+    if profile {
+      writeln!(f, "#include <time.h>")?;
+    }
+
+    if !single_header {
+      writeln!(f, "#include \"{}\"", self.header_name)?;
+    }
+    writeln!(f)?;
+
+    if profile {
+      ProfileHelpersImpl { data: self.data }.fmt(f)?;
+    }
+
+    DynArray::new(prefix, "uint32_t").imple().fmt(f)?;
+    DynArray::new(prefix, "uint64_t").imple().fmt(f)?;
+    DynQueue::new(prefix, "uint32_t").imple().fmt(f)?;
 
     for event in self.data.events.values() {
-      let event_t = EventStructName::new(event.name);
-      DynQueue::new(event_t).imple().fmt(f)?;
+      let event_t = EventStructName::new(prefix, event.name);
+      DynQueue::new(prefix, event_t).imple().fmt(f)?;
     }
 
     // Skip list of vecs_id_t:
 
-    let max_key_fn_name = function_name!("max"; "vecs_id_t");
+    let max_key_fn_name = function_name!(&prefix.upper, "max"; "vecs_id_t");
 
     write!(
       f,
@@ -55,7 +88,7 @@ impl<'a> Display for Impl<'a> {
       max_key_fn_name = max_key_fn_name,
     )?;
 
-    let id_eq_fn_name = function_name!("eq"; "vecs_id_t");
+    let id_eq_fn_name = function_name!(&prefix.upper, "eq"; "vecs_id_t");
 
     write!(
       f,
@@ -67,7 +100,7 @@ impl<'a> Display for Impl<'a> {
       eq_fn_name = id_eq_fn_name,
     )?;
 
-    let id_cmp_fn_name = function_name!("cmp"; "vecs_id_t");
+    let id_cmp_fn_name = function_name!(&prefix.upper, "cmp"; "vecs_id_t");
 
     write!(
       f,
@@ -79,7 +112,7 @@ impl<'a> Display for Impl<'a> {
       cmp_fn_name = id_cmp_fn_name,
     )?;
 
-    let index_index = SkipList::new("vecs_id_t", "uint32_t");
+    let index_index = SkipList::new(prefix, "vecs_id_t", "uint32_t");
     let index_index_t = index_index.get_type();
 
     SkipListImplInit {}.fmt(f)?;
@@ -89,32 +122,42 @@ impl<'a> Display for Impl<'a> {
 
     for component in self.data.components.values() {
       let component_name = component.name();
-      let component_t = ComponentStructName::new(component_name);
+      let component_t = ComponentStructName::new(prefix, component_name);
 
       if !component.is_empty() {
-        DynArray::new(component_t.clone()).imple().fmt(f)?;
-        SparseDynArray::new(component_t.clone()).imple().fmt(f)?;
+        DynArray::new(prefix, component_t.clone()).imple().fmt(f)?;
+        SparseDynArray::new(prefix, component_t.clone())
+          .imple()
+          .fmt(f)?;
       }
     }
 
     for node in self.data.nodes.values() {
-      let node_t = NodeStructName::new(node.name);
-      DynArray::new(node_t).imple().fmt(f)?;
+      let node_t = NodeStructName::new(prefix, node.name);
+      DynArray::new(prefix, node_t).imple().fmt(f)?;
     }
 
-    DynArray::new("vecs_entity_t").imple().fmt(f)?;
-    let entity_array = SparseDynArray::new("vecs_entity_t");
+    let entity_t = EngineStructName::new(prefix, "entity");
+    DynArray::new(prefix, entity_t.clone()).imple().fmt(f)?;
+    let entity_array = SparseDynArray::new(prefix, entity_t);
     entity_array.imple().fmt(f)?;
 
-    let op_add_component_queue = DynQueue::new("vecs_op_union_add_component_t");
+    let op_add_component_queue = DynQueue::new(
+      prefix,
+      EngineStructName::new(prefix, "op_union_add_component"),
+    );
     let op_add_component_queue_t = op_add_component_queue.get_type();
     op_add_component_queue.imple().fmt(f)?;
 
-    let op_other_queue = DynQueue::new("vecs_op_union_other_t");
+    let op_other_queue =
+      DynQueue::new(prefix, EngineStructName::new(prefix, "op_union_other"));
     let op_other_queue_t = op_other_queue.get_type();
     op_other_queue.imple().fmt(f)?;
 
-    let op_remove_component_queue = DynQueue::new("vecs_op_union_remove_component_t");
+    let op_remove_component_queue = DynQueue::new(
+      prefix,
+      EngineStructName::new(prefix, "op_union_remove_component"),
+    );
     let op_remove_component_queue_t = op_remove_component_queue.get_type();
     op_remove_component_queue.imple().fmt(f)?;
 
@@ -125,38 +168,51 @@ impl<'a> Display for Impl<'a> {
     write!(
       f,
       concat!(
-        "void vecs_init(vecs_engine_t *e) {{\n",
-        "  e->state = VECS_STATE_NONE;\n",
+        "vecs_status_t {prefix}_init({prefix}_engine_t *e, const vecs_allocator_t *allocator) {{\n",
+        "  // Every container is left destroyable even if its initialization fails, so\n",
+        "  // we can clean up at the end.\n",
+        "  vecs_status_t status = VECS_OK;\n",
+        "  e->allocator = allocator != NULL ? *allocator : vecs_allocator_default;\n",
+        "  e->state = {PREFIX}_STATE_NONE;\n",
         "  e->entities_to_add = 0;\n",
         "  e->things_to_add = NULL;\n",
         "  e->things_to_add_count = 0;\n",
-        "  e->next_state = VECS_STATE_NONE;\n",
-        "  {entity_array_method_init}(&e->entities, 0);\n",
-        "  {op_add_component_queue_method_init}(&e->ops_add_component, 0);\n",
-        "  {op_other_queue_method_init}(&e->ops_other, 0);\n",
-        "  {op_remove_component_queue_method_init}(&e->ops_remove_component, 0);\n",
+        "  e->next_state = {PREFIX}_STATE_NONE;\n",
+        "  if ({entity_array_method_init}(&e->entities, {entity_capacity}, &e->allocator) != VECS_OK)\n",
+        "    status = VECS_ERR_OOM;\n",
+        "  if ({op_add_component_queue_method_init}(&e->ops_add_component, 0, &e->allocator) != VECS_OK)\n",
+        "    status = VECS_ERR_OOM;\n",
+        "  if ({op_other_queue_method_init}(&e->ops_other, 0, &e->allocator) != VECS_OK)\n",
+        "    status = VECS_ERR_OOM;\n",
+        "  if ({op_remove_component_queue_method_init}(&e->ops_remove_component, 0, &e->allocator) != VECS_OK)\n",
+        "    status = VECS_ERR_OOM;\n",
       ),
       entity_array_method_init = method_name!(&entity_array_t, "init"),
+      entity_capacity = self.data.settings.entity_capacity,
       op_add_component_queue_method_init =
         method_name!(&op_add_component_queue_t, "init"),
       op_other_queue_method_init = method_name!(&op_other_queue_t, "init"),
       op_remove_component_queue_method_init =
         method_name!(&op_remove_component_queue_t, "init"),
+      prefix = prefix.lower,
+      PREFIX = prefix.upper,
     )?;
 
     for component in self.data.components.values() {
       if !component.is_empty() {
         let component_name = component.name();
-        let component_t = ComponentStructName::new(component_name);
+        let component_t = ComponentStructName::new(prefix, component_name);
 
-        let dyn_array = SparseDynArray::new(component_t.clone());
+        let dyn_array = SparseDynArray::new(prefix, component_t.clone());
         let dyn_array_t = dyn_array.get_type();
 
         write!(
           f,
           concat!(
-            "  {component_array_method_init}(&e->components_{component_name}, 0);\n",
-            "  {index_index_method_init}(&e->entity_to_component_{component_name});\n",
+            "  if ({component_array_method_init}(&e->components_{component_name}, 0, &e->allocator) != VECS_OK)\n",
+            "    status = VECS_ERR_OOM;\n",
+            "  if ({index_index_method_init}(&e->entity_to_component_{component_name}, &e->allocator) != VECS_OK)\n",
+            "    status = VECS_ERR_OOM;\n",
           ),
           component_name = component_name,
           component_array_method_init = method_name!(&dyn_array_t, "init"),
@@ -166,15 +222,17 @@ impl<'a> Display for Impl<'a> {
     }
 
     for node in self.data.nodes.values() {
-      let node_t = NodeStructName::new(node.name);
-      let dyn_array = DynArray::new(node_t);
+      let node_t = NodeStructName::new(prefix, node.name);
+      let dyn_array = DynArray::new(prefix, node_t);
       let dyn_array_t = dyn_array.get_type();
 
       write!(
         f,
         concat!(
-          "  {node_array_method_init}(&e->nodes_{node_name}, 0);\n",
-          "  {index_index_method_init}(&e->entity_to_node_{node_name});\n",
+          "  if ({node_array_method_init}(&e->nodes_{node_name}, 0, &e->allocator) != VECS_OK)\n",
+          "    status = VECS_ERR_OOM;\n",
+          "  if ({index_index_method_init}(&e->entity_to_node_{node_name}, &e->allocator) != VECS_OK)\n",
+          "    status = VECS_ERR_OOM;\n",
         ),
         node_name = node.name,
         node_array_method_init = method_name!(&dyn_array_t, "init"),
@@ -183,35 +241,56 @@ impl<'a> Display for Impl<'a> {
     }
 
     for event in self.data.events.values() {
-      let event_t = EventStructName::new(event.name);
-      let dyn_queue = DynQueue::new(event_t);
+      let event_t = EventStructName::new(prefix, event.name);
+      let dyn_queue = DynQueue::new(prefix, event_t);
       let dyn_queue_t = dyn_queue.get_type();
 
       write!(
         f,
-        concat!("{event_queue_method_init}(&e->events_{event_name}, 0);\n"),
+        concat!(
+          "  if ({event_queue_method_init}(&e->events_{event_name}, 0, &e->allocator) != VECS_OK)\n",
+          "    status = VECS_ERR_OOM;\n",
+        ),
         event_name = event.name,
         event_queue_method_init = method_name!(&dyn_queue_t, "init"),
       )?;
     }
 
+    if profile {
+      ProfileInitImpl { data: self.data }.fmt(f)?;
+    }
+
     write!(
       f,
       concat!(
+        "\n",
+        "  if (status != VECS_OK)\n",
+        "    {prefix}_destroy(e);\n",
+        "  return status;\n",
         "}}\n",
-        "void vecs_destroy(vecs_engine_t *e) {{\n",
-        "  e->state = VECS_STATE_NONE;\n",
+        "void {prefix}_destroy({prefix}_engine_t *e) {{\n",
+        "  e->state = {PREFIX}_STATE_NONE;\n",
         "  {entity_array_method_destroy}(&e->entities);\n",
+        "  {op_add_component_queue_method_destroy}(&e->ops_add_component);\n",
+        "  {op_other_queue_method_destroy}(&e->ops_other);\n",
+        "  {op_remove_component_queue_method_destroy}(&e->ops_remove_component);\n",
       ),
       entity_array_method_destroy = method_name!(&entity_array_t, "destroy"),
+      op_add_component_queue_method_destroy =
+        method_name!(&op_add_component_queue_t, "destroy"),
+      op_other_queue_method_destroy = method_name!(&op_other_queue_t, "destroy"),
+      op_remove_component_queue_method_destroy =
+        method_name!(&op_remove_component_queue_t, "destroy"),
+      prefix = prefix.lower,
+      PREFIX = prefix.upper,
     )?;
 
     for component in self.data.components.values() {
       if !component.is_empty() {
         let component_name = component.name();
-        let component_t = ComponentStructName::new(component_name);
+        let component_t = ComponentStructName::new(prefix, component_name);
 
-        let dyn_array = SparseDynArray::new(component_t.clone());
+        let dyn_array = SparseDynArray::new(prefix, component_t.clone());
         let dyn_array_t = dyn_array.get_type();
 
         write!(
@@ -228,8 +307,8 @@ impl<'a> Display for Impl<'a> {
     }
 
     for node in self.data.nodes.values() {
-      let node_t = NodeStructName::new(node.name);
-      let dyn_array = DynArray::new(node_t);
+      let node_t = NodeStructName::new(prefix, node.name);
+      let dyn_array = DynArray::new(prefix, node_t);
       let dyn_array_t = dyn_array.get_type();
 
       write!(
@@ -245,13 +324,13 @@ impl<'a> Display for Impl<'a> {
     }
 
     for event in self.data.events.values() {
-      let event_t = EventStructName::new(event.name);
-      let dyn_queue = DynQueue::new(event_t);
+      let event_t = EventStructName::new(prefix, event.name);
+      let dyn_queue = DynQueue::new(prefix, event_t);
       let dyn_queue_t = dyn_queue.get_type();
 
       write!(
         f,
-        concat!("{event_queue_method_destroy}(&e->events_{event_name});\n"),
+        concat!("  {event_queue_method_destroy}(&e->events_{event_name});\n"),
         event_name = event.name,
         event_queue_method_destroy = method_name!(&dyn_queue_t, "destroy"),
       )?;
@@ -261,22 +340,23 @@ impl<'a> Display for Impl<'a> {
       f,
       concat!(
         "  if (e->things_to_add != NULL) {{\n",
-        "    free(e->things_to_add);\n",
+        "    vecs_free(&e->allocator, e->things_to_add);\n",
         "  }}\n",
         "}}\n",
-        "vecs_id_t vecs_add_entity(vecs_engine_t *e) {{\n",
-        "  vecs_entity_t ent = {{0}};\n",
-        "  vecs_id_t id;\n",
-        "  {entity_array_method_push}(&e->entities, ent, &id.index, &id.gen);\n",
-        "  return id;\n",
+        "vecs_status_t {prefix}_add_entity({prefix}_engine_t *e, vecs_id_t *entity) {{\n",
+        "  {prefix}_entity_t ent = {{0}};\n",
+        "  if ({entity_array_method_push}(&e->entities, ent, &entity->index, &entity->gen) == NULL)\n",
+        "    return VECS_ERR_OOM;\n",
+        "  return VECS_OK;\n",
         "}}\n",
-        "vecs_tmp_id_t vecs_schedule_add_entity(vecs_engine_t *e) {{\n",
+        "vecs_tmp_id_t {prefix}_schedule_add_entity({prefix}_engine_t *e) {{\n",
         "  vecs_tmp_id_t id = {{.index = e->entities_to_add}};\n",
         "  ++e->entities_to_add;\n",
         "  return id;\n",
         "}}\n",
       ),
       entity_array_method_push = method_name!(&entity_array_t, "push"),
+      prefix = prefix.lower,
     )?;
 
     // Mask utilities:
@@ -306,13 +386,14 @@ impl<'a> Display for Impl<'a> {
 
     for component in self.data.components.values() {
       let component_name = component.name();
-      let component_t = ComponentStructName::new(component_name);
-      let component_mask_name = ComponentMaskName::new(component_name);
+      let component_t = ComponentStructName::new(prefix, component_name);
+      let component_mask_name = ComponentMaskName::new(prefix, component_name);
 
-      let entity_array = SparseDynArray::new("vecs_entity_t");
+      let entity_array =
+        SparseDynArray::new(prefix, EngineStructName::new(prefix, "entity"));
       let entity_array_t = entity_array.get_type();
 
-      let component_array = SparseDynArray::new(component_t.clone());
+      let component_array = SparseDynArray::new(prefix, component_t.clone());
       let component_array_t = component_array.get_type();
 
       // Has component:
@@ -320,27 +401,29 @@ impl<'a> Display for Impl<'a> {
         f,
         concat!(
           "// Currently, this method will not detect disabled components.\n",
-          "bool vecs_has_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-          "  vecs_entity_t *ent = {entity_array_method_get}(&e->entities, entity.index, entity.gen);\n",
+          "bool {prefix}_has_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+          "  {prefix}_entity_t *ent = {entity_array_method_get}(&e->entities, entity.index, entity.gen);\n",
           "  return match_mask(ent->mask, {component_mask_name});\n",
           "}}\n",
         ),
         component_name = component_name,
         entity_array_method_get = method_name!(&entity_array_t, "get"),
         component_mask_name = component_mask_name,
+        prefix = prefix.lower,
       )?;
 
       if !component.is_empty() {
         write!(
           f,
           concat!(
-            "{component_t} *vecs_get_component_{component_name}(vecs_engine_t *e, vecs_id_t component_id) {{\n",
+            "{component_t} *{prefix}_get_component_{component_name}({prefix}_engine_t *e, vecs_id_t component_id) {{\n",
             "  return {component_array_method_get}(&e->components_{component_name}, component_id.index, component_id.gen);\n",
             "}}\n",
           ),
           component_name = component_name,
           component_t = component_t,
           component_array_method_get = method_name!(&component_array_t, "get"),
+          prefix = prefix.lower,
         )?;
       }
 
@@ -350,12 +433,22 @@ impl<'a> Display for Impl<'a> {
           write!(
             f,
             concat!(
-              "vecs_id_t vecs_{state_name}_add_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
+              "vecs_id_t {prefix}_{state_name}_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
               "  vecs_id_t component_id;\n",
-              "  {component_array_method_push}(&e->components_{component_name}, component, &component_id.index, &component_id.gen);\n",
-              "  {entity_to_component_array_method_add}(&e->entity_to_component_{component_name}, entity, component_id.index);\n",
+              "  {component_t} removed;\n",
+              "  if ({component_array_method_push}(&e->components_{component_name}, component, &component_id.index, &component_id.gen) == NULL)\n",
+              "    return vecs_id_invalid;\n",
+              "  if ({entity_to_component_array_method_add}(&e->entity_to_component_{component_name}, entity, component_id.index) != VECS_OK) {{\n",
+              "    {component_array_method_remove_unchecked}(&e->components_{component_name}, component_id.index, &removed);\n",
+              "    return vecs_id_invalid;\n",
+              "  }}\n",
               "\n",
-              "  vecs_{state_name}_enable_component_{component_name}(e, entity);\n",
+              "  if ({prefix}_{state_name}_enable_component_{component_name}(e, entity) != VECS_OK) {{\n",
+              "    uint32_t component_index;\n",
+              "    {entity_to_component_array_method_remove}(&e->entity_to_component_{component_name}, entity, &component_index);\n",
+              "    {component_array_method_remove_unchecked}(&e->components_{component_name}, component_id.index, &removed);\n",
+              "    return vecs_id_invalid;\n",
+              "  }}\n",
               "  return component_id;\n",
               "}}\n",
             ),
@@ -363,19 +456,25 @@ impl<'a> Display for Impl<'a> {
             component_name = component_name,
             component_t = component_t,
             component_array_method_push = method_name!(&component_array_t, "push"),
+            component_array_method_remove_unchecked =
+              method_name!(&component_array_t, "remove_unchecked"),
             entity_to_component_array_method_add =
               method_name!(&index_index_t, "add"),
+            entity_to_component_array_method_remove =
+              method_name!(&index_index_t, "remove"),
+            prefix = prefix.lower,
           )?;
         } else {
           write!(
             f,
             concat!(
-              "void vecs_{state_name}_add_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-              "  vecs_{state_name}_enable_component_{component_name}(e, entity);\n",
+              "vecs_status_t {prefix}_{state_name}_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+              "  return {prefix}_{state_name}_enable_component_{component_name}(e, entity);\n",
               "}}\n",
             ),
             state_name = state.name,
             component_name = component_name,
+            prefix = prefix.lower,
           )?;
         }
 
@@ -384,17 +483,18 @@ impl<'a> Display for Impl<'a> {
           write!(
             f,
             concat!(
-              "vecs_id_t vecs_{state_name}_upsert_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
-              "  if (vecs_has_component_{component_name}(e, entity)) {{\n",
-              "    return vecs_{state_name}_update_component_{component_name}(e, entity, component);\n",
+              "vecs_id_t {prefix}_{state_name}_upsert_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
+              "  if ({prefix}_has_component_{component_name}(e, entity)) {{\n",
+              "    return {prefix}_{state_name}_update_component_{component_name}(e, entity, component);\n",
               "  }} else {{\n",
-              "    return vecs_{state_name}_add_component_{component_name}(e, entity, component);\n",
+              "    return {prefix}_{state_name}_add_component_{component_name}(e, entity, component);\n",
               "  }}\n",
               "}}\n",
             ),
             state_name = state.name,
             component_name = component_name,
             component_t = component_t,
+            prefix = prefix.lower,
           )?;
         }
 
@@ -403,7 +503,7 @@ impl<'a> Display for Impl<'a> {
           write!(
             f,
             concat!(
-              "vecs_id_t vecs_{state_name}_update_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
+              "vecs_id_t {prefix}_{state_name}_update_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
               "  uint32_t component_index;\n",
               "  {entity_to_component_array_method_get}(&e->entity_to_component_{component_name}, entity, &component_index);\n",
               "  {component_t} *found = {component_array_method_get_unchecked}(&e->components_{component_name}, component_index);\n",
@@ -418,6 +518,7 @@ impl<'a> Display for Impl<'a> {
               method_name!(&index_index_t, "get"),
             component_array_method_get_unchecked =
               method_name!(&component_array_t, "get_unchecked"),
+            prefix = prefix.lower,
           )?;
         }
 
@@ -426,8 +527,8 @@ impl<'a> Display for Impl<'a> {
           write!(
             f,
             concat!(
-              "bool vecs_{state_name}_remove_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-              "  vecs_{state_name}_disable_component_{component_name}(e, entity);\n",
+              "bool {prefix}_{state_name}_remove_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+              "  {prefix}_{state_name}_disable_component_{component_name}(e, entity);\n",
               "\n",
               "  vecs_id_t component_id;\n",
               "  bool found = {entity_to_component_array_method_remove}(&e->entity_to_component_{component_name}, entity, &component_id.index);\n",
@@ -445,17 +546,19 @@ impl<'a> Display for Impl<'a> {
               method_name!(&component_array_t, "remove_unchecked"),
             entity_to_component_array_method_remove =
               method_name!(&index_index_t, "remove"),
+            prefix = prefix.lower,
           )?;
         } else {
           write!(
             f,
             concat!(
-              "void vecs_{state_name}_remove_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-              "  vecs_{state_name}_disable_component_{component_name}(e, entity);\n",
+              "void {prefix}_{state_name}_remove_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+              "  {prefix}_{state_name}_disable_component_{component_name}(e, entity);\n",
               "}}\n",
             ),
             state_name = state.name,
             component_name = component_name,
+            prefix = prefix.lower,
           )?;
         }
 
@@ -465,20 +568,21 @@ impl<'a> Display for Impl<'a> {
           concat!(
             "// A disabled component will not produce nodes and will not be a part of the entity mask.\n",
             "// It will however be found in the entity_to_component index.\n",
-            "void vecs_{state_name}_disable_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-            "  vecs_entity_t *ent = {entity_array_method_get}(&e->entities, entity.index, entity.gen);\n",
+            "void {prefix}_{state_name}_disable_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+            "  {prefix}_entity_t *ent = {entity_array_method_get}(&e->entities, entity.index, entity.gen);\n",
           ),
           state_name = state.name,
           entity_array_method_get = method_name!(&entity_array_t, "get"),
           component_name = component_name,
+          prefix = prefix.lower,
         )?;
 
         for node in state.nodes.iter().map(|n| self.data.nodes.get(n).unwrap()) {
           if node.components.contains(component_name) {
-            let node_t = NodeStructName::new(node.name);
-            let node_mask_name = NodeMaskName::new(node.name);
+            let node_t = NodeStructName::new(prefix, node.name);
+            let node_mask_name = NodeMaskName::new(prefix, node.name);
 
-            let node_array = DynArray::new(node_t);
+            let node_array = DynArray::new(prefix, node_t);
             let node_array_t = node_array.get_type();
 
             write!(
@@ -486,8 +590,9 @@ impl<'a> Display for Impl<'a> {
               concat!(
                 "  if (match_mask(ent->mask, {node_mask_name})) {{\n",
                 "    uint32_t node_index;\n",
-                "    {entity_to_node_method_remove}(&e->entity_to_node_{node_name}, entity, &node_index);\n",
-                "    {node_array_method_remove}(&e->nodes_{node_name}, node_index);\n",
+                "    // May be missing if enabling the component ran out of memory.\n",
+                "    if ({entity_to_node_method_remove}(&e->entity_to_node_{node_name}, entity, &node_index))\n",
+                "      {node_array_method_remove}(&e->nodes_{node_name}, node_index);\n",
                 "  }}\n",
               ),
               node_mask_name = node_mask_name,
@@ -508,20 +613,25 @@ impl<'a> Display for Impl<'a> {
         write!(
           f,
           concat!(
-            "void vecs_{state_name}_enable_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-            "  vecs_entity_t *ent = {entity_array_method_get}(&e->entities, entity.index, entity.gen);\n",
+            "vecs_status_t {prefix}_{state_name}_enable_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+            "  {prefix}_entity_t *ent = {entity_array_method_get}(&e->entities, entity.index, entity.gen);\n",
             "  mix_mask({component_mask_name}, ent->mask);\n",
           ),
           state_name = state.name,
           entity_array_method_get = method_name!(&entity_array_t, "get"),
           component_name = component_name,
           component_mask_name = component_mask_name,
+          prefix = prefix.lower,
         )?;
 
+        let mut has_nodes = false;
+
         for node in state.nodes.iter().map(|n| self.data.nodes.get(n).unwrap()) {
           if node.components.contains(component_name) {
-            let node_t = NodeStructName::new(node.name);
-            let node_mask_name = NodeMaskName::new(node.name);
+            has_nodes = true;
+
+            let node_t = NodeStructName::new(prefix, node.name);
+            let node_mask_name = NodeMaskName::new(prefix, node.name);
 
             write!(
               f,
@@ -558,24 +668,47 @@ impl<'a> Display for Impl<'a> {
               }
             }
 
-            let node_array = DynArray::new(node_t);
+            let node_array = DynArray::new(prefix, node_t);
             let node_array_t = node_array.get_type();
 
             write!(
               f,
               concat!(
-                "    uint32_t node_index = {node_array_method_push}(&e->nodes_{node_name}, node);\n",
-                "    {entity_to_node_method_add}(&e->entity_to_node_{node_name}, entity, node_index);\n",
+                "    uint32_t node_index;\n",
+                "    if ({node_array_method_push}(&e->nodes_{node_name}, node, &node_index) != VECS_OK)\n",
+                "      goto oom;\n",
+                "    if ({entity_to_node_method_add}(&e->entity_to_node_{node_name}, entity, node_index) != VECS_OK) {{\n",
+                "      {node_array_method_pop}(&e->nodes_{node_name});\n",
+                "      goto oom;\n",
+                "    }}\n",
                 "  }}\n",
               ),
               node_name = node.name,
               node_array_method_push = method_name!(&node_array_t, "push"),
+              node_array_method_pop = method_name!(&node_array_t, "pop"),
               entity_to_node_method_add = method_name!(&index_index_t, "add"),
             )?;
           }
         }
 
-        write!(f, concat!("}}\n",),)?;
+        writeln!(f, "  return VECS_OK;")?;
+
+        if has_nodes {
+          write!(
+            f,
+            concat!(
+              "oom:\n",
+              "  // Undo whatever was done.\n",
+              "  {prefix}_{state_name}_disable_component_{component_name}(e, entity);\n",
+              "  return VECS_ERR_OOM;\n",
+            ),
+            state_name = state.name,
+            component_name = component_name,
+            prefix = prefix.lower,
+          )?;
+        }
+
+        writeln!(f, "}}")?;
       }
 
       // Callbacks that are stored in the deferred operations and apply them
@@ -584,81 +717,91 @@ impl<'a> Display for Impl<'a> {
           write!(
             f,
             concat!(
-              "static void vecs_{state_name}_apply_store_entity_in_{component_name}(vecs_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, vecs_op_union_other_t op) {{\n",
-              "  vecs_op_store_entity_t store = op.store_entity;\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_store_entity_in_{component_name}({prefix}_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, {prefix}_op_union_other_t op) {{\n",
+              "  {prefix}_op_store_entity_t store = op.store_entity;\n",
               "  vecs_id_t entity = new_entities[store.tmp_entity.index];\n",
               "  vecs_id_t *location = (vecs_id_t*)((uint8_t*)e->components_{component_name}.items.items + store.location_offset);\n",
               "  *location = entity;\n",
+              "  return VECS_OK;\n",
               "}}\n",
-              "static void vecs_{state_name}_apply_store_component_{component_name}(vecs_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, vecs_op_union_other_t op) {{\n",
-              "  vecs_op_store_component_t store = op.store_component;\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_store_component_{component_name}({prefix}_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, {prefix}_op_union_other_t op) {{\n",
+              "  {prefix}_op_store_component_t store = op.store_component;\n",
               "  vecs_id_t component = new_components[store.tmp_component.index];\n",
               "  vecs_id_t *location = (vecs_id_t*)((uint8_t*)e->components_{component_name}.items.items + store.location_offset);\n",
               "  *location = component;\n",
+              "  return VECS_OK;\n",
               "}}\n",
-              "static vecs_id_t vecs_{state_name}_apply_add_component_{component_name}(vecs_engine_t *e, vecs_id_t *new_entities, vecs_op_union_add_component_t op) {{\n",
-              "  vecs_op_add_component_{component_name}_t add = op.add_{component_name};\n",
-              "  return vecs_{state_name}_add_component_{component_name}(e, add.entity, add.component);\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t *new_entities, {prefix}_op_union_add_component_t op, vecs_id_t *result) {{\n",
+              "  {prefix}_op_add_component_{component_name}_t add = op.add_{component_name};\n",
+              "  *result = {prefix}_{state_name}_add_component_{component_name}(e, add.entity, add.component);\n",
+              "  return vecs_id_is_invalid(*result) ? VECS_ERR_OOM : VECS_OK;\n",
               "}}\n",
-              "static vecs_id_t vecs_{state_name}_apply_tmp_add_component_{component_name}(vecs_engine_t *e, vecs_id_t *new_entities, vecs_op_union_add_component_t op) {{\n",
-              "  vecs_op_tmp_add_component_{component_name}_t add_tmp = op.add_tmp_{component_name};\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_tmp_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t *new_entities, {prefix}_op_union_add_component_t op, vecs_id_t *result) {{\n",
+              "  {prefix}_op_tmp_add_component_{component_name}_t add_tmp = op.add_tmp_{component_name};\n",
               "  vecs_id_t entity = new_entities[add_tmp.tmp_entity.index];\n",
-              "  return vecs_{state_name}_add_component_{component_name}(e, entity, add_tmp.component);\n",
+              "  *result = {prefix}_{state_name}_add_component_{component_name}(e, entity, add_tmp.component);\n",
+              "  return vecs_id_is_invalid(*result) ? VECS_ERR_OOM : VECS_OK;\n",
               "}}\n",
-              "static void vecs_{state_name}_apply_upsert_component_{component_name}(vecs_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, vecs_op_union_other_t op) {{\n",
-              "  vecs_op_update_component_{component_name}_t upsert = op.update_{component_name};\n",
-              "  vecs_{state_name}_upsert_component_{component_name}(e, upsert.entity, upsert.component);\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_upsert_component_{component_name}({prefix}_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, {prefix}_op_union_other_t op) {{\n",
+              "  {prefix}_op_update_component_{component_name}_t upsert = op.update_{component_name};\n",
+              "  vecs_id_t id = {prefix}_{state_name}_upsert_component_{component_name}(e, upsert.entity, upsert.component);\n",
+              "  return vecs_id_is_invalid(id) ? VECS_ERR_OOM : VECS_OK;\n",
               "}}\n",
-              "static void vecs_{state_name}_apply_update_component_{component_name}(vecs_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, vecs_op_union_other_t op) {{\n",
-              "  vecs_op_update_component_{component_name}_t update = op.update_{component_name};\n",
-              "  vecs_{state_name}_update_component_{component_name}(e, update.entity, update.component);\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_update_component_{component_name}({prefix}_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, {prefix}_op_union_other_t op) {{\n",
+              "  {prefix}_op_update_component_{component_name}_t update = op.update_{component_name};\n",
+              "  {prefix}_{state_name}_update_component_{component_name}(e, update.entity, update.component);\n",
+              "  return VECS_OK;\n",
               "}}\n",
-              "static void vecs_{state_name}_apply_remove_component_{component_name}(vecs_engine_t *e, vecs_op_union_remove_component_t op) {{\n",
-              "  vecs_op_remove_component_t remove = op.remove;\n",
-              "  vecs_{state_name}_remove_component_{component_name}(e, remove.entity);\n",
+              "static void {prefix}_{state_name}_apply_remove_component_{component_name}({prefix}_engine_t *e, {prefix}_op_union_remove_component_t op) {{\n",
+              "  {prefix}_op_remove_component_t remove = op.remove;\n",
+              "  {prefix}_{state_name}_remove_component_{component_name}(e, remove.entity);\n",
               "}}\n",
-              "static void vecs_{state_name}_apply_enable_component_{component_name}(vecs_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, vecs_op_union_other_t op) {{\n",
-              "  vecs_op_enable_component_t enable = op.enable;\n",
-              "  vecs_{state_name}_enable_component_{component_name}(e, enable.entity);\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_enable_component_{component_name}({prefix}_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, {prefix}_op_union_other_t op) {{\n",
+              "  {prefix}_op_enable_component_t enable = op.enable;\n",
+              "  return {prefix}_{state_name}_enable_component_{component_name}(e, enable.entity);\n",
               "}}\n",
-              "static void vecs_{state_name}_apply_disable_component_{component_name}(vecs_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, vecs_op_union_other_t op) {{\n",
-              "  vecs_op_disable_component_t disable = op.disable;\n",
-              "  vecs_{state_name}_disable_component_{component_name}(e, disable.entity);\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_disable_component_{component_name}({prefix}_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, {prefix}_op_union_other_t op) {{\n",
+              "  {prefix}_op_disable_component_t disable = op.disable;\n",
+              "  {prefix}_{state_name}_disable_component_{component_name}(e, disable.entity);\n",
+              "  return VECS_OK;\n",
               "}}\n",
             ),
             state_name = state.name,
             component_name = component_name,
+            prefix = prefix.lower,
           )?;
         } else {
           write!(
             f,
             concat!(
-              "static vecs_id_t vecs_{state_name}_apply_add_component_{component_name}(vecs_engine_t *e, vecs_id_t *new_entities, vecs_op_union_add_component_t op) {{\n",
-              "  vecs_op_add_component_{component_name}_t add = op.add_{component_name};\n",
-              "  vecs_{state_name}_add_component_{component_name}(e, add.entity);\n",
-              "  return vecs_id_invalid;\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t *new_entities, {prefix}_op_union_add_component_t op, vecs_id_t *result) {{\n",
+              "  {prefix}_op_add_component_{component_name}_t add = op.add_{component_name};\n",
+              "  *result = vecs_id_invalid;\n",
+              "  return {prefix}_{state_name}_add_component_{component_name}(e, add.entity);\n",
               "}}\n",
-              "static vecs_id_t vecs_{state_name}_apply_tmp_add_component_{component_name}(vecs_engine_t *e, vecs_id_t *new_entities, vecs_op_union_add_component_t op) {{\n",
-              "  vecs_op_tmp_add_component_{component_name}_t add_tmp = op.add_tmp_{component_name};\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_tmp_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t *new_entities, {prefix}_op_union_add_component_t op, vecs_id_t *result) {{\n",
+              "  {prefix}_op_tmp_add_component_{component_name}_t add_tmp = op.add_tmp_{component_name};\n",
               "  vecs_id_t entity = new_entities[add_tmp.tmp_entity.index];\n",
-              "  vecs_{state_name}_add_component_{component_name}(e, entity);\n",
-              "  return vecs_id_invalid;\n",
+              "  *result = vecs_id_invalid;\n",
+              "  return {prefix}_{state_name}_add_component_{component_name}(e, entity);\n",
               "}}\n",
-              "static void vecs_{state_name}_apply_remove_component_{component_name}(vecs_engine_t *e, vecs_op_union_remove_component_t op) {{\n",
-              "  vecs_op_remove_component_t remove = op.remove;\n",
-              "  vecs_{state_name}_remove_component_{component_name}(e, remove.entity);\n",
+              "static void {prefix}_{state_name}_apply_remove_component_{component_name}({prefix}_engine_t *e, {prefix}_op_union_remove_component_t op) {{\n",
+              "  {prefix}_op_remove_component_t remove = op.remove;\n",
+              "  {prefix}_{state_name}_remove_component_{component_name}(e, remove.entity);\n",
               "}}\n",
-              "static void vecs_{state_name}_apply_enable_component_{component_name}(vecs_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, vecs_op_union_other_t op) {{\n",
-              "  vecs_op_enable_component_t enable = op.enable;\n",
-              "  vecs_{state_name}_enable_component_{component_name}(e, enable.entity);\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_enable_component_{component_name}({prefix}_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, {prefix}_op_union_other_t op) {{\n",
+              "  {prefix}_op_enable_component_t enable = op.enable;\n",
+              "  return {prefix}_{state_name}_enable_component_{component_name}(e, enable.entity);\n",
               "}}\n",
-              "static void vecs_{state_name}_apply_disable_component_{component_name}(vecs_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, vecs_op_union_other_t op) {{\n",
-              "  vecs_op_disable_component_t disable = op.disable;\n",
-              "  vecs_{state_name}_disable_component_{component_name}(e, disable.entity);\n",
+              "static vecs_status_t {prefix}_{state_name}_apply_disable_component_{component_name}({prefix}_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, {prefix}_op_union_other_t op) {{\n",
+              "  {prefix}_op_disable_component_t disable = op.disable;\n",
+              "  {prefix}_{state_name}_disable_component_{component_name}(e, disable.entity);\n",
+              "  return VECS_OK;\n",
               "}}\n",
             ),
             state_name = state.name,
             component_name = component_name,
+            prefix = prefix.lower,
           )?;
         }
       }
@@ -686,23 +829,25 @@ impl<'a> Display for Impl<'a> {
         write!(
           f,
           concat!(
-            "vecs_id_t (* const vecs_op_map_{op_name}_{component_name}[{states_len}])(vecs_engine_t *, vecs_id_t *, vecs_op_union_add_component_t) = {{\n",
+            "vecs_status_t (* const {prefix}_op_map_{op_name}_{component_name}[{states_len}])({prefix}_engine_t *, vecs_id_t *, {prefix}_op_union_add_component_t, vecs_id_t *) = {{\n",
             "  NULL,\n",
           ),
           op_name = op_name,
           component_name = component_name,
           states_len = self.data.states.len() + 1,
+          prefix = prefix.lower,
         )?;
 
-        // `states` being a BTreeMap guarantees this will be in the same order as the
+        // `states` being an IndexMap guarantees this will be in the same order as the
         // states enum, meaning it will be able to index this properly
         for state in self.data.states.values() {
           write!(
             f,
-            "  &vecs_{state_name}_apply_{op_name}_{component_name},\n",
+            "  &{prefix}_{state_name}_apply_{op_name}_{component_name},\n",
             op_name = op_name,
             component_name = component_name,
             state_name = state.name,
+            prefix = prefix.lower,
           )?;
         }
 
@@ -722,23 +867,25 @@ impl<'a> Display for Impl<'a> {
         write!(
           f,
           concat!(
-            "void (* const vecs_op_map_{op_name}_{component_name}[{states_len}])(vecs_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, vecs_op_union_other_t op) = {{\n",
+            "vecs_status_t (* const {prefix}_op_map_{op_name}_{component_name}[{states_len}])({prefix}_engine_t *e, vecs_id_t *restrict new_entities, vecs_id_t *restrict new_components, {prefix}_op_union_other_t op) = {{\n",
             "  NULL,\n",
           ),
           op_name = op_name,
           component_name = component_name,
           states_len = self.data.states.len() + 1,
+          prefix = prefix.lower,
         )?;
 
-        // `states` being a BTreeMap guarantees this will be in the same order as the
+        // `states` being an IndexMap guarantees this will be in the same order as the
         // states enum, meaning it will be able to index this properly
         for state in self.data.states.values() {
           write!(
             f,
-            "  &vecs_{state_name}_apply_{op_name}_{component_name},\n",
+            "  &{prefix}_{state_name}_apply_{op_name}_{component_name},\n",
             op_name = op_name,
             component_name = component_name,
             state_name = state.name,
+            prefix = prefix.lower,
           )?;
         }
 
@@ -752,23 +899,25 @@ impl<'a> Display for Impl<'a> {
         write!(
           f,
           concat!(
-            "void (* const vecs_op_map_{op_name}_{component_name}[{states_len}])(vecs_engine_t *, vecs_op_union_remove_component_t) = {{\n",
+            "void (* const {prefix}_op_map_{op_name}_{component_name}[{states_len}])({prefix}_engine_t *, {prefix}_op_union_remove_component_t) = {{\n",
             "  NULL,\n",
           ),
           op_name = op_name,
           component_name = component_name,
           states_len = self.data.states.len() + 1,
+          prefix = prefix.lower,
         )?;
 
-        // `states` being a BTreeMap guarantees this will be in the same order as the
+        // `states` being an IndexMap guarantees this will be in the same order as the
         // states enum, meaning it will be able to index this properly
         for state in self.data.states.values() {
           write!(
             f,
-            "  &vecs_{state_name}_apply_{op_name}_{component_name},\n",
+            "  &{prefix}_{state_name}_apply_{op_name}_{component_name},\n",
             op_name = op_name,
             component_name = component_name,
             state_name = state.name,
+            prefix = prefix.lower,
           )?;
         }
 
@@ -778,14 +927,14 @@ impl<'a> Display for Impl<'a> {
 
     for component in self.data.components.values() {
       let component_name = component.name();
-      let component_t = ComponentStructName::new(component_name);
-      let ops = ComponentTmpOps::new(component_name);
+      let component_t = ComponentStructName::new(prefix, component_name);
+      let ops = ComponentTmpOps::new(prefix, component_name);
 
       if !component.is_empty() {
         write!(
           f,
           concat!(
-            "void vecs_schedule_store_entity_in_{component_name}(vecs_engine_t *e, vecs_tmp_id_t tmp_entity, vecs_id_t *location) {{\n",
+            "vecs_status_t {prefix}_schedule_store_entity_in_{component_name}({prefix}_engine_t *e, vecs_tmp_id_t tmp_entity, vecs_id_t *location) {{\n",
             "  assert(sizeof(uint8_t*) == sizeof(vecs_id_t*) && sizeof(uint8_t*) == sizeof({component_t}*));\n",
             "\n",
             "  // Make sure the location is inside the component array\n",
@@ -793,11 +942,11 @@ impl<'a> Display for Impl<'a> {
             "  assert((uint8_t*)location < (uint8_t*)e->components_{component_name}.items.items + e->components_{component_name}.items.len * sizeof({component_t}));\n",
             "\n",
             "  ptrdiff_t offset = (uint8_t*)e->components_{component_name}.items.items - (uint8_t*)location;\n",
-            "  vecs_op_store_entity_t store = {{.tmp_entity = tmp_entity, .location_offset = offset}};\n",
-            "  vecs_op_union_other_t op = {{.apply = vecs_op_map_store_entity_in_{component_name}[e->state], .store_entity = store}};\n",
-            "  {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
+            "  {prefix}_op_store_entity_t store = {{.tmp_entity = tmp_entity, .location_offset = offset}};\n",
+            "  {prefix}_op_union_other_t op = {{.apply = {prefix}_op_map_store_entity_in_{component_name}[e->state], .store_entity = store}};\n",
+            "  return {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
             "}}\n",
-            "void vecs_schedule_store_component_{component_name}(vecs_engine_t *e, vecs_tmp_id_t tmp_component, vecs_id_t *location) {{\n",
+            "vecs_status_t {prefix}_schedule_store_component_{component_name}({prefix}_engine_t *e, vecs_tmp_id_t tmp_component, vecs_id_t *location) {{\n",
             "  assert(sizeof(uint8_t*) == sizeof(vecs_id_t*) && sizeof(uint8_t*) == sizeof({component_t}*));\n",
             "\n",
             "  // Make sure the location is inside the component array\n",
@@ -805,48 +954,50 @@ impl<'a> Display for Impl<'a> {
             "  assert((uint8_t*)location < (uint8_t*)e->components_{component_name}.items.items + e->components_{component_name}.items.len * sizeof({component_t}));\n",
             "\n",
             "  ptrdiff_t offset = (uint8_t*)e->components_{component_name}.items.items - (uint8_t*)location;\n",
-            "  vecs_op_store_component_t store = {{.tmp_component = tmp_component, .location_offset = offset}};\n",
-            "  vecs_op_union_other_t op = {{.apply = vecs_op_map_store_component_{component_name}[e->state], .store_component = store}};\n",
-            "  {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
+            "  {prefix}_op_store_component_t store = {{.tmp_component = tmp_component, .location_offset = offset}};\n",
+            "  {prefix}_op_union_other_t op = {{.apply = {prefix}_op_map_store_component_{component_name}[e->state], .store_component = store}};\n",
+            "  return {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
             "}}\n",
-            "vecs_tmp_id_t vecs_schedule_add_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
+            "vecs_tmp_id_t {prefix}_schedule_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
             "  vecs_tmp_id_t id = {{.index = e->ops_add_component.len}};\n",
             "  {component_add_t} add = {{.entity = entity, .component = component}};\n",
-            "  vecs_op_union_add_component_t op = {{.apply = vecs_op_map_add_component_{component_name}[e->state], .add_{component_name} = add}};\n",
-            "  {op_add_component_queue_method_enqueue}(&e->ops_add_component, op);\n",
+            "  {prefix}_op_union_add_component_t op = {{.apply = {prefix}_op_map_add_component_{component_name}[e->state], .add_{component_name} = add}};\n",
+            "  if ({op_add_component_queue_method_enqueue}(&e->ops_add_component, op) != VECS_OK)\n",
+            "    return vecs_tmp_id_invalid;\n",
             "  return id;\n",
             "}}\n",
-            "vecs_tmp_id_t vecs_schedule_tmp_add_component_{component_name}(vecs_engine_t *e, vecs_tmp_id_t entity, {component_t} component) {{\n",
+            "vecs_tmp_id_t {prefix}_schedule_tmp_add_component_{component_name}({prefix}_engine_t *e, vecs_tmp_id_t entity, {component_t} component) {{\n",
             "  vecs_tmp_id_t id = {{.index = e->ops_add_component.len}};\n",
             "  {component_add_tmp_t} add_tmp = {{.tmp_entity = entity, .component = component}};\n",
-            "  vecs_op_union_add_component_t op = {{.apply = vecs_op_map_tmp_add_component_{component_name}[e->state], .add_tmp_{component_name} = add_tmp}};\n",
-            "  {op_add_component_queue_method_enqueue}(&e->ops_add_component, op);\n",
+            "  {prefix}_op_union_add_component_t op = {{.apply = {prefix}_op_map_tmp_add_component_{component_name}[e->state], .add_tmp_{component_name} = add_tmp}};\n",
+            "  if ({op_add_component_queue_method_enqueue}(&e->ops_add_component, op) != VECS_OK)\n",
+            "    return vecs_tmp_id_invalid;\n",
             "  return id;\n",
             "}}\n",
-            "void vecs_schedule_enable_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-            "  vecs_op_enable_component_t enable = {{.entity = entity}};\n",
-            "  vecs_op_union_other_t op = {{.apply = vecs_op_map_enable_component_{component_name}[e->state], .enable = enable}};\n",
-            "  {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
+            "vecs_status_t {prefix}_schedule_enable_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+            "  {prefix}_op_enable_component_t enable = {{.entity = entity}};\n",
+            "  {prefix}_op_union_other_t op = {{.apply = {prefix}_op_map_enable_component_{component_name}[e->state], .enable = enable}};\n",
+            "  return {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
             "}}\n",
-            "void vecs_schedule_upsert_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
+            "vecs_status_t {prefix}_schedule_upsert_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
             "  {component_update_t} upsert = {{.entity = entity, .component = component}};\n",
-            "  vecs_op_union_other_t op = {{.apply = vecs_op_map_upsert_component_{component_name}[e->state], .update_{component_name} = upsert}};\n",
-            "  {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
+            "  {prefix}_op_union_other_t op = {{.apply = {prefix}_op_map_upsert_component_{component_name}[e->state], .update_{component_name} = upsert}};\n",
+            "  return {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
             "}}\n",
-            "void vecs_schedule_update_component_{component_name}(vecs_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
+            "vecs_status_t {prefix}_schedule_update_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity, {component_t} component) {{\n",
             "  {component_update_t} update = {{.entity = entity, .component = component}};\n",
-            "  vecs_op_union_other_t op = {{.apply = vecs_op_map_update_component_{component_name}[e->state], .update_{component_name} = update}};\n",
-            "  {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
+            "  {prefix}_op_union_other_t op = {{.apply = {prefix}_op_map_update_component_{component_name}[e->state], .update_{component_name} = update}};\n",
+            "  return {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
             "}}\n",
-            "void vecs_schedule_remove_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-            "  vecs_op_remove_component_t remove = {{.entity = entity}};\n",
-            "  vecs_op_union_remove_component_t op = {{.apply = vecs_op_map_remove_component_{component_name}[e->state], .remove = remove}};\n",
-            "  {op_remove_component_queue_method_enqueue}(&e->ops_remove_component, op);\n",
+            "vecs_status_t {prefix}_schedule_remove_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+            "  {prefix}_op_remove_component_t remove = {{.entity = entity}};\n",
+            "  {prefix}_op_union_remove_component_t op = {{.apply = {prefix}_op_map_remove_component_{component_name}[e->state], .remove = remove}};\n",
+            "  return {op_remove_component_queue_method_enqueue}(&e->ops_remove_component, op);\n",
             "}}\n",
-            "void vecs_schedule_disable_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-            "  vecs_op_disable_component_t disable = {{.entity = entity}};\n",
-            "  vecs_op_union_other_t op = {{.apply = vecs_op_map_disable_component_{component_name}[e->state], .disable = disable}};\n",
-            "  {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
+            "vecs_status_t {prefix}_schedule_disable_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+            "  {prefix}_op_disable_component_t disable = {{.entity = entity}};\n",
+            "  {prefix}_op_union_other_t op = {{.apply = {prefix}_op_map_disable_component_{component_name}[e->state], .disable = disable}};\n",
+            "  return {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
             "}}\n",
           ),
           component_name = component_name,
@@ -859,37 +1010,36 @@ impl<'a> Display for Impl<'a> {
           op_other_queue_method_enqueue = method_name!(&op_other_queue_t, "enqueue"),
           op_remove_component_queue_method_enqueue =
             method_name!(&op_remove_component_queue_t, "enqueue"),
+          prefix = prefix.lower,
         )?;
       } else {
         write!(
           f,
           concat!(
-            "void vecs_schedule_add_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-            "  vecs_tmp_id_t id = {{.index = e->ops_add_component.len}};\n",
+            "vecs_status_t {prefix}_schedule_add_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
             "  {component_add_t} add = {{.entity = entity}};\n",
-            "  vecs_op_union_add_component_t op = {{.apply = vecs_op_map_add_component_{component_name}[e->state], .add_{component_name} = add}};\n",
-            "  {op_add_component_queue_method_enqueue}(&e->ops_add_component, op);\n",
+            "  {prefix}_op_union_add_component_t op = {{.apply = {prefix}_op_map_add_component_{component_name}[e->state], .add_{component_name} = add}};\n",
+            "  return {op_add_component_queue_method_enqueue}(&e->ops_add_component, op);\n",
             "}}\n",
-            "void vecs_schedule_tmp_add_component_{component_name}(vecs_engine_t *e, vecs_tmp_id_t entity) {{\n",
-            "  vecs_tmp_id_t id = {{.index = e->ops_add_component.len}};\n",
+            "vecs_status_t {prefix}_schedule_tmp_add_component_{component_name}({prefix}_engine_t *e, vecs_tmp_id_t entity) {{\n",
             "  {component_add_tmp_t} add_tmp = {{.tmp_entity = entity}};\n",
-            "  vecs_op_union_add_component_t op = {{.apply = vecs_op_map_tmp_add_component_{component_name}[e->state], .add_tmp_{component_name} = add_tmp}};\n",
-            "  {op_add_component_queue_method_enqueue}(&e->ops_add_component, op);\n",
+            "  {prefix}_op_union_add_component_t op = {{.apply = {prefix}_op_map_tmp_add_component_{component_name}[e->state], .add_tmp_{component_name} = add_tmp}};\n",
+            "  return {op_add_component_queue_method_enqueue}(&e->ops_add_component, op);\n",
             "}}\n",
-            "void vecs_schedule_enable_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-            "  vecs_op_enable_component_t enable = {{.entity = entity}};\n",
-            "  vecs_op_union_other_t op = {{.apply = vecs_op_map_enable_component_{component_name}[e->state], .enable = enable}};\n",
-            "  {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
+            "vecs_status_t {prefix}_schedule_enable_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+            "  {prefix}_op_enable_component_t enable = {{.entity = entity}};\n",
+            "  {prefix}_op_union_other_t op = {{.apply = {prefix}_op_map_enable_component_{component_name}[e->state], .enable = enable}};\n",
+            "  return {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
             "}}\n",
-            "void vecs_schedule_remove_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-            "  vecs_op_remove_component_t remove = {{.entity = entity}};\n",
-            "  vecs_op_union_remove_component_t op = {{.apply = vecs_op_map_remove_component_{component_name}[e->state], .remove = remove}};\n",
-            "  {op_remove_component_queue_method_enqueue}(&e->ops_remove_component, op);\n",
+            "vecs_status_t {prefix}_schedule_remove_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+            "  {prefix}_op_remove_component_t remove = {{.entity = entity}};\n",
+            "  {prefix}_op_union_remove_component_t op = {{.apply = {prefix}_op_map_remove_component_{component_name}[e->state], .remove = remove}};\n",
+            "  return {op_remove_component_queue_method_enqueue}(&e->ops_remove_component, op);\n",
             "}}\n",
-            "void vecs_schedule_disable_component_{component_name}(vecs_engine_t *e, vecs_id_t entity) {{\n",
-            "  vecs_op_disable_component_t disable = {{.entity = entity}};\n",
-            "  vecs_op_union_other_t op = {{.apply = vecs_op_map_disable_component_{component_name}[e->state], .disable = disable}};\n",
-            "  {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
+            "vecs_status_t {prefix}_schedule_disable_component_{component_name}({prefix}_engine_t *e, vecs_id_t entity) {{\n",
+            "  {prefix}_op_disable_component_t disable = {{.entity = entity}};\n",
+            "  {prefix}_op_union_other_t op = {{.apply = {prefix}_op_map_disable_component_{component_name}[e->state], .disable = disable}};\n",
+            "  return {op_other_queue_method_enqueue}(&e->ops_other, op);\n",
             "}}\n",
           ),
           component_name = component_name,
@@ -900,6 +1050,7 @@ impl<'a> Display for Impl<'a> {
           op_other_queue_method_enqueue = method_name!(&op_other_queue_t, "enqueue"),
           op_remove_component_queue_method_enqueue =
             method_name!(&op_remove_component_queue_t, "enqueue"),
+          prefix = prefix.lower,
         )?;
       }
     }
@@ -909,26 +1060,46 @@ impl<'a> Display for Impl<'a> {
       write!(
         f,
         concat!(
-          "vecs_node_{node_name}_array_t vecs_nodes_{node_name}(vecs_engine_t *e) {{\n",
+          "{prefix}_node_{node_name}_array_t {prefix}_nodes_{node_name}({prefix}_engine_t *e) {{\n",
           "  return e->nodes_{node_name};\n",
           "}}\n",
         ),
         node_name = node.name,
+        prefix = prefix.lower,
       )?;
     }
 
+    // Used by the state loops to leave the engine in a consistent state if a deferred
+    // operation fails.
+    write!(
+      f,
+      concat!(
+        "static void {prefix}_drop_deferred_ops({prefix}_engine_t *e) {{\n",
+        "  e->entities_to_add = 0;\n",
+        "  e->ops_add_component.len = 0;\n",
+        "  e->ops_add_component.head = 0;\n",
+        "  e->ops_other.len = 0;\n",
+        "  e->ops_other.head = 0;\n",
+        "  e->ops_remove_component.len = 0;\n",
+        "  e->ops_remove_component.head = 0;\n",
+        "}}\n",
+      ),
+      prefix = prefix.lower,
+    )?;
+
     for state in self.data.states.values() {
-      let state_id = StateIdName::new(state.name);
+      let state_id = StateIdName::new(prefix, state.name);
 
       write!(
         f,
         concat!(
-          "void vecs_schedule_state_to_{state_name}(vecs_engine_t *e) {{\n",
+          "void {prefix}_schedule_state_to_{state_name}({prefix}_engine_t *e) {{\n",
           "  e->next_state = {state_id};\n",
           "}}\n\n",
         ),
         state_name = state.name,
         state_id = state_id,
+        prefix = prefix.lower,
       )?;
 
       // State transitions:
@@ -939,43 +1110,31 @@ impl<'a> Display for Impl<'a> {
 
         write!(
           f,
-          "void vecs_state_{}_to_{}(vecs_engine_t *e) {{\n",
-          other_state.name, state.name,
+          "vecs_status_t {prefix}_state_{}_to_{}({prefix}_engine_t *e) {{\n",
+          other_state.name,
+          state.name,
+          prefix = prefix.lower,
         )?;
 
         let old_relevant_nodes = other_state
           .nodes
           .iter()
           .map(|n| self.data.nodes.get(n).unwrap())
-          .collect::<HashSet<_>>();
+          .collect::<IndexSet<_>>();
 
         let new_relevant_nodes = state
           .nodes
           .iter()
           .map(|n| self.data.nodes.get(n).unwrap())
-          .collect::<HashSet<_>>();
-
-        // Remove unnecessary nodes:
-        for old_relevant_node in old_relevant_nodes.difference(&new_relevant_nodes) {
-          let node_t = NodeStructName::new(old_relevant_node.name);
-          let node_array = DynArray::new(node_t);
-          let node_array_t = node_array.get_type();
-
-          write!(
-            f,
-            concat!("  {node_array_method_destroy}(&e->nodes_{node_name});\n",),
-            node_name = old_relevant_node.name,
-            node_array_method_destroy = method_name!(&node_array_t, "destroy"),
-          )?;
-        }
+          .collect::<IndexSet<_>>();
 
         // Add new necessary nodes:
-        let mut new_nodes = new_relevant_nodes
+        let new_nodes = new_relevant_nodes
           .difference(&old_relevant_nodes)
-          .peekable();
+          .collect::<Vec<_>>();
 
         // If there is some new node to track
-        if new_nodes.peek().is_some() {
+        if !new_nodes.is_empty() {
           write!(
             f,
             concat!(
@@ -984,16 +1143,17 @@ impl<'a> Display for Impl<'a> {
               "      goto continue_outer;\n",
               "    }}\n",
               "\n",
-              "    vecs_entity_t *ent = &e->entities.items.items[i];\n",
+              "    {prefix}_entity_t *ent = &e->entities.items.items[i];\n",
               "    uint32_t gen = e->entities.gens.items[i];\n",
               "    vecs_id_t entity = {{.index = i, .gen = gen}};\n",
             ),
             entity_method_is_hole = method_name!(&entity_array_t, "is_hole"),
+            prefix = prefix.lower,
           )?;
 
-          for new_relevant_node in new_nodes {
-            let node_t = NodeStructName::new(new_relevant_node.name);
-            let node_mask_name = NodeMaskName::new(new_relevant_node.name);
+          for new_relevant_node in new_nodes.iter() {
+            let node_t = NodeStructName::new(prefix, new_relevant_node.name);
+            let node_mask_name = NodeMaskName::new(prefix, new_relevant_node.name);
 
             write!(
               f,
@@ -1030,14 +1190,17 @@ impl<'a> Display for Impl<'a> {
               }
             }
 
-            let node_array = DynArray::new(node_t);
+            let node_array = DynArray::new(prefix, node_t);
             let node_array_t = node_array.get_type();
 
             write!(
               f,
               concat!(
-                "      uint32_t node_index = {node_array_method_push}(&e->nodes_{node_name}, node);\n",
-                "      {entity_to_node_method_add}(&e->entity_to_node_{node_name}, entity, node_index);\n",
+                "      uint32_t node_index;\n",
+                "      if ({node_array_method_push}(&e->nodes_{node_name}, node, &node_index) != VECS_OK)\n",
+                "        goto oom;\n",
+                "      if ({entity_to_node_method_add}(&e->entity_to_node_{node_name}, entity, node_index) != VECS_OK)\n",
+                "        goto oom;\n",
                 "    }}\n",
               ),
               node_name = new_relevant_node.name,
@@ -1049,27 +1212,68 @@ impl<'a> Display for Impl<'a> {
           write!(f, concat!("continue_outer:\n", "    ;\n", "  }}\n",),)?;
         }
 
+        // Remove unnecessary nodes, only once nothing else can fail:
+        for old_relevant_node in old_relevant_nodes.difference(&new_relevant_nodes) {
+          let node_t = NodeStructName::new(prefix, old_relevant_node.name);
+          let node_array = DynArray::new(prefix, node_t);
+          let node_array_t = node_array.get_type();
+
+          write!(
+            f,
+            concat!("  {node_array_method_destroy}(&e->nodes_{node_name});\n",),
+            node_name = old_relevant_node.name,
+            node_array_method_destroy = method_name!(&node_array_t, "destroy"),
+          )?;
+        }
+
         write!(
           f,
-          concat!("  e->state = {state_id};\n", "}}\n",),
+          concat!("  e->state = {state_id};\n", "  return VECS_OK;\n"),
           state_id = state_id,
         )?;
+
+        // Drop the partially built nodes, so the old state is kept as it was:
+        if !new_nodes.is_empty() {
+          writeln!(f, "oom:")?;
+
+          for new_relevant_node in new_nodes.iter() {
+            let node_t = NodeStructName::new(prefix, new_relevant_node.name);
+            let node_array = DynArray::new(prefix, node_t);
+            let node_array_t = node_array.get_type();
+
+            write!(
+              f,
+              concat!("  {node_array_method_destroy}(&e->nodes_{node_name});\n",),
+              node_name = new_relevant_node.name,
+              node_array_method_destroy = method_name!(&node_array_t, "destroy"),
+            )?;
+          }
+
+          writeln!(f, "  return VECS_ERR_OOM;")?;
+        }
+
+        writeln!(f, "}}")?;
       }
 
       // State loops:
       write!(
         f,
         concat!(
-          "void vecs_run_state_{state_name}(vecs_engine_t *e) {{\n",
+          "vecs_status_t {prefix}_run_state_{state_name}({prefix}_engine_t *e) {{\n",
           "  e->state = {state_id};\n",
         ),
         state_name = state.name,
         state_id = state_id,
+        prefix = prefix.lower,
       )?;
 
+      if profile {
+        writeln!(f, "  uint64_t profile_start;")?;
+      }
+
       for event in self.data.events.values() {
-        let event_t = EventStructName::new(event.name);
-        let event_queue = DynQueue::new(event_t.clone());
+        let event_t = EventStructName::new(prefix, event.name);
+        let event_queue = DynQueue::new(prefix, event_t.clone());
         let event_queue_name = event_queue.get_type();
 
         write!(
@@ -1093,8 +1297,12 @@ impl<'a> Display for Impl<'a> {
               .expect("failed to find system in state");
 
             if system.event == event.name {
+              if profile {
+                ProfileStart { prefix, indent: 4 }.fmt(f)?;
+              }
+
               if let Some(node) = system.node {
-                let node_t = NodeStructName::new(node);
+                let node_t = NodeStructName::new(prefix, node);
 
                 write!(
                   f,
@@ -1112,12 +1320,45 @@ impl<'a> Display for Impl<'a> {
               } else {
                 write!(f, "    {system_name}(e, ev);\n", system_name = system.name)?;
               }
+
+              if profile {
+                let index = profile::system_index(self.data, system.name);
+                ProfileEnd {
+                  prefix,
+                  indent: 4,
+                  index,
+                }
+                .fmt(f)?;
+              }
             }
           }
         }
         write!(f, "  }}\n")?;
       }
 
+      // Each phase of the flush is profiled separately.
+      let profile_start = |f: &mut std::fmt::Formatter<'_>| {
+        if profile {
+          ProfileStart { prefix, indent: 2 }.fmt(f)?;
+        }
+
+        Ok(())
+      };
+
+      let profile_end = |f: &mut std::fmt::Formatter<'_>, phase: &str| {
+        if profile {
+          let index = profile::phase_index(self.data, phase);
+          ProfileEnd {
+            prefix,
+            indent: 2,
+            index,
+          }
+          .fmt(f)?;
+        }
+
+        Ok(())
+      };
+
       write!(
         f,
         concat!(
@@ -1125,62 +1366,131 @@ impl<'a> Display for Impl<'a> {
           "  size_t new_thing_count = e->entities_to_add + new_component_count;\n",
           "\n",
           "  if (new_thing_count > e->things_to_add_count) {{\n",
-          "    e->things_to_add = malloc(new_thing_count * sizeof(vecs_id_t));\n",
+          "    // Nothing was applied yet, so the operations are kept for the next run.\n",
+          "    vecs_id_t *things_to_add = vecs_alloc(&e->allocator, new_thing_count * sizeof(vecs_id_t));\n",
+          "    if (things_to_add == NULL)\n",
+          "      return VECS_ERR_OOM;\n",
+          "\n",
+          "    if (e->things_to_add != NULL)\n",
+          "      vecs_free(&e->allocator, e->things_to_add);\n",
+          "    e->things_to_add = things_to_add;\n",
           "    e->things_to_add_count = new_thing_count;\n",
           "  }}\n",
           "\n",
           "  vecs_id_t *new_entities = e->things_to_add + 0;\n",
           "  vecs_id_t *new_components = e->things_to_add + e->entities_to_add;\n",
           "\n",
+        ),
+      )?;
+
+      profile_start(f)?;
+      write!(
+        f,
+        concat!(
           "  for (size_t i = 0; i < e->entities_to_add; ++i) {{\n",
-          "    new_entities[i] = vecs_add_entity(e);\n",
+          "    if ({prefix}_add_entity(e, &new_entities[i]) != VECS_OK)\n",
+          "      goto oom;\n",
           "  }}\n",
-          "\n",
+        ),
+        prefix = prefix.lower,
+      )?;
+      profile_end(f, "flush_add_entities")?;
+
+      writeln!(f)?;
+      profile_start(f)?;
+      write!(
+        f,
+        concat!(
           "  for (size_t i = 0; i < new_component_count; ++i) {{\n",
-          "    vecs_op_union_add_component_t op = {op_add_component_queue_method_dequeue}(&e->ops_add_component);\n",
-          "    new_components[i] = op.apply(e, new_entities, op);\n",
+          "    {prefix}_op_union_add_component_t op = {op_add_component_queue_method_dequeue}(&e->ops_add_component);\n",
+          "    if (op.apply(e, new_entities, op, &new_components[i]) != VECS_OK)\n",
+          "      goto oom;\n",
           "  }}\n",
-          "\n",
+        ),
+        op_add_component_queue_method_dequeue =
+          method_name!(&op_add_component_queue_t, "dequeue"),
+        prefix = prefix.lower,
+      )?;
+      profile_end(f, "flush_add_components")?;
+
+      writeln!(f)?;
+      profile_start(f)?;
+      write!(
+        f,
+        concat!(
           "  size_t ops_other_count = e->ops_other.len;\n",
           "  for (size_t i = 0; i < ops_other_count; ++i) {{\n",
-          "    vecs_op_union_other_t op = {op_other_queue_method_dequeue}(&e->ops_other);\n",
-          "    op.apply(e, new_entities, new_components, op);\n",
+          "    {prefix}_op_union_other_t op = {op_other_queue_method_dequeue}(&e->ops_other);\n",
+          "    if (op.apply(e, new_entities, new_components, op) != VECS_OK)\n",
+          "      goto oom;\n",
           "  }}\n",
-          "\n",
+        ),
+        op_other_queue_method_dequeue = method_name!(&op_other_queue_t, "dequeue"),
+        prefix = prefix.lower,
+      )?;
+      profile_end(f, "flush_other")?;
+
+      writeln!(f)?;
+      profile_start(f)?;
+      write!(
+        f,
+        concat!(
           "  size_t remove_component_count = e->ops_remove_component.len;\n",
           "  for (size_t i = 0; i < remove_component_count; ++i) {{\n",
-          "    vecs_op_union_remove_component_t op = {op_remove_component_queue_method_dequeue}(&e->ops_remove_component);\n",
+          "    {prefix}_op_union_remove_component_t op = {op_remove_component_queue_method_dequeue}(&e->ops_remove_component);\n",
           "    op.apply(e, op);\n",
           "  }}\n",
-          "}}\n",
         ),
-        op_add_component_queue_method_dequeue =
-          method_name!(&op_add_component_queue_t, "dequeue"),
-        op_other_queue_method_dequeue = method_name!(&op_other_queue_t, "dequeue"),
         op_remove_component_queue_method_dequeue =
           method_name!(&op_remove_component_queue_t, "dequeue"),
+        prefix = prefix.lower,
+      )?;
+      profile_end(f, "flush_remove_components")?;
+
+      write!(
+        f,
+        concat!(
+          "\n",
+          "  e->entities_to_add = 0;\n",
+          "  return VECS_OK;\n",
+          "oom:\n",
+          "  {prefix}_drop_deferred_ops(e);\n",
+          "  return VECS_ERR_OOM;\n",
+          "}}\n",
+        ),
+        prefix = prefix.lower,
       )?;
     }
 
     // Event emition:
     for event in self.data.events.values() {
-      let event_t = EventStructName::new(event.name);
-      let event_queue = DynQueue::new(event_t.clone());
+      let event_t = EventStructName::new(prefix, event.name);
+      let event_queue = DynQueue::new(prefix, event_t.clone());
       let event_queue_t = event_queue.get_type();
 
       write!(
         f,
         concat!(
-          "void vecs_emit_{event_name}(vecs_engine_t *e, {event_t} ev) {{\n",
-          "  {event_queue_method_enqueue}(&e->events_{event_name}, ev);\n",
+          "vecs_status_t {prefix}_emit_{event_name}({prefix}_engine_t *e, {event_t} ev) {{\n",
+          "  return {event_queue_method_enqueue}(&e->events_{event_name}, ev);\n",
           "}}\n",
         ),
         event_name = event.name,
         event_t = event_t,
         event_queue_method_enqueue = method_name!(&event_queue_t, "enqueue"),
+        prefix = prefix.lower,
       )?;
     }
 
+    if profile {
+      ProfileMethodsImpl { data: self.data }.fmt(f)?;
+    }
+
+    ReflectionImpl { data: self.data }.fmt(f)?;
+
+    // Debugging:
+    DumpJsonImpl { data: self.data }.fmt(f)?;
+
     Ok(())
   }
 }
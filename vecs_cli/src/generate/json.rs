@@ -0,0 +1,337 @@
+use std::fmt::Display;
+
+use crate::{
+  common::StringKind,
+  parse::data::str::Span,
+  resolve::cst::{Cst, Field, TypeName},
+};
+
+// The resolved spec as JSON, for tools that want to read it without parsing it. Every
+// declaration is in declaration order and carries its span. Lines are 1-based and
+// columns 0-based, offsets are in bytes.
+//
+// The built-in `frame` event has a default span, without a file. Masks are written as
+// hex strings, since JSON numbers can't hold 64 bits everywhere.
+pub struct CstJson<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+// A JSON string.
+struct JsonStr<'a>(&'a str);
+
+impl<'a> Display for JsonStr<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "\"")?;
+
+    for c in self.0.chars() {
+      match c {
+        '"' => write!(f, "\\\"")?,
+        '\\' => write!(f, "\\\\")?,
+        '\n' => write!(f, "\\n")?,
+        '\r' => write!(f, "\\r")?,
+        '\t' => write!(f, "\\t")?,
+        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+        c => write!(f, "{}", c)?,
+      }
+    }
+
+    write!(f, "\"")
+  }
+}
+
+struct JsonSpan<'a>(&'a Span<'a>);
+
+impl<'a> Display for JsonSpan<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let span = self.0;
+
+    match span.file {
+      Some(file) => write!(f, "{{\"file\": {}, ", JsonStr(file))?,
+      None => write!(f, "{{\"file\": null, ")?,
+    }
+
+    write!(
+      f,
+      concat!(
+        "\"start\": {{\"offset\": {}, \"line\": {}, \"column\": {}}}, ",
+        "\"end\": {{\"offset\": {}, \"line\": {}, \"column\": {}}}}}",
+      ),
+      span.start_byte_offset,
+      span.start_line,
+      span.start_column,
+      span.end_byte_offset,
+      span.end_line,
+      span.end_column,
+    )
+  }
+}
+
+// A list of strings.
+struct JsonStrs<'a, T: AsRef<str>>(&'a [T]);
+
+impl<'a, T: AsRef<str>> Display for JsonStrs<'a, T> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "[")?;
+    for (i, s) in self.0.iter().enumerate() {
+      if i > 0 {
+        write!(f, ", ")?;
+      }
+      write!(f, "{}", JsonStr(s.as_ref()))?;
+    }
+    write!(f, "]")
+  }
+}
+
+struct JsonFields<'a>(&'a [Field<'a>]);
+
+impl<'a> Display for JsonFields<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "[")?;
+    for (i, field) in self.0.iter().enumerate() {
+      if i > 0 {
+        write!(f, ", ")?;
+      }
+      write!(
+        f,
+        "{{\"name\": {}, \"type\": {}, \"span\": {}}}",
+        JsonStr(field.name),
+        JsonStr(&field.type_components.join(" ")),
+        JsonSpan(&field.span),
+      )?;
+    }
+    write!(f, "]")
+  }
+}
+
+// The members shared by everything declared with a C type: globals, components and
+// events. `type` is null for markers and for structs declared with a body.
+struct JsonTypeName<'a>(&'a TypeName<'a>);
+
+impl<'a> Display for JsonTypeName<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let typ = self.0;
+
+    write!(f, "\"name\": {}, \"type\": ", JsonStr(typ.name))?;
+    if typ.type_components.is_empty() {
+      write!(f, "null")?;
+    } else {
+      write!(f, "{}", JsonStr(&typ.type_components.join(" ")))?;
+    }
+
+    write!(
+      f,
+      ", \"fields\": {}, \"span\": {}",
+      JsonFields(&typ.fields),
+      JsonSpan(&typ.span),
+    )
+  }
+}
+
+// Writes `items` as a JSON array with one item per line.
+fn write_array<T>(
+  f: &mut std::fmt::Formatter<'_>,
+  key: &str,
+  items: impl Iterator<Item = T>,
+  mut write_item: impl FnMut(&mut std::fmt::Formatter<'_>, T) -> std::fmt::Result,
+) -> std::fmt::Result {
+  write!(f, "  {}: [", JsonStr(key))?;
+
+  let mut empty = true;
+  for item in items {
+    write!(f, "{}\n    ", if empty { "" } else { "," })?;
+    write_item(f, item)?;
+    empty = false;
+  }
+
+  write!(f, "{}]", if empty { "" } else { "\n  " })
+}
+
+impl<'a> Display for CstJson<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let settings = &self.data.settings;
+
+    write!(
+      f,
+      concat!(
+        "{{\n",
+        "  \"settings\": {{\"prefix\": {}, \"profile\": {}, \"single_header\": {}, ",
+        "\"entity_capacity\": {}}},\n",
+        "  \"mask_len\": {},\n",
+      ),
+      JsonStr(&settings.prefix.lower),
+      settings.profile,
+      settings.single_header,
+      settings.entity_capacity,
+      self.data.node_mask_arr_size,
+    )?;
+
+    write_array(f, "includes", self.data.includes.iter(), |f, include| {
+      let (path, system) = match &include.path {
+        StringKind::DoubleQuoted(path) => (path, false),
+        StringKind::AngleBracketed(path) => (path, true),
+      };
+
+      write!(
+        f,
+        "{{\"path\": {}, \"system\": {}, \"span\": {}}}",
+        JsonStr(path),
+        system,
+        JsonSpan(&include.span),
+      )
+    })?;
+    writeln!(f, ",")?;
+
+    write_array(f, "globals", self.data.globals.values(), |f, global| {
+      write!(f, "{{{}}}", JsonTypeName(global))
+    })?;
+    writeln!(f, ",")?;
+
+    write_array(
+      f,
+      "components",
+      self.data.components.values(),
+      |f, component| {
+        write!(
+          f,
+          "{{{}, \"marker\": {}, \"mask_index\": {}, \"mask_bit\": {}}}",
+          JsonTypeName(&component.typ),
+          component.is_empty(),
+          component.mask_i,
+          component.mask_j,
+        )
+      },
+    )?;
+    writeln!(f, ",")?;
+
+    write_array(f, "events", self.data.events.values(), |f, event| {
+      write!(f, "{{{}}}", JsonTypeName(event))
+    })?;
+    writeln!(f, ",")?;
+
+    write_array(f, "nodes", self.data.nodes.values(), |f, node| {
+      let components = node.components.iter().collect::<Vec<_>>();
+      let mask = node
+        .mask
+        .iter()
+        .map(|m| format!("0x{:016x}", m))
+        .collect::<Vec<_>>();
+
+      write!(
+        f,
+        "{{\"name\": {}, \"components\": {}, \"mask\": {}, \"span\": {}}}",
+        JsonStr(node.name),
+        JsonStrs(&components),
+        JsonStrs(&mask),
+        JsonSpan(&node.span),
+      )
+    })?;
+    writeln!(f, ",")?;
+
+    write_array(f, "systems", self.data.systems.values(), |f, system| {
+      write!(
+        f,
+        "{{\"name\": {}, \"event\": {}, \"node\": ",
+        JsonStr(system.name),
+        JsonStr(system.event),
+      )?;
+
+      match system.node {
+        Some(node) => write!(f, "{}", JsonStr(node))?,
+        None => write!(f, "null")?,
+      }
+
+      write!(f, ", \"span\": {}}}", JsonSpan(&system.span))
+    })?;
+    writeln!(f, ",")?;
+
+    write_array(f, "states", self.data.states.values(), |f, state| {
+      write!(f, "{{\"name\": {}, \"systems\": [", JsonStr(state.name))?;
+      for (i, layer) in state.systems.iter().enumerate() {
+        if i > 0 {
+          write!(f, ", ")?;
+        }
+        write!(f, "{}", JsonStrs(layer))?;
+      }
+
+      write!(
+        f,
+        "], \"nodes\": {}, \"span\": {}}}",
+        JsonStrs(&state.nodes),
+        JsonSpan(&state.span),
+      )
+    })?;
+
+    writeln!(f, "\n}}")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{CstJson, JsonStr};
+  use crate::spec::Spec;
+
+  #[test]
+  fn test_json_str() {
+    assert_eq!(JsonStr("transform").to_string(), "\"transform\"");
+    assert_eq!(
+      JsonStr("a \"b\"\\c\n\u{1}").to_string(),
+      "\"a \\\"b\\\"\\\\c\\n\\u0001\""
+    );
+  }
+
+  #[test]
+  fn test_cst_json() {
+    let text = concat!(
+      "include <stdio.h>;\n",
+      "component pos { float x };\n",
+      "system s { pos };\n",
+      "state main { systems { { s } } };\n",
+    );
+    let spec = Spec::new("j.vecs", text.to_string()).expect("spec error");
+    let cst = spec.resolve().expect("resolve error");
+    let json = CstJson { data: &cst }.to_string();
+
+    let span = |start: (usize, usize, usize), end: (usize, usize, usize)| {
+      format!(
+        concat!(
+          "\"span\": {{\"file\": \"j.vecs\", ",
+          "\"start\": {{\"offset\": {}, \"line\": {}, \"column\": {}}}, ",
+          "\"end\": {{\"offset\": {}, \"line\": {}, \"column\": {}}}}}",
+        ),
+        start.0, start.1, start.2, end.0, end.1, end.2
+      )
+    };
+    let lines = json.lines().map(str::trim).collect::<Vec<_>>();
+
+    assert_eq!(lines[0], "{");
+    assert_eq!(lines[3], "\"includes\": [");
+    assert_eq!(
+      lines[4],
+      format!(
+        "{{\"path\": \"stdio.h\", \"system\": true, {}}}",
+        span((0, 1, 0), (17, 1, 17))
+      )
+    );
+    assert!(lines[8].starts_with("{\"name\": \"pos\", \"type\": null, \"fields\": [{\"name\": \"x\", \"type\": \"float\", "));
+    assert!(lines[8].contains(&span((19, 2, 0), (44, 2, 25))));
+    assert!(
+      lines[8].ends_with("\"marker\": false, \"mask_index\": 0, \"mask_bit\": 0}")
+    );
+    assert!(lines[14].starts_with("{\"name\": \"s\", \"components\": [\"pos\"], \"mask\": [\"0x0000000000000001\"], "));
+    assert_eq!(
+      lines[17],
+      format!(
+        "{{\"name\": \"s\", \"event\": \"frame\", \"node\": \"s\", {}}}",
+        span((46, 3, 0), (62, 3, 16))
+      )
+    );
+    assert_eq!(
+      lines[20],
+      format!(
+        "{{\"name\": \"main\", \"systems\": [[\"s\"]], \"nodes\": [\"s\"], {}}}",
+        span((64, 4, 0), (96, 4, 32))
+      )
+    );
+    assert_eq!(lines.last(), Some(&"}"));
+  }
+}
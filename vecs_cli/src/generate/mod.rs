@@ -1,13 +1,30 @@
 mod common;
+mod constants;
+mod cpp;
+mod dump;
 mod generics;
+mod graph;
 mod header;
 mod imple;
-mod constants;
+mod json;
+mod profile;
+mod reflection;
+mod rust;
+mod single_header;
+mod stubs;
 
 use std::io;
 
+use cpp::CppHeader;
+use graph::Graph;
 use header::Header;
+pub use header::RUNTIME_HEADER;
 use imple::Impl;
+use json::CstJson;
+use rust::RustModule;
+use single_header::SingleHeader;
+use stubs::Stubs;
+pub use stubs::defined_systems;
 
 use crate::resolve::cst::Cst;
 
@@ -24,3 +41,46 @@ pub fn generate_impl<W: io::Write>(
   let c = Impl { data, header_name };
   write!(w, "{}", c)
 }
+
+pub fn generate_single_header<W: io::Write>(data: &Cst, w: &mut W) -> io::Result<()> {
+  let h = SingleHeader { data };
+  write!(w, "{}", h)
+}
+
+pub fn generate_rust<W: io::Write>(data: &Cst, w: &mut W) -> io::Result<()> {
+  let rs = RustModule { data };
+  write!(w, "{}", rs)
+}
+
+pub fn generate_cpp<W: io::Write>(
+  data: &Cst,
+  header_name: &str,
+  w: &mut W,
+) -> io::Result<()> {
+  let hpp = CppHeader { data, header_name };
+  write!(w, "{}", hpp)
+}
+
+pub fn generate_stubs<W: io::Write>(
+  data: &Cst,
+  header_name: Option<&str>,
+  skip: &[String],
+  w: &mut W,
+) -> io::Result<()> {
+  let stubs = Stubs {
+    data,
+    header_name,
+    skip,
+  };
+  write!(w, "{}", stubs)
+}
+
+pub fn generate_graph<W: io::Write>(data: &Cst, w: &mut W) -> io::Result<()> {
+  let graph = Graph { data };
+  write!(w, "{}", graph)
+}
+
+pub fn generate_cst_json<W: io::Write>(data: &Cst, w: &mut W) -> io::Result<()> {
+  let json = CstJson { data };
+  write!(w, "{}", json)
+}
@@ -0,0 +1,307 @@
+use std::fmt::Display;
+
+use crate::resolve::cst::{Cst, Prefix};
+
+// Profiling instrumentation. Only generated if `settings.profile` is set.
+//
+// There is one counter per system, followed by one counter per phase of the
+// deferred operations flush, in the order of `PHASES`.
+
+pub const PHASES: [&str; 4] = [
+  "flush_add_entities",
+  "flush_add_components",
+  "flush_other",
+  "flush_remove_components",
+];
+
+// Index of the counter of a system.
+pub fn system_index(data: &Cst, system_name: &str) -> usize {
+  data
+    .systems
+    .keys()
+    .position(|name| *name == system_name)
+    .expect("system not found")
+}
+
+// Index of the counter of a flush phase.
+pub fn phase_index(data: &Cst, phase_name: &str) -> usize {
+  let phase = PHASES
+    .iter()
+    .position(|name| *name == phase_name)
+    .expect("phase not found");
+
+  data.systems.len() + phase
+}
+
+pub fn entries_len(data: &Cst) -> usize {
+  data.systems.len() + PHASES.len()
+}
+
+// For `clock_gettime`. Must come before any include.
+pub struct ProfilePosixSource;
+
+impl Display for ProfilePosixSource {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      concat!(
+        "#if !defined(_POSIX_C_SOURCE) || _POSIX_C_SOURCE < 199309L\n",
+        "#undef _POSIX_C_SOURCE\n",
+        "#define _POSIX_C_SOURCE 199309L\n",
+        "#endif\n",
+      )
+    )
+  }
+}
+
+pub struct ProfileHeader<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> Display for ProfileHeader<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    write!(
+      f,
+      concat!(
+        "// Profiling.\n",
+        "#define {PREFIX}_PROFILE 1\n",
+        "\n",
+        "typedef struct {prefix}_profile_entry {{\n",
+        "  const char *name;\n",
+        "  uint64_t calls;\n",
+        "  uint64_t total_ns;\n",
+        "  uint64_t max_ns;\n",
+        "}} {prefix}_profile_entry_t;\n",
+        "\n",
+      ),
+      prefix = prefix.lower,
+      PREFIX = prefix.upper,
+    )
+  }
+}
+
+// Goes in the engine struct.
+pub struct ProfileEngineField<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> Display for ProfileEngineField<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    write!(
+      f,
+      concat!(
+        "\n",
+        "  // One entry per system, then one per deferred operation flush phase\n",
+        "  {prefix}_profile_entry_t profile[{entries_len}];\n",
+      ),
+      entries_len = entries_len(self.data),
+      prefix = prefix.lower,
+    )
+  }
+}
+
+pub struct ProfileMethodsHeader<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> Display for ProfileMethodsHeader<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    write!(
+      f,
+      concat!(
+        "// Writes the accumulated timings to `out`.\n",
+        "void {prefix}_profile_report({prefix}_engine_t *e, FILE *out);\n",
+        "void {prefix}_profile_reset({prefix}_engine_t *e);\n",
+      ),
+      prefix = prefix.lower,
+    )
+  }
+}
+
+// Helpers used by the state loops. Must come before them.
+pub struct ProfileHelpersImpl<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> Display for ProfileHelpersImpl<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    write!(
+      f,
+      concat!(
+        "static inline uint64_t {prefix}_profile_now(void) {{\n",
+        "  struct timespec ts;\n",
+        "  clock_gettime(CLOCK_MONOTONIC, &ts);\n",
+        "  return (uint64_t)ts.tv_sec * 1000000000 + (uint64_t)ts.tv_nsec;\n",
+        "}}\n",
+        "static inline void {prefix}_profile_record({prefix}_profile_entry_t *entry, uint64_t start) {{\n",
+        "  uint64_t elapsed = {prefix}_profile_now() - start;\n",
+        "  entry->calls += 1;\n",
+        "  entry->total_ns += elapsed;\n",
+        "  if (elapsed > entry->max_ns)\n",
+        "    entry->max_ns = elapsed;\n",
+        "}}\n",
+      ),
+      prefix = prefix.lower,
+    )
+  }
+}
+
+// Goes in `vecs_init`.
+pub struct ProfileInitImpl<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> Display for ProfileInitImpl<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    let names = self.data.systems.keys().chain(PHASES.iter());
+
+    for (i, name) in names.enumerate() {
+      writeln!(
+        f,
+        "  e->profile[{}] = ({prefix}_profile_entry_t){{.name = \"{}\"}};",
+        i,
+        name,
+        prefix = prefix.lower,
+      )?;
+    }
+
+    Ok(())
+  }
+}
+
+pub struct ProfileMethodsImpl<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> Display for ProfileMethodsImpl<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    write!(
+      f,
+      concat!(
+        "void {prefix}_profile_report({prefix}_engine_t *e, FILE *out) {{\n",
+        "  fprintf(out, \"%-32s %10s %12s %12s %12s\\n\", \"name\", \"calls\", \"total ms\", \"avg us\", \"max us\");\n",
+        "  for (size_t i = 0; i < {entries_len}; ++i) {{\n",
+        "    {prefix}_profile_entry_t *entry = &e->profile[i];\n",
+        "    double avg_ns = entry->calls > 0 ? (double)entry->total_ns / entry->calls : 0;\n",
+        "    fprintf(out, \"%-32s %10llu %12.3f %12.3f %12.3f\\n\", entry->name, (unsigned long long)entry->calls,\n",
+        "            entry->total_ns / 1e6, avg_ns / 1e3, entry->max_ns / 1e3);\n",
+        "  }}\n",
+        "}}\n",
+        "void {prefix}_profile_reset({prefix}_engine_t *e) {{\n",
+        "  for (size_t i = 0; i < {entries_len}; ++i) {{\n",
+        "    e->profile[i].calls = 0;\n",
+        "    e->profile[i].total_ns = 0;\n",
+        "    e->profile[i].max_ns = 0;\n",
+        "  }}\n",
+        "}}\n",
+      ),
+      entries_len = entries_len(self.data),
+      prefix = prefix.lower,
+    )
+  }
+}
+
+// Wrap some code in `ProfileStart` and `ProfileEnd` to record its timing. The
+// state loop must declare `uint64_t profile_start`.
+pub struct ProfileStart<'a> {
+  pub prefix: &'a Prefix,
+  pub indent: usize,
+}
+
+impl<'a> Display for ProfileStart<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = self.prefix;
+
+    writeln!(
+      f,
+      "{:indent$}profile_start = {prefix}_profile_now();",
+      "",
+      indent = self.indent,
+      prefix = prefix.lower,
+    )
+  }
+}
+
+pub struct ProfileEnd<'a> {
+  pub prefix: &'a Prefix,
+  pub indent: usize,
+  pub index: usize,
+}
+
+impl<'a> Display for ProfileEnd<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = self.prefix;
+
+    writeln!(
+      f,
+      "{:indent$}{prefix}_profile_record(&e->profile[{index}], profile_start);",
+      "",
+      indent = self.indent,
+      index = self.index,
+      prefix = prefix.lower,
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{entries_len, phase_index, system_index};
+  use crate::{generate::generate_impl, spec::Spec};
+
+  #[test]
+  fn test_profile() {
+    let text = concat!(
+      "component pos { float x };\n",
+      "system tick;\n",
+      "system move { pos };\n",
+      "state main { systems { { tick; move } } };\n",
+    );
+    let spec = Spec::new("profile.vecs", text.to_string()).expect("spec error");
+    let mut cst = spec.resolve().expect("resolve error");
+    cst.settings.profile = true;
+
+    assert_eq!(system_index(&cst, "tick"), 0);
+    assert_eq!(system_index(&cst, "move"), 1);
+    assert_eq!(phase_index(&cst, "flush_add_entities"), 2);
+    assert_eq!(phase_index(&cst, "flush_remove_components"), 5);
+    assert_eq!(entries_len(&cst), 6);
+
+    let mut out = Vec::new();
+    generate_impl(&cst, "profile.h", &mut out).expect("generate error");
+    let out = String::from_utf8(out).expect("output is not UTF-8");
+
+    assert!(out.contains(concat!(
+      "    profile_start = vecs_profile_now();\n",
+      "    tick(e, ev);\n",
+      "    vecs_profile_record(&e->profile[0], profile_start);\n",
+    )));
+    assert!(out.contains(concat!(
+      "    profile_start = vecs_profile_now();\n",
+      "    nodes_len = e->nodes_move.len;\n",
+    )));
+    assert!(out.contains(concat!(
+      "      move(e, *node, ev);\n",
+      "    }\n",
+      "    vecs_profile_record(&e->profile[1], profile_start);\n",
+    )));
+    assert!(out.contains("vecs_profile_record(&e->profile[2], profile_start);"));
+
+    cst.settings.profile = false;
+    let mut out = Vec::new();
+    generate_impl(&cst, "profile.h", &mut out).expect("generate error");
+    let out = String::from_utf8(out).expect("output is not UTF-8");
+    assert!(!out.contains("profile_start"));
+  }
+}
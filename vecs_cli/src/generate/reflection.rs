@@ -0,0 +1,345 @@
+use std::fmt::Display;
+
+use crate::{
+  generate::{
+    common::{ComponentStructName, EventStructName},
+    constants::StateIdName,
+  },
+  resolve::cst::Cst,
+};
+
+// Static tables describing the schema, so tooling can be written against any engine.
+
+pub struct ReflectionHeader<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> Display for ReflectionHeader<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    write!(
+      f,
+      concat!(
+        "// Reflection.\n",
+        "typedef struct {prefix}_component_info {{\n",
+        "  const char *name;\n",
+        "  // Zero for marker components.\n",
+        "  size_t size;\n",
+        "  // mask[mask_i] & (1 << mask_j)\n",
+        "  uint16_t mask_i;\n",
+        "  uint8_t mask_j;\n",
+        "}} {prefix}_component_info_t;\n",
+        "\n",
+        "typedef struct {prefix}_event_info {{\n",
+        "  const char *name;\n",
+        "  size_t size;\n",
+        "}} {prefix}_event_info_t;\n",
+        "\n",
+        "typedef struct {prefix}_node_info {{\n",
+        "  const char *name;\n",
+        "  const char *const *components;\n",
+        "  size_t components_len;\n",
+        "}} {prefix}_node_info_t;\n",
+        "\n",
+        "typedef struct {prefix}_system_info {{\n",
+        "  const char *name;\n",
+        "  const char *event;\n",
+        "  // NULL for singleton systems.\n",
+        "  const char *node;\n",
+        "  const {prefix}_state_t *states;\n",
+        "  size_t states_len;\n",
+        "}} {prefix}_system_info_t;\n",
+        "\n",
+        "extern const {prefix}_component_info_t {prefix}_component_infos[];\n",
+        "extern const size_t {prefix}_component_infos_len;\n",
+        "extern const {prefix}_event_info_t {prefix}_event_infos[];\n",
+        "extern const size_t {prefix}_event_infos_len;\n",
+        "extern const {prefix}_node_info_t {prefix}_node_infos[];\n",
+        "extern const size_t {prefix}_node_infos_len;\n",
+        "extern const {prefix}_system_info_t {prefix}_system_infos[];\n",
+        "extern const size_t {prefix}_system_infos_len;\n",
+        "// Indexed by `{prefix}_state_t`.\n",
+        "extern const char *const {prefix}_state_names[{states_len}];\n",
+        "\n",
+      ),
+      states_len = self.data.states.len() + 1,
+      prefix = prefix.lower,
+    )
+  }
+}
+
+pub struct ReflectionImpl<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+// Zero-length arrays are not standard C, so empty tables get a dummy entry. Their
+// `_len` will be zero regardless.
+fn write_empty_entry<W: std::fmt::Write>(w: &mut W, len: usize) -> std::fmt::Result {
+  if len == 0 {
+    writeln!(w, "  {{0}},")?;
+  }
+
+  Ok(())
+}
+
+impl<'a> Display for ReflectionImpl<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    // Components:
+    writeln!(
+      f,
+      "const {prefix}_component_info_t {prefix}_component_infos[] = {{",
+      prefix = prefix.lower,
+    )?;
+
+    for component in self.data.components.values() {
+      let size = if component.is_empty() {
+        "0".to_string()
+      } else {
+        format!(
+          "sizeof({})",
+          ComponentStructName::new(prefix, component.name())
+        )
+      };
+
+      writeln!(
+        f,
+        "  {{.name = \"{}\", .size = {}, .mask_i = {}, .mask_j = {}}},",
+        component.name(),
+        size,
+        component.mask_i,
+        component.mask_j,
+      )?;
+    }
+
+    write_empty_entry(f, self.data.components.len())?;
+    writeln!(
+      f,
+      "}};\nconst size_t {prefix}_component_infos_len = {};\n",
+      self.data.components.len(),
+      prefix = prefix.lower,
+    )?;
+
+    // Events:
+    writeln!(
+      f,
+      "const {prefix}_event_info_t {prefix}_event_infos[] = {{",
+      prefix = prefix.lower,
+    )?;
+
+    for event in self.data.events.values() {
+      writeln!(
+        f,
+        "  {{.name = \"{}\", .size = sizeof({})}},",
+        event.name,
+        EventStructName::new(prefix, event.name),
+      )?;
+    }
+
+    write_empty_entry(f, self.data.events.len())?;
+    writeln!(
+      f,
+      "}};\nconst size_t {prefix}_event_infos_len = {};\n",
+      self.data.events.len(),
+      prefix = prefix.lower,
+    )?;
+
+    // Nodes:
+    for node in self.data.nodes.values() {
+      if node.components.is_empty() {
+        continue;
+      }
+
+      write!(
+        f,
+        "static const char *const {prefix}_node_{}_component_names[] = {{",
+        node.name,
+        prefix = prefix.lower,
+      )?;
+
+      for (i, component_name) in node.components.iter().enumerate() {
+        let sep = if i == 0 { "" } else { ", " };
+        write!(f, "{}\"{}\"", sep, component_name)?;
+      }
+
+      writeln!(f, "}};")?;
+    }
+
+    writeln!(
+      f,
+      "const {prefix}_node_info_t {prefix}_node_infos[] = {{",
+      prefix = prefix.lower,
+    )?;
+
+    for node in self.data.nodes.values() {
+      let components = if node.components.is_empty() {
+        "NULL".to_string()
+      } else {
+        format!(
+          "{prefix}_node_{}_component_names",
+          node.name,
+          prefix = prefix.lower,
+        )
+      };
+
+      writeln!(
+        f,
+        "  {{.name = \"{}\", .components = {}, .components_len = {}}},",
+        node.name,
+        components,
+        node.components.len(),
+      )?;
+    }
+
+    write_empty_entry(f, self.data.nodes.len())?;
+    writeln!(
+      f,
+      "}};\nconst size_t {prefix}_node_infos_len = {};\n",
+      self.data.nodes.len(),
+      prefix = prefix.lower,
+    )?;
+
+    // Systems:
+    let system_states = |system_name: &str| {
+      self
+        .data
+        .states
+        .values()
+        .filter(|state| state.systems.iter().flatten().any(|s| *s == system_name))
+        .collect::<Vec<_>>()
+    };
+
+    for system in self.data.systems.values() {
+      let states = system_states(system.name);
+
+      if states.is_empty() {
+        continue;
+      }
+
+      write!(
+        f,
+        "static const {prefix}_state_t {prefix}_system_{}_states[] = {{",
+        system.name,
+        prefix = prefix.lower,
+      )?;
+
+      for (i, state) in states.iter().enumerate() {
+        let sep = if i == 0 { "" } else { ", " };
+        write!(f, "{}{}", sep, StateIdName::new(prefix, state.name))?;
+      }
+
+      writeln!(f, "}};")?;
+    }
+
+    writeln!(
+      f,
+      "const {prefix}_system_info_t {prefix}_system_infos[] = {{",
+      prefix = prefix.lower,
+    )?;
+
+    for system in self.data.systems.values() {
+      let states_len = system_states(system.name).len();
+
+      let node = match system.node {
+        Some(node) => format!("\"{}\"", node),
+        None => "NULL".to_string(),
+      };
+
+      let states = if states_len == 0 {
+        "NULL".to_string()
+      } else {
+        format!(
+          "{prefix}_system_{}_states",
+          system.name,
+          prefix = prefix.lower,
+        )
+      };
+
+      writeln!(
+        f,
+        "  {{.name = \"{}\", .event = \"{}\", .node = {}, .states = {}, .states_len = {}}},",
+        system.name, system.event, node, states, states_len,
+      )?;
+    }
+
+    write_empty_entry(f, self.data.systems.len())?;
+    writeln!(
+      f,
+      "}};\nconst size_t {prefix}_system_infos_len = {};\n",
+      self.data.systems.len(),
+      prefix = prefix.lower,
+    )?;
+
+    // States:
+    writeln!(
+      f,
+      "const char *const {prefix}_state_names[{}] = {{\n  \"none\",",
+      self.data.states.len() + 1,
+      prefix = prefix.lower,
+    )?;
+
+    for state in self.data.states.values() {
+      writeln!(f, "  \"{}\",", state.name)?;
+    }
+
+    writeln!(f, "}};")?;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ReflectionImpl;
+  use crate::spec::Spec;
+
+  #[test]
+  fn test_reflection_tables() {
+    // Enough components to need a second mask word.
+    let mut text = (0..64)
+      .map(|i| format!("component c{};\n", i))
+      .collect::<String>();
+    text.push_str(concat!(
+      "component pos { float x };\n",
+      "system move { pos };\n",
+      "state main { systems { { move } } };\n",
+      "state menu { systems {} };\n",
+    ));
+
+    let spec = Spec::new("reflection.vecs", text).expect("spec error");
+    let cst = spec.resolve().expect("resolve error");
+    let tables = ReflectionImpl { data: &cst }.to_string();
+    let lines = tables.lines().collect::<Vec<_>>();
+
+    assert_eq!(
+      lines[0],
+      "const vecs_component_info_t vecs_component_infos[] = {"
+    );
+    assert_eq!(
+      lines[1],
+      "  {.name = \"c0\", .size = 0, .mask_i = 0, .mask_j = 0},"
+    );
+    assert_eq!(
+      lines[64],
+      "  {.name = \"c63\", .size = 0, .mask_i = 0, .mask_j = 63},"
+    );
+    assert_eq!(
+      lines[65],
+      "  {.name = \"pos\", .size = sizeof(vecs_component_pos_t), .mask_i = 1, .mask_j = 0},"
+    );
+    assert!(tables.contains("const size_t vecs_component_infos_len = 65;\n"));
+
+    assert!(tables.contains(
+      "static const vecs_state_t vecs_system_move_states[] = {VECS_STATE_MAIN};\n"
+    ));
+    assert!(tables.ends_with(concat!(
+      "const char *const vecs_state_names[3] = {\n",
+      "  \"none\",\n",
+      "  \"main\",\n",
+      "  \"menu\",\n",
+      "};\n",
+    )));
+  }
+}
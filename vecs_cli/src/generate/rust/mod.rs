@@ -0,0 +1,1188 @@
+pub mod names;
+
+use std::fmt::Display;
+
+use names::{RustIdent, rust_type};
+
+use crate::{
+  generate::common::UpperCamel,
+  resolve::cst::{Component, Cst, Node, TypeName},
+};
+
+// Rust backend. Generates a single module from the same `Cst` as the C backend, with
+// the same semantics, except that operations dispatch on the current state at
+// runtime instead of having one function per state.
+
+// Things that do not depend on the spec.
+const RUNTIME: &str = concat!(
+  "// Generated by vecs. Do not edit.\n",
+  "//\n",
+  "// Types written in C in the spec are mapped to Rust primitives when possible.\n",
+  "// Other types must be defined in the parent module, under their C name without\n",
+  "// `struct`, `enum` or `union`, e.g. `struct transform` refers to `super::transform`.\n",
+  "\n",
+  "#![allow(unused, non_camel_case_types, clippy::all)]\n",
+  "\n",
+  "use std::collections::VecDeque;\n",
+  "\n",
+  "/// Index and generation in one struct. Used for permanent references to entities and\n",
+  "/// components.\n",
+  "#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]\n",
+  "pub struct Id {\n",
+  "  pub index: u32,\n",
+  "  pub generation: u32,\n",
+  "}\n",
+  "\n",
+  "impl Id {\n",
+  "  pub const INVALID: Id = Id { index: u32::MAX, generation: u32::MAX };\n",
+  "\n",
+  "  pub fn is_invalid(self) -> bool {\n",
+  "    self == Self::INVALID\n",
+  "  }\n",
+  "}\n",
+  "\n",
+  "/// Refers to an entity or component that will only exist once the deferred operations\n",
+  "/// are applied, at the end of `Engine::run`.\n",
+  "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n",
+  "pub struct TmpId {\n",
+  "  pub index: u32,\n",
+  "}\n",
+  "\n",
+  "/// Payload of the built-in `frame` event.\n",
+  "#[derive(Debug, Clone, Copy, Default, PartialEq)]\n",
+  "pub struct Frame {\n",
+  "  pub delta: f32,\n",
+  "  pub runtime: f64,\n",
+  "  pub frame: u64,\n",
+  "}\n",
+  "\n",
+  "/// Values addressed by `Id`s. Removing a value leaves a hole that is reused by a later\n",
+  "/// push, under a new generation so that old `Id`s do not find the new value.\n",
+  "pub struct Sparse<T> {\n",
+  "  items: Vec<Option<T>>,\n",
+  "  generations: Vec<u32>,\n",
+  "  holes: Vec<u32>,\n",
+  "}\n",
+  "\n",
+  "impl<T> Default for Sparse<T> {\n",
+  "  fn default() -> Self {\n",
+  "    Self { items: Vec::new(), generations: Vec::new(), holes: Vec::new() }\n",
+  "  }\n",
+  "}\n",
+  "\n",
+  "impl<T> Sparse<T> {\n",
+  "  pub fn with_capacity(capacity: usize) -> Self {\n",
+  "    Self {\n",
+  "      items: Vec::with_capacity(capacity),\n",
+  "      generations: Vec::with_capacity(capacity),\n",
+  "      holes: Vec::new(),\n",
+  "    }\n",
+  "  }\n",
+  "\n",
+  "  pub fn push(&mut self, value: T) -> Id {\n",
+  "    if let Some(index) = self.holes.pop() {\n",
+  "      self.items[index as usize] = Some(value);\n",
+  "      return Id { index, generation: self.generations[index as usize] };\n",
+  "    }\n",
+  "\n",
+  "    let index = self.items.len() as u32;\n",
+  "    self.items.push(Some(value));\n",
+  "    self.generations.push(0);\n",
+  "    Id { index, generation: 0 }\n",
+  "  }\n",
+  "\n",
+  "  pub fn get(&self, id: Id) -> Option<&T> {\n",
+  "    match self.generations.get(id.index as usize) {\n",
+  "      Some(&generation) if generation == id.generation => self.items[id.index as usize].as_ref(),\n",
+  "      _ => None,\n",
+  "    }\n",
+  "  }\n",
+  "\n",
+  "  pub fn get_mut(&mut self, id: Id) -> Option<&mut T> {\n",
+  "    match self.generations.get(id.index as usize) {\n",
+  "      Some(&generation) if generation == id.generation => self.items[id.index as usize].as_mut(),\n",
+  "      _ => None,\n",
+  "    }\n",
+  "  }\n",
+  "\n",
+  "  pub fn remove(&mut self, id: Id) -> Option<T> {\n",
+  "    self.get(id)?;\n",
+  "\n",
+  "    let index = id.index as usize;\n",
+  "    self.generations[index] = self.generations[index].wrapping_add(1);\n",
+  "    self.holes.push(id.index);\n",
+  "    self.items[index].take()\n",
+  "  }\n",
+  "\n",
+  "  /// Live values and their `Id`s.\n",
+  "  pub fn iter(&self) -> impl Iterator<Item = (Id, &T)> {\n",
+  "    self.items.iter().zip(self.generations.iter()).enumerate().filter_map(\n",
+  "      |(index, (item, &generation))| {\n",
+  "        item.as_ref().map(|item| (Id { index: index as u32, generation }, item))\n",
+  "      },\n",
+  "    )\n",
+  "  }\n",
+  "}\n",
+  "\n",
+  "// An entity or a component scheduled to be added.\n",
+  "#[derive(Clone, Copy)]\n",
+  "enum EntityRef {\n",
+  "  Id(Id),\n",
+  "  Tmp(TmpId),\n",
+  "}\n",
+  "\n",
+  "impl EntityRef {\n",
+  "  fn resolve(self, new_entities: &[Id]) -> Id {\n",
+  "    match self {\n",
+  "      EntityRef::Id(id) => id,\n",
+  "      EntityRef::Tmp(tmp) => new_entities[tmp.index as usize],\n",
+  "    }\n",
+  "  }\n",
+  "}\n",
+  "\n",
+);
+
+fn component_t<'a>(component: &Component<'a>) -> UpperCamel<'a> {
+  UpperCamel::new(component.name(), "Component")
+}
+
+fn event_t<'a>(name: &'a str) -> UpperCamel<'a> {
+  UpperCamel::new(name, "Event")
+}
+
+fn node_t<'a>(name: &'a str) -> UpperCamel<'a> {
+  UpperCamel::new(name, "Node")
+}
+
+// Writes the Rust type of a component or event, named `name`.
+fn write_type<W: std::fmt::Write>(
+  w: &mut W,
+  name: impl Display,
+  typ: &TypeName,
+) -> std::fmt::Result {
+  if !typ.fields.is_empty() {
+    let fields: Vec<_> = typ
+      .fields
+      .iter()
+      .map(|field| (field.name, rust_type(&field.type_components)))
+      .collect();
+
+    // We can only derive traits we know the fields implement.
+    if fields.iter().all(|(_, (_, builtin))| *builtin) {
+      writeln!(w, "#[derive(Debug, Clone, Copy, Default, PartialEq)]")?;
+    }
+
+    writeln!(w, "pub struct {} {{", name)?;
+
+    for (field_name, (field_t, _)) in fields {
+      writeln!(w, "  pub {}: {},", RustIdent(field_name), field_t)?;
+    }
+
+    writeln!(w, "}}\n")
+  } else if !typ.type_components.is_empty() {
+    writeln!(
+      w,
+      "pub type {} = {};\n",
+      name,
+      rust_type(&typ.type_components).0
+    )
+  } else {
+    writeln!(w, "#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]")?;
+    writeln!(w, "pub struct {};\n", name)
+  }
+}
+
+// Writes an array literal of a mask.
+fn write_mask<W: std::fmt::Write>(
+  w: &mut W,
+  mask_size: u16,
+  mask: &[u64],
+) -> std::fmt::Result {
+  write!(w, "[")?;
+
+  for i in 0..mask_size as usize {
+    if i > 0 {
+      write!(w, ", ")?;
+    }
+
+    write!(w, "{:#x}", mask.get(i).unwrap_or(&0))?;
+  }
+
+  write!(w, "]")
+}
+
+pub struct RustModule<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> RustModule<'a> {
+  // States in which a node is kept up to date.
+  fn node_states(&self, node: &Node) -> Vec<UpperCamel<'a>> {
+    self
+      .data
+      .states
+      .values()
+      .filter(|state| state.nodes.contains(&node.name))
+      .map(|state| UpperCamel::new(state.name, ""))
+      .collect()
+  }
+
+  fn nodes_with(
+    &self,
+    component: &'a Component,
+  ) -> impl Iterator<Item = &'a Node<'a>> {
+    self
+      .data
+      .nodes
+      .values()
+      .filter(|node| node.components.contains(component.name()))
+  }
+
+  // Components of a node that have storage.
+  fn node_components(
+    &self,
+    node: &'a Node,
+  ) -> impl Iterator<Item = &'a Component<'a>> {
+    node
+      .components
+      .iter()
+      .map(|name| self.data.components.get(name).unwrap())
+      .filter(|component| !component.is_empty())
+  }
+
+  fn write_types(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let mask_size = self.data.node_mask_arr_size;
+
+    writeln!(f, "const MASK_LEN: usize = {};\n", mask_size)?;
+    writeln!(
+      f,
+      concat!(
+        "fn match_mask(outer: &[u64; MASK_LEN], inner: &[u64; MASK_LEN]) -> bool {{\n",
+        "  outer.iter().zip(inner.iter()).all(|(o, i)| o & i == *i)\n",
+        "}}\n",
+      )
+    )?;
+
+    writeln!(f, "// Globals.")?;
+    let globals: Vec<_> = self
+      .data
+      .globals
+      .values()
+      .map(|global| (global.name, rust_type(&global.type_components)))
+      .collect();
+
+    if globals.iter().all(|(_, (_, builtin))| *builtin) {
+      writeln!(f, "#[derive(Debug, Clone, Copy, Default, PartialEq)]")?;
+    }
+
+    writeln!(f, "pub struct Globals {{")?;
+    for (name, (typ, _)) in globals {
+      writeln!(f, "  pub {}: {},", RustIdent(name), typ)?;
+    }
+    writeln!(f, "}}\n")?;
+
+    for event in self.data.events.values() {
+      writeln!(f, "/// Event `{}`.", event.name)?;
+      write_type(f, event_t(event.name), event)?;
+    }
+
+    for component in self.data.components.values() {
+      if !component.is_empty() {
+        writeln!(f, "/// Component `{}`.", component.name())?;
+        write_type(f, component_t(component), &component.typ)?;
+      }
+
+      write!(
+        f,
+        "const COMPONENT_{}_MASK: [u64; MASK_LEN] = ",
+        component.name().to_ascii_uppercase()
+      )?;
+
+      let mut mask = vec![0; component.mask_i as usize + 1];
+      mask[component.mask_i as usize] = 1 << component.mask_j;
+      write_mask(f, mask_size, &mask)?;
+      writeln!(f, ";\n")?;
+    }
+
+    for node in self.data.nodes.values() {
+      writeln!(
+        f,
+        concat!(
+          "/// Node `{node_name}`. Present for every entity with all of its enabled\n",
+          "/// components, while in a state that uses it.\n",
+          "#[derive(Debug, Clone, Copy, PartialEq, Eq)]\n",
+          "pub struct {node_t} {{\n",
+          "  pub entity: Id,",
+        ),
+        node_name = node.name,
+        node_t = node_t(node.name),
+      )?;
+
+      for component in self.node_components(node) {
+        writeln!(f, "  pub {}_id: Id,", component.name())?;
+      }
+
+      writeln!(f, "}}\n")?;
+
+      writeln!(f, "impl {} {{", node_t(node.name))?;
+
+      for component in self.node_components(node) {
+        write!(
+          f,
+          concat!(
+            "  pub fn {method}<'e>(&self, engine: &'e Engine) -> &'e {component_t} {{\n",
+            "    engine.components_{component_name}.get(self.{component_name}_id).unwrap()\n",
+            "  }}\n",
+            "\n",
+            "  pub fn {component_name}_mut<'e>(&self, engine: &'e mut Engine) -> &'e mut {component_t} {{\n",
+            "    engine.components_{component_name}.get_mut(self.{component_name}_id).unwrap()\n",
+            "  }}\n",
+          ),
+          method = RustIdent(component.name()),
+          component_name = component.name(),
+          component_t = component_t(component),
+        )?;
+      }
+
+      writeln!(f, "}}\n")?;
+
+      write!(
+        f,
+        "const NODE_{}_MASK: [u64; MASK_LEN] = ",
+        node.name.to_ascii_uppercase()
+      )?;
+      write_mask(f, mask_size, &node.mask)?;
+      writeln!(f, ";\n")?;
+    }
+
+    writeln!(
+      f,
+      "#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]"
+    )?;
+    writeln!(f, "pub enum State {{\n  #[default]\n  None,")?;
+    for state in self.data.states.values() {
+      writeln!(f, "  {},", UpperCamel::new(state.name, ""))?;
+    }
+    writeln!(f, "}}\n")?;
+
+    writeln!(
+      f,
+      concat!(
+        "// Bookkeeping of an entity. Components are kept while disabled, while nodes are\n",
+        "// only kept while the current state uses them.\n",
+        "struct Entity {{\n",
+        "  mask: [u64; MASK_LEN],",
+      )
+    )?;
+
+    for component in self.data.components.values() {
+      if !component.is_empty() {
+        writeln!(f, "  component_{}: Option<Id>,", component.name())?;
+      }
+    }
+
+    for node in self.data.nodes.values() {
+      writeln!(f, "  node_{}: Option<u32>,", node.name)?;
+    }
+
+    writeln!(f, "}}\n")?;
+
+    writeln!(
+      f,
+      "impl Entity {{\n  fn new() -> Self {{\n    Self {{\n      mask: [0; MASK_LEN],"
+    )?;
+
+    for component in self.data.components.values() {
+      if !component.is_empty() {
+        writeln!(f, "      component_{}: None,", component.name())?;
+      }
+    }
+
+    for node in self.data.nodes.values() {
+      writeln!(f, "      node_{}: None,", node.name)?;
+    }
+
+    writeln!(f, "    }}\n  }}\n}}\n")
+  }
+
+  fn write_ops(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "// Deferred operations.\nenum OpAddComponent {{")?;
+    for component in self.data.components.values() {
+      let variant = UpperCamel::new(component.name(), "");
+
+      if component.is_empty() {
+        writeln!(f, "  {}(EntityRef),", variant)?;
+      } else {
+        writeln!(f, "  {}(EntityRef, {}),", variant, component_t(component))?;
+      }
+    }
+    writeln!(f, "}}\n")?;
+
+    writeln!(f, "enum OpOther {{")?;
+    for component in self.data.components.values() {
+      let variant = UpperCamel::new(component.name(), "");
+      let component_t = component_t(component);
+
+      writeln!(f, "  Enable{}(Id),\n  Disable{}(Id),", variant, variant)?;
+
+      if !component.is_empty() {
+        write!(
+          f,
+          concat!(
+            "  Upsert{variant}(Id, {component_t}),\n",
+            "  Update{variant}(Id, {component_t}),\n",
+            "  StoreEntityIn{variant}(TmpId, Id, fn(&mut {component_t}, Id)),\n",
+            "  StoreComponent{variant}(TmpId, Id, fn(&mut {component_t}, Id)),\n",
+          ),
+          variant = variant,
+          component_t = component_t,
+        )?;
+      }
+    }
+    writeln!(f, "}}\n")?;
+
+    writeln!(f, "enum OpRemoveComponent {{")?;
+    for component in self.data.components.values() {
+      writeln!(f, "  {}(Id),", UpperCamel::new(component.name(), ""))?;
+    }
+    writeln!(f, "}}\n")
+  }
+
+  fn write_systems(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(
+      f,
+      "/// One method per system. Called by `Engine::run` for the current state."
+    )?;
+    writeln!(f, "pub trait Systems {{")?;
+
+    for system in self.data.systems.values() {
+      if let Some(node) = system.node {
+        write!(
+          f,
+          concat!(
+            "  /// System `{system_name}`, on `{event_name}`, once per `{node_name}` node.\n",
+            "  fn {method}(&mut self, engine: &mut Engine, node: {node_t}, event: &{event_t});\n",
+          ),
+          system_name = system.name,
+          event_name = system.event,
+          node_name = node,
+          method = RustIdent(system.name),
+          node_t = node_t(node),
+          event_t = event_t(system.event),
+        )?;
+      } else {
+        write!(
+          f,
+          concat!(
+            "  /// System singleton `{system_name}`, on `{event_name}`.\n",
+            "  fn {method}(&mut self, engine: &mut Engine, event: &{event_t});\n",
+          ),
+          system_name = system.name,
+          event_name = system.event,
+          method = RustIdent(system.name),
+          event_t = event_t(system.event),
+        )?;
+      }
+    }
+
+    writeln!(f, "}}\n")
+  }
+
+  fn write_engine_struct(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(
+      f,
+      concat!(
+        "pub struct Engine {{\n",
+        "  state: State,\n",
+        "  pub globals: Globals,\n",
+        "  entities: Sparse<Entity>,",
+      )
+    )?;
+
+    for component in self.data.components.values() {
+      if !component.is_empty() {
+        writeln!(
+          f,
+          "  components_{}: Sparse<{}>,",
+          component.name(),
+          component_t(component)
+        )?;
+      }
+    }
+
+    for node in self.data.nodes.values() {
+      writeln!(f, "  nodes_{}: Vec<{}>,", node.name, node_t(node.name))?;
+    }
+
+    for event in self.data.events.values() {
+      writeln!(
+        f,
+        "  events_{}: VecDeque<{}>,",
+        event.name,
+        event_t(event.name)
+      )?;
+    }
+
+    writeln!(
+      f,
+      concat!(
+        "  // Deferred operations\n",
+        "  entities_to_add: u32,\n",
+        "  ops_add_component: VecDeque<OpAddComponent>,\n",
+        "  ops_other: VecDeque<OpOther>,\n",
+        "  ops_remove_component: VecDeque<OpRemoveComponent>,\n",
+        "}}\n",
+      )
+    )
+  }
+
+  fn write_engine_basics(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(
+      f,
+      concat!(
+        "  pub fn new(globals: Globals) -> Self {{\n",
+        "    Self {{\n",
+        "      state: State::None,\n",
+        "      globals,\n",
+        "      entities: Sparse::with_capacity({}),",
+      ),
+      self.data.settings.entity_capacity
+    )?;
+
+    for component in self.data.components.values() {
+      if !component.is_empty() {
+        writeln!(
+          f,
+          "      components_{}: Sparse::default(),",
+          component.name()
+        )?;
+      }
+    }
+
+    for node in self.data.nodes.values() {
+      writeln!(f, "      nodes_{}: Vec::new(),", node.name)?;
+    }
+
+    for event in self.data.events.values() {
+      writeln!(f, "      events_{}: VecDeque::new(),", event.name)?;
+    }
+
+    writeln!(
+      f,
+      concat!(
+        "      entities_to_add: 0,\n",
+        "      ops_add_component: VecDeque::new(),\n",
+        "      ops_other: VecDeque::new(),\n",
+        "      ops_remove_component: VecDeque::new(),\n",
+        "    }}\n",
+        "  }}\n",
+        "\n",
+        "  pub fn state(&self) -> State {{\n",
+        "    self.state\n",
+        "  }}\n",
+        "\n",
+        "  pub fn add_entity(&mut self) -> Id {{\n",
+        "    self.entities.push(Entity::new())\n",
+        "  }}\n",
+        "\n",
+        "  pub fn schedule_add_entity(&mut self) -> TmpId {{\n",
+        "    let id = TmpId {{ index: self.entities_to_add }};\n",
+        "    self.entities_to_add += 1;\n",
+        "    id\n",
+        "  }}\n",
+        "\n",
+        "  fn schedule_add_component(&mut self, op: OpAddComponent) -> TmpId {{\n",
+        "    let id = TmpId {{ index: self.ops_add_component.len() as u32 }};\n",
+        "    self.ops_add_component.push_back(op);\n",
+        "    id\n",
+        "  }}\n",
+      )
+    )?;
+
+    for event in self.data.events.values() {
+      writeln!(
+        f,
+        concat!(
+          "  pub fn emit_{event_name}(&mut self, event: {event_t}) {{\n",
+          "    self.events_{event_name}.push_back(event);\n",
+          "  }}\n",
+        ),
+        event_name = event.name,
+        event_t = event_t(event.name),
+      )?;
+    }
+
+    Ok(())
+  }
+
+  fn write_engine_nodes(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for node in self.data.nodes.values() {
+      let states = self.node_states(node);
+      let node_name = node.name;
+
+      writeln!(
+        f,
+        concat!(
+          "  pub fn nodes_{node_name}(&self) -> &[{node_t}] {{\n",
+          "    &self.nodes_{node_name}\n",
+          "  }}\n",
+        ),
+        node_name = node_name,
+        node_t = node_t(node_name),
+      )?;
+
+      write!(
+        f,
+        "  fn node_{}_active(state: State) -> bool {{\n    ",
+        node_name
+      )?;
+      if states.is_empty() {
+        writeln!(f, "false")?;
+      } else {
+        write!(f, "matches!(state, ")?;
+        for (i, state) in states.iter().enumerate() {
+          if i > 0 {
+            write!(f, " | ")?;
+          }
+          write!(f, "State::{}", state)?;
+        }
+        writeln!(f, ")")?;
+      }
+      writeln!(f, "  }}\n")?;
+
+      write!(
+        f,
+        concat!(
+          "  // Adds the `{node_name}` node of `entity` if it matches and is not there already.\n",
+          "  fn push_node_{node_name}(&mut self, entity: Id) {{\n",
+          "    let Some(ent) = self.entities.get(entity) else {{ return }};\n",
+          "    if ent.node_{node_name}.is_some() || !match_mask(&ent.mask, &NODE_{NODE_NAME}_MASK) {{\n",
+          "      return;\n",
+          "    }}\n",
+        ),
+        node_name = node_name,
+        NODE_NAME = node_name.to_ascii_uppercase(),
+      )?;
+
+      for component in self.node_components(node) {
+        writeln!(
+          f,
+          "    let Some({name}_id) = ent.component_{name} else {{ return }};",
+          name = component.name()
+        )?;
+      }
+
+      write!(f, "    let node = {} {{ entity", node_t(node_name))?;
+      for component in self.node_components(node) {
+        write!(f, ", {}_id", component.name())?;
+      }
+      writeln!(f, " }};")?;
+
+      write!(
+        f,
+        concat!(
+          "    let index = self.nodes_{node_name}.len() as u32;\n",
+          "    self.nodes_{node_name}.push(node);\n",
+          "    self.entities.get_mut(entity).unwrap().node_{node_name} = Some(index);\n",
+          "  }}\n",
+          "\n",
+          "  // Removes the `{node_name}` node of `entity`, if present.\n",
+          "  fn remove_node_{node_name}(&mut self, entity: Id) {{\n",
+          "    let Some(ent) = self.entities.get_mut(entity) else {{ return }};\n",
+          "    let Some(index) = ent.node_{node_name}.take() else {{ return }};\n",
+          "    self.nodes_{node_name}.swap_remove(index as usize);\n",
+          "\n",
+          "    // The last node took its place.\n",
+          "    if let Some(moved) = self.nodes_{node_name}.get(index as usize) {{\n",
+          "      if let Some(ent) = self.entities.get_mut(moved.entity) {{\n",
+          "        ent.node_{node_name} = Some(index);\n",
+          "      }}\n",
+          "    }}\n",
+          "  }}\n",
+          "\n",
+        ),
+        node_name = node_name,
+      )?;
+    }
+
+    // Transitions:
+    writeln!(
+      f,
+      concat!(
+        "  /// Builds the nodes used by `state` and drops the ones it doesn't use.\n",
+        "  pub fn set_state(&mut self, state: State) {{\n",
+        "    let old = self.state;\n",
+        "    self.state = state;\n",
+        "    let entities: Vec<Id> = self.entities.iter().map(|(id, _)| id).collect();",
+      )
+    )?;
+
+    for node in self.data.nodes.values() {
+      write!(
+        f,
+        concat!(
+          "\n",
+          "    let was_active = Self::node_{node_name}_active(old);\n",
+          "    let is_active = Self::node_{node_name}_active(state);\n",
+          "    if was_active && !is_active {{\n",
+          "      for node in self.nodes_{node_name}.drain(..) {{\n",
+          "        if let Some(ent) = self.entities.get_mut(node.entity) {{\n",
+          "          ent.node_{node_name} = None;\n",
+          "        }}\n",
+          "      }}\n",
+          "    }} else if is_active && !was_active {{\n",
+          "      for entity in entities.iter() {{\n",
+          "        self.push_node_{node_name}(*entity);\n",
+          "      }}\n",
+          "    }}\n",
+        ),
+        node_name = node.name,
+      )?;
+    }
+
+    writeln!(f, "  }}\n")
+  }
+
+  fn write_engine_components(
+    &self,
+    f: &mut std::fmt::Formatter<'_>,
+  ) -> std::fmt::Result {
+    for component in self.data.components.values() {
+      let name = component.name();
+
+      write!(
+        f,
+        concat!(
+          "  // Component `{name}`.\n",
+          "\n",
+          "  /// Whether `entity` has `{name}` enabled.\n",
+          "  pub fn has_component_{name}(&self, entity: Id) -> bool {{\n",
+          "    self\n",
+          "      .entities\n",
+          "      .get(entity)\n",
+          "      .is_some_and(|ent| match_mask(&ent.mask, &COMPONENT_{NAME}_MASK))\n",
+          "  }}\n",
+          "\n",
+          "  /// A disabled component does not produce nodes, but is kept.\n",
+          "  pub fn disable_component_{name}(&mut self, entity: Id) {{\n",
+        ),
+        name = name,
+        NAME = name.to_ascii_uppercase(),
+      )?;
+
+      for node in self.nodes_with(component) {
+        writeln!(f, "    self.remove_node_{}(entity);", node.name)?;
+      }
+
+      write!(
+        f,
+        concat!(
+          "    if let Some(ent) = self.entities.get_mut(entity) {{\n",
+          "      for (m, c) in ent.mask.iter_mut().zip(COMPONENT_{NAME}_MASK.iter()) {{\n",
+          "        *m &= !c;\n",
+          "      }}\n",
+          "    }}\n",
+          "  }}\n",
+          "\n",
+          "  pub fn enable_component_{name}(&mut self, entity: Id) {{\n",
+          "    let Some(ent) = self.entities.get_mut(entity) else {{ return }};\n",
+        ),
+        name = name,
+        NAME = name.to_ascii_uppercase(),
+      )?;
+
+      if !component.is_empty() {
+        writeln!(
+          f,
+          "    if ent.component_{}.is_none() {{\n      return;\n    }}",
+          name
+        )?;
+      }
+
+      write!(
+        f,
+        concat!(
+          "    for (m, c) in ent.mask.iter_mut().zip(COMPONENT_{NAME}_MASK.iter()) {{\n",
+          "      *m |= c;\n",
+          "    }}\n",
+        ),
+        NAME = name.to_ascii_uppercase(),
+      )?;
+
+      for node in self.nodes_with(component) {
+        writeln!(
+          f,
+          concat!(
+            "    if Self::node_{node_name}_active(self.state) {{\n",
+            "      self.push_node_{node_name}(entity);\n",
+            "    }}",
+          ),
+          node_name = node.name,
+        )?;
+      }
+
+      writeln!(f, "  }}\n")?;
+
+      if component.is_empty() {
+        write!(
+          f,
+          concat!(
+            "  pub fn add_component_{name}(&mut self, entity: Id) {{\n",
+            "    self.enable_component_{name}(entity);\n",
+            "  }}\n",
+            "\n",
+            "  /// Returns whether `entity` had `{name}` enabled.\n",
+            "  pub fn remove_component_{name}(&mut self, entity: Id) -> bool {{\n",
+            "    let had = self.has_component_{name}(entity);\n",
+            "    self.disable_component_{name}(entity);\n",
+            "    had\n",
+            "  }}\n",
+            "\n",
+            "  pub fn schedule_add_component_{name}(&mut self, entity: Id) {{\n",
+            "    self.schedule_add_component(OpAddComponent::{Name}(EntityRef::Id(entity)));\n",
+            "  }}\n",
+            "\n",
+            "  pub fn schedule_tmp_add_component_{name}(&mut self, entity: TmpId) {{\n",
+            "    self.schedule_add_component(OpAddComponent::{Name}(EntityRef::Tmp(entity)));\n",
+            "  }}\n",
+            "\n",
+          ),
+          name = name,
+          Name = UpperCamel::new(name, ""),
+        )?;
+      } else {
+        write!(
+          f,
+          concat!(
+            "  pub fn get_component_{name}(&self, component: Id) -> Option<&{component_t}> {{\n",
+            "    self.components_{name}.get(component)\n",
+            "  }}\n",
+            "\n",
+            "  pub fn get_component_{name}_mut(&mut self, component: Id) -> Option<&mut {component_t}> {{\n",
+            "    self.components_{name}.get_mut(component)\n",
+            "  }}\n",
+            "\n",
+            "  /// The `{name}` of `entity`, even if disabled.\n",
+            "  pub fn entity_component_{name}(&self, entity: Id) -> Option<Id> {{\n",
+            "    self.entities.get(entity)?.component_{name}\n",
+            "  }}\n",
+            "\n",
+            "  /// An entity has at most one `{name}`, so any previous one is removed.\n",
+            "  /// Returns `None` if `entity` does not exist.\n",
+            "  pub fn add_component_{name}(&mut self, entity: Id, component: {component_t}) -> Option<Id> {{\n",
+            "    self.entities.get(entity)?;\n",
+            "    self.remove_component_{name}(entity);\n",
+            "\n",
+            "    let id = self.components_{name}.push(component);\n",
+            "    self.entities.get_mut(entity).unwrap().component_{name} = Some(id);\n",
+            "    self.enable_component_{name}(entity);\n",
+            "    Some(id)\n",
+            "  }}\n",
+            "\n",
+            "  pub fn upsert_component_{name}(&mut self, entity: Id, component: {component_t}) -> Option<Id> {{\n",
+            "    match self.entity_component_{name}(entity) {{\n",
+            "      Some(id) => {{\n",
+            "        *self.components_{name}.get_mut(id).unwrap() = component;\n",
+            "        Some(id)\n",
+            "      }}\n",
+            "      None => self.add_component_{name}(entity, component),\n",
+            "    }}\n",
+            "  }}\n",
+            "\n",
+            "  /// Returns `None`, dropping `component`, if `entity` has no `{name}`.\n",
+            "  pub fn update_component_{name}(&mut self, entity: Id, component: {component_t}) -> Option<Id> {{\n",
+            "    let id = self.entity_component_{name}(entity)?;\n",
+            "    *self.components_{name}.get_mut(id).unwrap() = component;\n",
+            "    Some(id)\n",
+            "  }}\n",
+            "\n",
+            "  pub fn remove_component_{name}(&mut self, entity: Id) -> Option<{component_t}> {{\n",
+            "    self.disable_component_{name}(entity);\n",
+            "    let id = self.entities.get_mut(entity)?.component_{name}.take()?;\n",
+            "    self.components_{name}.remove(id)\n",
+            "  }}\n",
+            "\n",
+            "  pub fn schedule_add_component_{name}(&mut self, entity: Id, component: {component_t}) -> TmpId {{\n",
+            "    self.schedule_add_component(OpAddComponent::{Name}(EntityRef::Id(entity), component))\n",
+            "  }}\n",
+            "\n",
+            "  pub fn schedule_tmp_add_component_{name}(&mut self, entity: TmpId, component: {component_t}) -> TmpId {{\n",
+            "    self.schedule_add_component(OpAddComponent::{Name}(EntityRef::Tmp(entity), component))\n",
+            "  }}\n",
+            "\n",
+            "  pub fn schedule_upsert_component_{name}(&mut self, entity: Id, component: {component_t}) {{\n",
+            "    self.ops_other.push_back(OpOther::Upsert{Name}(entity, component));\n",
+            "  }}\n",
+            "\n",
+            "  pub fn schedule_update_component_{name}(&mut self, entity: Id, component: {component_t}) {{\n",
+            "    self.ops_other.push_back(OpOther::Update{Name}(entity, component));\n",
+            "  }}\n",
+            "\n",
+            "  /// Calls `store` with the `{name}` identified by `component` and the id of the\n",
+            "  /// new entity once it exists.\n",
+            "  pub fn schedule_store_entity_in_{name}(&mut self, tmp_entity: TmpId, component: Id, store: fn(&mut {component_t}, Id)) {{\n",
+            "    self.ops_other.push_back(OpOther::StoreEntityIn{Name}(tmp_entity, component, store));\n",
+            "  }}\n",
+            "\n",
+            "  /// Calls `store` with the `{name}` identified by `component` and the id of the\n",
+            "  /// new component once it exists.\n",
+            "  pub fn schedule_store_component_{name}(&mut self, tmp_component: TmpId, component: Id, store: fn(&mut {component_t}, Id)) {{\n",
+            "    self.ops_other.push_back(OpOther::StoreComponent{Name}(tmp_component, component, store));\n",
+            "  }}\n",
+            "\n",
+          ),
+          name = name,
+          Name = UpperCamel::new(name, ""),
+          component_t = component_t(component),
+        )?;
+      }
+
+      write!(
+        f,
+        concat!(
+          "  pub fn schedule_enable_component_{name}(&mut self, entity: Id) {{\n",
+          "    self.ops_other.push_back(OpOther::Enable{Name}(entity));\n",
+          "  }}\n",
+          "\n",
+          "  pub fn schedule_disable_component_{name}(&mut self, entity: Id) {{\n",
+          "    self.ops_other.push_back(OpOther::Disable{Name}(entity));\n",
+          "  }}\n",
+          "\n",
+          "  pub fn schedule_remove_component_{name}(&mut self, entity: Id) {{\n",
+          "    self.ops_remove_component.push_back(OpRemoveComponent::{Name}(entity));\n",
+          "  }}\n",
+          "\n",
+        ),
+        name = name,
+        Name = UpperCamel::new(name, ""),
+      )?;
+    }
+
+    Ok(())
+  }
+
+  fn write_engine_flush(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(
+      f,
+      concat!(
+        "  // Applies the deferred operations: adding entities, adding components, everything\n",
+        "  // else, then removing components.\n",
+        "  fn flush(&mut self) {{\n",
+        "    let new_entities: Vec<Id> = (0..self.entities_to_add).map(|_| self.add_entity()).collect();\n",
+        "    self.entities_to_add = 0;\n",
+        "\n",
+        "    let mut new_components = Vec::with_capacity(self.ops_add_component.len());\n",
+        "    while let Some(op) = self.ops_add_component.pop_front() {{\n",
+        "      let id = match op {{",
+      )
+    )?;
+
+    for component in self.data.components.values() {
+      let name = component.name();
+      let variant = UpperCamel::new(name, "");
+
+      if component.is_empty() {
+        write!(
+          f,
+          concat!(
+            "        OpAddComponent::{Name}(entity) => {{\n",
+            "          self.add_component_{name}(entity.resolve(&new_entities));\n",
+            "          Id::INVALID\n",
+            "        }}\n",
+          ),
+          name = name,
+          Name = variant,
+        )?;
+      } else {
+        write!(
+          f,
+          concat!(
+            "        OpAddComponent::{Name}(entity, component) => self\n",
+            "          .add_component_{name}(entity.resolve(&new_entities), component)\n",
+            "          .unwrap_or(Id::INVALID),\n",
+          ),
+          name = name,
+          Name = variant,
+        )?;
+      }
+    }
+
+    writeln!(
+      f,
+      concat!(
+        "      }};\n",
+        "      new_components.push(id);\n",
+        "    }}\n",
+        "\n",
+        "    while let Some(op) = self.ops_other.pop_front() {{\n",
+        "      match op {{",
+      )
+    )?;
+
+    for component in self.data.components.values() {
+      let name = component.name();
+
+      write!(
+        f,
+        concat!(
+          "        OpOther::Enable{Name}(entity) => self.enable_component_{name}(entity),\n",
+          "        OpOther::Disable{Name}(entity) => self.disable_component_{name}(entity),\n",
+        ),
+        name = name,
+        Name = UpperCamel::new(name, ""),
+      )?;
+
+      if !component.is_empty() {
+        write!(
+          f,
+          concat!(
+            "        OpOther::Upsert{Name}(entity, component) => {{\n",
+            "          self.upsert_component_{name}(entity, component);\n",
+            "        }}\n",
+            "        OpOther::Update{Name}(entity, component) => {{\n",
+            "          self.update_component_{name}(entity, component);\n",
+            "        }}\n",
+            "        OpOther::StoreEntityIn{Name}(tmp_entity, component, store) => {{\n",
+            "          let entity = new_entities[tmp_entity.index as usize];\n",
+            "          if let Some(component) = self.components_{name}.get_mut(component) {{\n",
+            "            store(component, entity);\n",
+            "          }}\n",
+            "        }}\n",
+            "        OpOther::StoreComponent{Name}(tmp_component, component, store) => {{\n",
+            "          let new_component = new_components[tmp_component.index as usize];\n",
+            "          if let Some(component) = self.components_{name}.get_mut(component) {{\n",
+            "            store(component, new_component);\n",
+            "          }}\n",
+            "        }}\n",
+          ),
+          name = name,
+          Name = UpperCamel::new(name, ""),
+        )?;
+      }
+    }
+
+    writeln!(
+      f,
+      concat!(
+        "      }}\n",
+        "    }}\n",
+        "\n",
+        "    while let Some(op) = self.ops_remove_component.pop_front() {{\n",
+        "      match op {{",
+      )
+    )?;
+
+    for component in self.data.components.values() {
+      writeln!(
+        f,
+        concat!(
+          "        OpRemoveComponent::{Name}(entity) => {{\n",
+          "          self.remove_component_{name}(entity);\n",
+          "        }}",
+        ),
+        name = component.name(),
+        Name = UpperCamel::new(component.name(), ""),
+      )?;
+    }
+
+    writeln!(f, "      }}\n    }}\n  }}\n")
+  }
+
+  fn write_engine_run(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(
+      f,
+      concat!(
+        "  /// Runs the systems of the current state on every queued event, then applies the\n",
+        "  /// deferred operations.\n",
+        "  pub fn run(&mut self, systems: &mut impl Systems) {{\n",
+        "    match self.state {{\n",
+        "      State::None => {{}}",
+      )
+    )?;
+
+    for state in self.data.states.values() {
+      writeln!(
+        f,
+        "      State::{} => self.run_state_{}(systems),",
+        UpperCamel::new(state.name, ""),
+        state.name
+      )?;
+    }
+
+    writeln!(f, "    }}\n\n    self.flush();\n  }}\n")?;
+
+    for state in self.data.states.values() {
+      writeln!(
+        f,
+        "  fn run_state_{}(&mut self, systems: &mut impl Systems) {{",
+        state.name
+      )?;
+
+      for event in self.data.events.values() {
+        let systems: Vec<_> = state
+          .systems
+          .iter()
+          .flatten()
+          .map(|name| self.data.systems.get(name).unwrap())
+          .filter(|system| system.event == event.name)
+          .collect();
+
+        // Events nobody listens to are still consumed.
+        if systems.is_empty() {
+          writeln!(f, "    self.events_{}.clear();", event.name)?;
+          continue;
+        }
+
+        writeln!(
+          f,
+          "    while let Some(event) = self.events_{}.pop_front() {{",
+          event.name
+        )?;
+
+        for system in systems {
+          if let Some(node) = system.node {
+            write!(
+              f,
+              concat!(
+                "      let len = self.nodes_{node_name}.len();\n",
+                "      for i in 0..len {{\n",
+                "        let Some(&node) = self.nodes_{node_name}.get(i) else {{ break }};\n",
+                "        systems.{method}(self, node, &event);\n",
+                "      }}\n",
+              ),
+              node_name = node,
+              method = RustIdent(system.name),
+            )?;
+          } else {
+            writeln!(f, "      systems.{}(self, &event);", RustIdent(system.name))?;
+          }
+        }
+
+        writeln!(f, "    }}")?;
+      }
+
+      writeln!(f, "  }}\n")?;
+    }
+
+    Ok(())
+  }
+}
+
+impl<'a> Display for RustModule<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(RUNTIME)?;
+
+    self.write_types(f)?;
+    self.write_ops(f)?;
+    self.write_systems(f)?;
+    self.write_engine_struct(f)?;
+
+    writeln!(f, "impl Engine {{")?;
+    self.write_engine_basics(f)?;
+    self.write_engine_nodes(f)?;
+    self.write_engine_components(f)?;
+    self.write_engine_flush(f)?;
+    self.write_engine_run(f)?;
+    writeln!(f, "}}")
+  }
+}
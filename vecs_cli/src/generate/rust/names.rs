@@ -0,0 +1,100 @@
+use std::fmt::Display;
+
+// Words that must be written as raw identifiers, e.g. a system called `move`.
+// `self`, `Self`, `super` and `crate` cannot be raw, but neither can they be names in
+// the spec without also breaking the C output, so we don't care about them.
+const KEYWORDS: [&str; 47] = [
+  "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+  "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+  "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override",
+  "priv", "pub", "ref", "return", "static", "struct", "trait", "true", "try", "type",
+  "typeof", "unsafe", "unsized", "use", "virtual", "where", "while",
+];
+
+// Formats a name from the spec so that it can be used as a Rust identifier.
+pub struct RustIdent<'a>(pub &'a str);
+
+impl<'a> Display for RustIdent<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if KEYWORDS.contains(&self.0) {
+      write!(f, "r#{}", self.0)
+    } else {
+      write!(f, "{}", self.0)
+    }
+  }
+}
+
+// The Rust type of a type written in C in the spec, along with whether it is one of
+// ours (a primitive or a runtime type), in which case we know which traits it
+// implements. Anything else must be defined in the parent module, under the name
+// it has in C without `struct`, `enum` or `union`.
+pub fn rust_type(type_components: &[&str]) -> (String, bool) {
+  let typ = type_components.join(" ");
+
+  let builtin = match typ.as_str() {
+    "char" | "signed char" => "i8",
+    "unsigned char" => "u8",
+    "short" | "short int" | "signed short" => "i16",
+    "unsigned short" | "unsigned short int" => "u16",
+    "int" | "signed" | "signed int" => "i32",
+    "unsigned" | "unsigned int" => "u32",
+    "long" | "long int" | "signed long" => "core::ffi::c_long",
+    "unsigned long" | "unsigned long int" => "core::ffi::c_ulong",
+    "long long" | "long long int" | "signed long long" => "i64",
+    "unsigned long long" | "unsigned long long int" => "u64",
+    "int8_t" => "i8",
+    "int16_t" => "i16",
+    "int32_t" => "i32",
+    "int64_t" => "i64",
+    "uint8_t" => "u8",
+    "uint16_t" => "u16",
+    "uint32_t" => "u32",
+    "uint64_t" => "u64",
+    "size_t" | "uintptr_t" => "usize",
+    "ssize_t" | "ptrdiff_t" | "intptr_t" => "isize",
+    "float" => "f32",
+    "double" => "f64",
+    "bool" | "_Bool" => "bool",
+    "vecs_id_t" => "Id",
+    "vecs_tmp_id_t" => "TmpId",
+    "vecs_frame_t" => "Frame",
+    _ => "",
+  };
+
+  if !builtin.is_empty() {
+    return (builtin.to_string(), true);
+  }
+
+  let name = type_components
+    .iter()
+    .copied()
+    .rfind(|c| !matches!(*c, "struct" | "enum" | "union" | "const"))
+    .unwrap_or("()");
+
+  (format!("super::{}", name), false)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{RustIdent, rust_type};
+
+  #[test]
+  fn test_rust_ident() {
+    assert_eq!(format!("{}", RustIdent("render")), "render");
+    assert_eq!(format!("{}", RustIdent("move")), "r#move");
+  }
+
+  #[test]
+  fn test_rust_type() {
+    assert_eq!(rust_type(&["unsigned", "int"]), ("u32".to_string(), true));
+    assert_eq!(rust_type(&["vecs_frame_t"]), ("Frame".to_string(), true));
+    assert_eq!(
+      rust_type(&["struct", "transform"]),
+      ("super::transform".to_string(), false)
+    );
+    assert_eq!(
+      rust_type(&["mouse_click_t"]),
+      ("super::mouse_click_t".to_string(), false)
+    );
+  }
+}
@@ -0,0 +1,51 @@
+use std::fmt::Display;
+
+use crate::{
+  generate::{header::Header, imple::Impl, profile::ProfilePosixSource},
+  resolve::cst::Cst,
+};
+
+// stb-style output: the declarations, with the runtime inlined, followed by the
+// implementation, which is only compiled where `{PREFIX}_IMPLEMENTATION` is defined.
+// Expects `settings.single_header` to be set.
+
+pub struct SingleHeader<'a> {
+  pub data: &'a Cst<'a>,
+}
+
+impl<'a> Display for SingleHeader<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    if self.data.settings.profile {
+      writeln!(f, "#ifdef {PREFIX}_IMPLEMENTATION", PREFIX = prefix.upper)?;
+      ProfilePosixSource.fmt(f)?;
+      writeln!(f, "#endif\n")?;
+    }
+
+    Header { data: self.data }.fmt(f)?;
+
+    write!(
+      f,
+      concat!(
+        "\n",
+        "#if defined({PREFIX}_IMPLEMENTATION) && !defined({PREFIX}_IMPLEMENTED)\n",
+        "#define {PREFIX}_IMPLEMENTED\n",
+        "\n",
+      ),
+      PREFIX = prefix.upper,
+    )?;
+
+    Impl {
+      data: self.data,
+      header_name: "",
+    }
+    .fmt(f)?;
+
+    writeln!(
+      f,
+      "\n#endif // {PREFIX}_IMPLEMENTATION",
+      PREFIX = prefix.upper
+    )
+  }
+}
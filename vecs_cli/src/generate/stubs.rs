@@ -0,0 +1,141 @@
+use std::{fmt::Display, sync::LazyLock};
+
+use regex::Regex;
+
+use crate::{
+  generate::common::{ComponentStructName, EventStructName, NodeStructName},
+  resolve::cst::Cst,
+};
+
+// Empty definitions of the systems, to be filled in by hand. Systems named in `skip`
+// are left out, which is how an existing file gets updated.
+pub struct Stubs<'a> {
+  pub data: &'a Cst<'a>,
+  // If set, the output starts a new file that includes this header. Otherwise it is
+  // only the stubs, meant to be appended to an existing file.
+  pub header_name: Option<&'a str>,
+  pub skip: &'a [String],
+}
+
+impl<'a> Display for Stubs<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let prefix = &self.data.settings.prefix;
+
+    if let Some(header_name) = self.header_name {
+      write!(
+        f,
+        concat!(
+          "// Definitions of the systems declared in the spec.\n",
+          "// Systems added later can be appended with `vecs_cli stubs --update`.\n",
+          "\n",
+          "#include \"{header_name}\"\n",
+        ),
+        header_name = header_name,
+      )?;
+    }
+
+    for system in self.data.systems.values() {
+      if self.skip.iter().any(|s| s == system.name) {
+        continue;
+      }
+
+      let event_t = EventStructName::new(prefix, system.event);
+
+      let Some(node_name) = system.node else {
+        write!(
+          f,
+          concat!(
+            "\n",
+            "// System singleton `{system_name}`, on `{event_name}`.\n",
+            "void {system_name}({prefix}_engine_t *engine, {event_t} event) {{\n",
+            "}}\n",
+          ),
+          system_name = system.name,
+          event_name = system.event,
+          event_t = event_t,
+          prefix = prefix.lower,
+        )?;
+        continue;
+      };
+
+      let node = self.data.nodes.get(node_name).expect("node not found");
+      let node_t = NodeStructName::new(prefix, node_name);
+
+      write!(
+        f,
+        concat!(
+          "\n",
+          "// System `{system_name}`, on `{event_name}`.\n",
+          "void {system_name}({prefix}_engine_t *engine, {node_t} node, {event_t} event) {{\n",
+        ),
+        system_name = system.name,
+        event_name = system.event,
+        node_t = node_t,
+        event_t = event_t,
+        prefix = prefix.lower,
+      )?;
+
+      for component_name in node.components.iter() {
+        let component = self
+          .data
+          .components
+          .get(component_name)
+          .expect("component not found");
+
+        if component.is_empty() {
+          writeln!(f, "  // `{}` is a marker, it has no data.", component_name)?;
+        } else {
+          writeln!(
+            f,
+            "  // {component_t} *{component_name} = {prefix}_node_{node_name}_get_{component_name}(engine, node);",
+            component_t = ComponentStructName::new(prefix, component_name),
+            component_name = component_name,
+            node_name = node_name,
+            prefix = prefix.lower,
+          )?;
+        }
+      }
+
+      writeln!(f, "}}")?;
+    }
+
+    Ok(())
+  }
+}
+
+static DEFINITION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"\bvoid\s+([A-Za-z_][A-Za-z0-9_]*)\s*\([^)]*\)\s*\{")
+    .expect("definition regex error")
+});
+
+// Names of the functions returning `void` that are defined in `c_src`, for when stubs
+// are appended to it. This doesn't understand C, but it is enough to find definitions
+// written like the stubs.
+pub fn defined_systems(c_src: &str) -> Vec<String> {
+  DEFINITION_REGEX
+    .captures_iter(c_src)
+    .map(|c| c[1].to_string())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::defined_systems;
+
+  #[test]
+  fn test_defined_systems() {
+    let c_src = concat!(
+      "#include \"vecs.h\"\n",
+      "\n",
+      "void render_init(vecs_engine_t *engine, vecs_event_frame_t event);\n",
+      "void move(vecs_engine_t *engine, vecs_node_move_t node,\n",
+      "          vecs_event_mouse_click_t event) {\n",
+      "}\n",
+      "static void helper(void)\n",
+      "{\n",
+      "}\n",
+    );
+
+    assert_eq!(defined_systems(c_src), vec!["move", "helper"]);
+  }
+}
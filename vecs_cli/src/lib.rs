@@ -0,0 +1,33 @@
+#![allow(irrefutable_let_patterns)]
+#![feature(trait_alias)]
+#![feature(formatting_options)]
+
+// The code generator as a library, e.g. for build scripts:
+//
+//   let spec = vecs_cli::Spec::read("engine.vecs")?;
+//   let cst = spec.resolve()?;
+//   vecs_cli::generate_header(&cst, &mut h_file)?;
+//   vecs_cli::generate_impl(&cst, "engine.h", &mut c_file)?;
+//
+// With the `build` feature, `build::Build` does all of this and compiles the result
+// with the `cc` crate.
+
+#[cfg(feature = "build")]
+pub mod build;
+pub mod common;
+mod error;
+pub mod format;
+pub mod generate;
+pub mod output;
+pub mod parse;
+pub mod resolve;
+mod spec;
+
+pub use error::{Error, ErrorLocation, MacroExpansion};
+pub use generate::{
+  RUNTIME_HEADER, generate_cpp, generate_header, generate_impl, generate_rust,
+  generate_single_header,
+};
+pub use parse::parse;
+pub use resolve::resolve;
+pub use spec::{Spec, set_prefix};
@@ -0,0 +1,365 @@
+use vecs_cli::{
+  parse::data::{
+    src::ParseSrc,
+    str::{Location, Span},
+  },
+  resolve::cst::{Cst, TypeName},
+};
+
+// Maps byte offsets to LSP positions and back. LSP lines are 0-based, characters are
+// UTF-16 code units, and lines end with `\n`, `\r\n` or `\r`, which is how the parser
+// tracks locations too.
+pub struct LineIndex<'a> {
+  text: &'a str,
+  // Where each character starts, and where the text ends.
+  locations: Vec<Location<'a>>,
+}
+
+impl<'a> LineIndex<'a> {
+  pub fn new(text: &'a str) -> Self {
+    let mut src = ParseSrc::from(text);
+    let mut locations = vec![src.location];
+
+    while src.next().is_some() {
+      locations.push(src.location);
+    }
+
+    Self { text, locations }
+  }
+
+  pub fn position(&self, offset: usize) -> (usize, usize) {
+    let i = self
+      .locations
+      .partition_point(|l| l.byte_offset <= offset)
+      .saturating_sub(1);
+    let location = self.locations[i];
+
+    (location.line - 1, location.column_utf16)
+  }
+
+  // Positions past the end of a line are clamped to it.
+  pub fn offset(&self, line: usize, character: usize) -> usize {
+    let start = self.locations.partition_point(|l| l.line <= line);
+    let mut end = self.text.len();
+
+    for location in self.locations[start..]
+      .iter()
+      .take_while(|l| l.line == line + 1)
+    {
+      if location.column_utf16 >= character {
+        return location.byte_offset;
+      }
+      end = location.byte_offset;
+    }
+
+    end
+  }
+}
+
+// A range of byte offsets.
+pub type Range = std::ops::Range<usize>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+  Global,
+  Component,
+  Event,
+  Node,
+  System,
+  State,
+}
+
+// Something declared in a spec, as it was the last time the spec resolved.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+  pub kind: SymbolKind,
+  pub name: String,
+  // Only the name, not the whole declaration.
+  pub range: Range,
+  // Markdown.
+  pub hover: String,
+}
+
+// The symbols declared in a spec. It doesn't borrow the text, so it can outlive it
+// and be used while the spec is being edited and doesn't resolve.
+#[derive(Debug, Clone, Default)]
+pub struct Analysis {
+  pub symbols: Vec<Symbol>,
+}
+
+impl Analysis {
+  pub fn new(text: &str, cst: &Cst) -> Self {
+    let mut symbols = Vec::new();
+    let mut add = |kind, name: &str, span: &Span, hover| {
+      // The built-in `frame` event isn't declared anywhere.
+      if span.file.is_some() {
+        symbols.push(Symbol {
+          kind,
+          name: name.to_string(),
+          range: name_range(text, span, name),
+          hover,
+        });
+      }
+    };
+
+    for global in cst.globals.values() {
+      let mut hover = format!("```\nglobal {} {}\n```", global.name, type_of(global));
+      push_doc(&mut hover, &global.doc);
+      add(SymbolKind::Global, global.name, &global.span, hover);
+    }
+
+    for component in cst.components.values() {
+      let name = component.name();
+      let (nodes, systems) = users(cst, name);
+
+      let mut hover = format!(
+        "```\ncomponent {} {}\n```\nmask index {}, bit {}",
+        name,
+        type_of(&component.typ),
+        component.mask_i,
+        component.mask_j,
+      );
+      push_doc(&mut hover, &component.typ.doc);
+      push_list(&mut hover, "Nodes", &nodes);
+      push_list(&mut hover, "Systems", &systems);
+
+      add(SymbolKind::Component, name, &component.span, hover);
+    }
+
+    for event in cst.events.values() {
+      let systems = cst
+        .systems
+        .values()
+        .filter(|s| s.event == event.name)
+        .map(|s| s.name)
+        .collect::<Vec<_>>();
+
+      let mut hover = format!("```\nevent {} {}\n```", event.name, type_of(event));
+      push_doc(&mut hover, &event.doc);
+      push_list(&mut hover, "Systems", &systems);
+
+      add(SymbolKind::Event, event.name, &event.span, hover);
+    }
+
+    for system in cst.systems.values() {
+      let mut hover = format!("```\nsystem {} on {}\n```", system.name, system.event);
+      push_doc(&mut hover, &system.doc);
+
+      if let Some(node) = system.node.and_then(|n| cst.nodes.get(n)) {
+        let components = node.components.iter().copied().collect::<Vec<_>>();
+        push_list(&mut hover, "Components", &components);
+      }
+
+      let states = cst
+        .states
+        .values()
+        .filter(|s| s.systems.iter().flatten().any(|n| *n == system.name))
+        .map(|s| s.name)
+        .collect::<Vec<_>>();
+      push_list(&mut hover, "States", &states);
+
+      add(SymbolKind::System, system.name, &system.span, hover);
+    }
+
+    for node in cst.nodes.values() {
+      // Systems declare a node with the same name, which is covered by the system.
+      if cst.systems.contains_key(node.name) {
+        continue;
+      }
+
+      let components = node.components.iter().copied().collect::<Vec<_>>();
+      let states = cst
+        .states
+        .values()
+        .filter(|s| s.nodes.contains(&node.name))
+        .map(|s| s.name)
+        .collect::<Vec<_>>();
+
+      let mut hover = format!("```\nnode {}\n```", node.name);
+      push_doc(&mut hover, &node.doc);
+      push_list(&mut hover, "Components", &components);
+      push_list(&mut hover, "States", &states);
+
+      add(SymbolKind::Node, node.name, &node.span, hover);
+    }
+
+    for state in cst.states.values() {
+      let mut hover = format!("```\nstate {}\n```", state.name);
+      push_doc(&mut hover, &state.doc);
+      for (i, layer) in state.systems.iter().enumerate() {
+        push_list(&mut hover, &format!("Layer {}", i), layer);
+      }
+      push_list(&mut hover, "Nodes", &state.nodes);
+
+      add(SymbolKind::State, state.name, &state.span, hover);
+    }
+
+    Self { symbols }
+  }
+
+  pub fn find(&self, name: &str) -> impl Iterator<Item = &Symbol> {
+    self.symbols.iter().filter(move |s| s.name == name)
+  }
+
+  pub fn components(&self) -> impl Iterator<Item = &Symbol> {
+    self
+      .symbols
+      .iter()
+      .filter(|s| s.kind == SymbolKind::Component)
+  }
+}
+
+// The nodes and systems requiring the component `name`.
+fn users<'src>(cst: &Cst<'src>, name: &str) -> (Vec<&'src str>, Vec<&'src str>) {
+  let (systems, nodes) = cst
+    .nodes
+    .values()
+    .filter(|n| n.components.contains(name))
+    .map(|n| n.name)
+    .partition(|n| cst.systems.contains_key(n));
+
+  (nodes, systems)
+}
+
+fn type_of(typ: &TypeName) -> String {
+  if !typ.fields.is_empty() {
+    let fields = typ
+      .fields
+      .iter()
+      .map(|f| format!("{} {};", f.type_components.join(" "), f.name))
+      .collect::<Vec<_>>();
+
+    format!("{{ {} }}", fields.join(" "))
+  } else if typ.type_components.is_empty() {
+    "(marker)".to_string()
+  } else {
+    typ.type_components.join(" ")
+  }
+}
+
+fn push_doc(hover: &mut String, doc: &[&str]) {
+  if !doc.is_empty() {
+    hover.push_str(&format!("\n\n{}", doc.join("\n")));
+  }
+}
+
+fn push_list(hover: &mut String, title: &str, names: &[&str]) {
+  if names.is_empty() {
+    return;
+  }
+
+  let names = names.iter().map(|n| format!("`{}`", n)).collect::<Vec<_>>();
+  hover.push_str(&format!("\n\n{}: {}", title, names.join(", ")));
+}
+
+fn is_ident(c: char) -> bool {
+  c.is_ascii_alphanumeric() || c == '_'
+}
+
+// The first occurrence of `name` as a whole word in the declaration, which is where
+// the declaration names it.
+fn name_range(text: &str, span: &Span, name: &str) -> Range {
+  let decl = &text[span.start_byte_offset..span.end_byte_offset];
+
+  decl
+    .match_indices(name)
+    .map(|(i, _)| span.start_byte_offset + i)
+    .find(|&start| word_at(text, start).is_some_and(|r| r.len() == name.len()))
+    .map(|start| start..start + name.len())
+    .unwrap_or(span.start_byte_offset..span.end_byte_offset)
+}
+
+// The identifier containing `offset`, or ending right at it.
+pub fn word_at(text: &str, offset: usize) -> Option<Range> {
+  let before = &text[..offset];
+  let start = before
+    .char_indices()
+    .rev()
+    .take_while(|(_, c)| is_ident(*c))
+    .last()
+    .map_or(offset, |(i, _)| i);
+  let end = text[offset..]
+    .find(|c| !is_ident(c))
+    .map_or(text.len(), |i| offset + i);
+
+  (start < end).then_some(start..end)
+}
+
+// Whether `offset` is inside the braces of a `system` or `node` declaration, where the
+// components go. Comments are expected to have been stripped.
+pub fn in_component_list(text: &str, offset: usize) -> bool {
+  let mut open_braces = Vec::new();
+
+  for (i, c) in text[..offset].char_indices() {
+    match c {
+      '{' => open_braces.push(i),
+      '}' => {
+        open_braces.pop();
+      }
+      _ => {}
+    }
+  }
+
+  // Declarations are only one level deep, a list inside another one is something else,
+  // like a layer of systems in a state.
+  let [brace] = open_braces[..] else {
+    return false;
+  };
+
+  let statement_start = text[..brace].rfind([';', '}']).map_or(0, |i| i + 1);
+  let tag = text[statement_start..brace].split_whitespace().next();
+
+  matches!(tag, Some("system" | "node"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{LineIndex, in_component_list, word_at};
+
+  #[test]
+  fn test_line_index() {
+    let text = "ab\nc\u{1F600}d\n";
+    let index = LineIndex::new(text);
+
+    assert_eq!(index.position(0), (0, 0));
+    assert_eq!(index.position(3), (1, 0));
+    assert_eq!(index.position(8), (1, 3));
+    assert_eq!(index.position(text.len()), (2, 0));
+
+    assert_eq!(index.offset(1, 3), 8);
+    assert_eq!(index.offset(0, 10), 2);
+    assert_eq!(index.offset(5, 0), text.len());
+
+    let text = "a\r\nb\rc\n";
+    let index = LineIndex::new(text);
+
+    assert_eq!(index.position(3), (1, 0));
+    assert_eq!(index.position(5), (2, 0));
+    assert_eq!(index.position(6), (2, 1));
+    assert_eq!(index.offset(0, 5), 1);
+    assert_eq!(index.offset(1, 5), 4);
+  }
+
+  #[test]
+  fn test_word_at() {
+    let text = "node n { transform };";
+
+    assert_eq!(word_at(text, 10), Some(9..18));
+    assert_eq!(word_at(text, 18), Some(9..18));
+    assert_eq!(word_at(text, 7), None);
+  }
+
+  #[test]
+  fn test_in_component_list() {
+    let text = concat!(
+      "component a; system s on e { a; ",
+      "}; state main { systems { { s } }; };",
+    );
+
+    assert!(in_component_list(text, 29));
+    assert!(in_component_list(text, 32));
+    assert!(!in_component_list(text, 12));
+    assert!(!in_component_list(text, 34));
+    assert!(!in_component_list(text, 61));
+  }
+}
@@ -0,0 +1,395 @@
+mod analysis;
+
+use std::{
+  collections::HashMap,
+  io::{self, BufRead, Write},
+};
+
+use serde_json::{Value, json};
+use vecs_cli::{
+  parse::{
+    ast::Ast,
+    comments::attach_comments,
+    data::src::ParseSrc,
+    parse, strip_comments,
+    tokens::{Token, tokenize},
+  },
+  resolve::resolve,
+};
+
+use analysis::{Analysis, LineIndex, Range, in_component_list, word_at};
+
+// A language server for specs, speaking JSON-RPC over stdin and stdout. Documents are
+// synced in full, and analyzed on every change: parsing and resolution errors become
+// diagnostics, and the declarations are kept for definitions, hovers and completions.
+// When a document doesn't resolve, the declarations from the last time it did are
+// kept.
+pub fn serve() -> Result<(), String> {
+  let mut server = Server::default();
+  let mut input = io::stdin().lock();
+
+  loop {
+    let Some(message) = read_message(&mut input)
+      .map_err(|e| format!("failed to read message: {}", e))?
+    else {
+      // The client went away without asking us to exit.
+      return Err("unexpected end of input".to_string());
+    };
+
+    if server.handle(message)? {
+      return Ok(());
+    }
+  }
+}
+
+#[derive(Default)]
+struct Document {
+  text: String,
+  // The text with comments stripped, which is what spans point into.
+  stripped: String,
+  analysis: Analysis,
+}
+
+#[derive(Default)]
+struct Server {
+  documents: HashMap<String, Document>,
+  shutdown: bool,
+}
+
+// LSP error codes.
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_REQUEST: i64 = -32600;
+
+impl Server {
+  // Returns whether the server should exit.
+  fn handle(&mut self, message: Value) -> Result<bool, String> {
+    let method = message["method"].as_str().unwrap_or_default();
+    let params = &message["params"];
+    let id = message.get("id").cloned();
+
+    let result = match method {
+      "initialize" => Some(json!({
+        "capabilities": {
+          // Full sync.
+          "textDocumentSync": 1,
+          "definitionProvider": true,
+          "hoverProvider": true,
+          "completionProvider": { "triggerCharacters": ["{", ";"] },
+        },
+        "serverInfo": { "name": "vecs", "version": env!("CARGO_PKG_VERSION") },
+      })),
+      "shutdown" => {
+        self.shutdown = true;
+        Some(Value::Null)
+      }
+      "exit" => {
+        if self.shutdown {
+          return Ok(true);
+        }
+        return Err("exit without shutdown".to_string());
+      }
+      "textDocument/didOpen" => {
+        let document = &params["textDocument"];
+        self.update(uri(params), str_of(&document["text"]))?;
+        None
+      }
+      "textDocument/didChange" => {
+        // With full sync, the last change has the whole text.
+        let changes = params["contentChanges"].as_array();
+        if let Some(change) = changes.and_then(|c| c.last()) {
+          self.update(uri(params), str_of(&change["text"]))?;
+        }
+        None
+      }
+      "textDocument/didClose" => {
+        self.documents.remove(uri(params));
+        publish_diagnostics(uri(params), Vec::new())?;
+        None
+      }
+      "textDocument/definition" => Some(self.definition(params)),
+      "textDocument/hover" => Some(self.hover(params)),
+      "textDocument/completion" => Some(self.completion(params)),
+      _ => None,
+    };
+
+    // Notifications don't have an id, and never get a response.
+    let Some(id) = id else {
+      return Ok(false);
+    };
+
+    let response = match result {
+      _ if self.shutdown && method != "shutdown" => json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": INVALID_REQUEST, "message": "the server is shutting down" },
+      }),
+      Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+      None => json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+          "code": METHOD_NOT_FOUND,
+          "message": format!("unsupported method `{}`", method),
+        },
+      }),
+    };
+
+    write_message(&response)
+      .map_err(|e| format!("failed to write response: {}", e))?;
+    Ok(false)
+  }
+
+  // Analyzes the new text of a document and publishes its diagnostics.
+  fn update(&mut self, uri: &str, text: &str) -> Result<(), String> {
+    let document = self.documents.entry(uri.to_string()).or_default();
+    document.text = text.to_string();
+
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    let index = LineIndex::new(&document.text);
+    let mut diagnostics = Vec::new();
+
+    let tokens = tokenize(ParseSrc::new(Some(path), &document.text));
+    document.stripped = match strip_comments(&document.text, &tokens) {
+      Ok(stripped) => stripped,
+      Err(e) => {
+        // Only the `/*` is highlighted, since the rest of the spec is the comment. The
+        // comment isn't blanked out, but definitions and hovers still line up.
+        let offset = e.location().byte_offset;
+        diagnostics.push(diagnostic(&index, offset..offset + 2, ERROR, e.message()));
+        document.stripped = document.text.clone();
+        return publish_diagnostics(uri, diagnostics);
+      }
+    };
+
+    match parse(ParseSrc::new(Some(path), &document.stripped)) {
+      Err(e) => {
+        let offset = e.location().byte_offset;
+        let range = word_at(&document.stripped, offset).unwrap_or(offset..offset);
+        diagnostics.push(diagnostic(&index, range, ERROR, e.message()));
+      }
+      Ok(ast) => match resolve(with_comments(ast.value, &document.text, &tokens)) {
+        Err(e) => {
+          // The calls of the macros the error is in.
+          let mut related = e
+            .expansions()
+            .iter()
+            .map(|expansion| {
+              let call =
+                expansion.call.start_byte_offset..expansion.call.end_byte_offset;
+              json!({
+                "location": { "uri": uri, "range": range(&index, call) },
+                "message": format!("in expansion of macro `{}`", expansion.name),
+              })
+            })
+            .collect::<Vec<_>>();
+          related.dedup();
+
+          let span = e.span();
+          let range = span.start_byte_offset..span.end_byte_offset;
+          let mut diagnostic = diagnostic(&index, range, ERROR, e.message());
+          if !related.is_empty() {
+            diagnostic["relatedInformation"] = Value::Array(related);
+          }
+          diagnostics.push(diagnostic);
+        }
+        Ok(cst) => {
+          for system in cst.systems.values() {
+            if system.in_state_count == 0 {
+              let range = system.span.start_byte_offset..system.span.end_byte_offset;
+              let message = format!(
+                "system {} is not referenced in any state. it will not be invoked.",
+                system.name
+              );
+              diagnostics.push(diagnostic(&index, range, WARNING, &message));
+            }
+          }
+
+          document.analysis = Analysis::new(&document.stripped, &cst);
+        }
+      },
+    }
+
+    publish_diagnostics(uri, diagnostics)
+  }
+
+  // The document and offset a `TextDocumentPositionParams` points at.
+  fn document_at(&self, params: &Value) -> Option<(&Document, usize)> {
+    let document = self.documents.get(uri(params))?;
+    let position = &params["position"];
+    let offset = LineIndex::new(&document.text).offset(
+      position["line"].as_u64()? as usize,
+      position["character"].as_u64()? as usize,
+    );
+
+    Some((document, offset))
+  }
+
+  // Every declaration with the name under the cursor. Only systems and their nodes
+  // can share names, and the node isn't a symbol of its own.
+  fn definition(&self, params: &Value) -> Value {
+    let Some((document, offset)) = self.document_at(params) else {
+      return Value::Null;
+    };
+    let Some(word) = word_at(&document.stripped, offset) else {
+      return Value::Null;
+    };
+
+    let index = LineIndex::new(&document.text);
+    let locations = document
+      .analysis
+      .find(&document.stripped[word])
+      .map(|s| json!({ "uri": uri(params), "range": range(&index, s.range.clone()) }))
+      .collect::<Vec<_>>();
+
+    Value::Array(locations)
+  }
+
+  fn hover(&self, params: &Value) -> Value {
+    let Some((document, offset)) = self.document_at(params) else {
+      return Value::Null;
+    };
+    let Some(word) = word_at(&document.stripped, offset) else {
+      return Value::Null;
+    };
+
+    let hovers = document
+      .analysis
+      .find(&document.stripped[word.clone()])
+      .map(|s| s.hover.as_str())
+      .collect::<Vec<_>>();
+
+    if hovers.is_empty() {
+      return Value::Null;
+    }
+
+    let index = LineIndex::new(&document.text);
+    json!({
+      "contents": { "kind": "markdown", "value": hovers.join("\n\n---\n\n") },
+      "range": range(&index, word),
+    })
+  }
+
+  // Component names, inside the braces of systems and nodes.
+  fn completion(&self, params: &Value) -> Value {
+    let Some((document, offset)) = self.document_at(params) else {
+      return Value::Null;
+    };
+
+    if !in_component_list(&document.stripped, offset) {
+      return Value::Null;
+    }
+
+    let items = document
+      .analysis
+      .components()
+      .map(|s| {
+        json!({
+          "label": s.name,
+          // Class.
+          "kind": 7,
+          "documentation": { "kind": "markdown", "value": s.hover },
+        })
+      })
+      .collect::<Vec<_>>();
+
+    Value::Array(items)
+  }
+}
+
+// Diagnostic severities.
+const ERROR: u8 = 1;
+const WARNING: u8 = 2;
+
+fn diagnostic(index: &LineIndex, r: Range, severity: u8, message: &str) -> Value {
+  json!({
+    "range": range(index, r),
+    "severity": severity,
+    "source": "vecs",
+    "message": message,
+  })
+}
+
+// Attaches the comments of `text` to the AST parsed from it, for doc comments.
+fn with_comments<'src>(
+  mut ast: Ast<'src>,
+  text: &'src str,
+  tokens: &[Token<'src>],
+) -> Ast<'src> {
+  attach_comments(&mut ast, text, tokens);
+  ast
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Result<(), String> {
+  let notification = json!({
+    "jsonrpc": "2.0",
+    "method": "textDocument/publishDiagnostics",
+    "params": { "uri": uri, "diagnostics": diagnostics },
+  });
+
+  write_message(&notification)
+    .map_err(|e| format!("failed to publish diagnostics: {}", e))
+}
+
+fn range(index: &LineIndex, range: Range) -> Value {
+  let (start_line, start_character) = index.position(range.start);
+  let (end_line, end_character) = index.position(range.end);
+
+  json!({
+    "start": { "line": start_line, "character": start_character },
+    "end": { "line": end_line, "character": end_character },
+  })
+}
+
+fn uri(params: &Value) -> &str {
+  str_of(&params["textDocument"]["uri"])
+}
+
+fn str_of(value: &Value) -> &str {
+  value.as_str().unwrap_or_default()
+}
+
+// Reads a message framed by a `Content-Length` header. Returns `None` at the end of
+// the input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+  let mut content_length = None;
+
+  loop {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+      return Ok(None);
+    }
+
+    let line = line.trim_end();
+    if line.is_empty() {
+      break;
+    }
+
+    if let Some((name, value)) = line.split_once(':')
+      && name.eq_ignore_ascii_case("content-length")
+    {
+      content_length = value.trim().parse::<usize>().ok();
+    }
+  }
+
+  let Some(content_length) = content_length else {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidData,
+      "missing Content-Length header",
+    ));
+  };
+
+  let mut content = vec![0; content_length];
+  input.read_exact(&mut content)?;
+
+  serde_json::from_slice(&content)
+    .map(Some)
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message(message: &Value) -> io::Result<()> {
+  let content = message.to_string();
+  let mut out = io::stdout().lock();
+
+  write!(out, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+  out.flush()
+}
@@ -1,79 +1,288 @@
-#![allow(irrefutable_let_patterns)]
-#![feature(trait_alias)]
-#![feature(formatting_options)]
-
 mod cli;
-mod common;
-mod generate;
-mod parse;
-mod resolve;
+mod lsp;
+#[cfg(target_os = "linux")]
+mod watch;
 
 use std::{
-  env,
-  fs::{self, File, OpenOptions},
-  io::{self, Write, stdout},
-  path::PathBuf,
+  env, fs,
+  io::{self, Write},
+  path::{Path, PathBuf},
+  process,
   str::FromStr,
 };
 
 use clap::Parser as _;
-use generate::{generate_header, generate_impl};
-
-use crate::{
-  cli::Cli,
-  parse::{data::src::ParseSrc, parse, strip_comments},
-  resolve::resolve,
+use vecs_cli::{
+  Spec,
+  format::format,
+  generate::{
+    defined_systems, generate_cpp, generate_cst_json, generate_graph,
+    generate_header, generate_impl, generate_rust, generate_single_header,
+    generate_stubs,
+  },
+  output::{Depfile, Output, include_deps},
+  resolve,
+  resolve::cst::Cst,
+  set_prefix,
 };
 
+use crate::cli::{Cli, Command, Emit, FmtArgs, GraphArgs, StubsArgs, Target};
+
 fn main() {
   let cli = Cli::parse();
 
-  let mut src_str = fs::read_to_string(&cli.source).expect("error reading file");
-  strip_comments(&mut src_str);
+  let result = match &cli.command {
+    Some(Command::Stubs(args)) => stubs(args),
+    Some(Command::Graph(args)) => graph(args),
+    Some(Command::Lsp) => lsp::serve(),
+    Some(Command::Fmt(args)) => fmt(args),
+    None => {
+      // Clap requires a source when there is no subcommand.
+      let source = cli.source.as_deref().unwrap();
+
+      if cli.watch {
+        watch(&cli, source)
+      } else {
+        run(&cli, source)
+      }
+    }
+  };
+
+  if let Err(e) = result {
+    eprintln!("{}", e);
+    process::exit(1);
+  }
+}
+
+// Only returns if watching fails.
+#[cfg(target_os = "linux")]
+fn watch(cli: &Cli, source: &str) -> Result<(), String> {
+  let mut watcher = watch::Watcher::new(&[Path::new(source)])
+    .map_err(|e| format!("failed to watch `{}`: {}", source, e))?;
+
+  loop {
+    // On error, the outputs of the last successful run are left as they are.
+    match run(cli, source) {
+      Ok(()) => eprintln!("generated from `{}`", source),
+      Err(e) => eprintln!("{}", e),
+    }
+
+    watcher
+      .wait()
+      .map_err(|e| format!("failed to watch `{}`: {}", source, e))?;
+  }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn watch(_: &Cli, _: &str) -> Result<(), String> {
+  Err("--watch is only supported on Linux".to_string())
+}
 
-  let src = ParseSrc::new(Some(&cli.source), &src_str);
-  let ast = parse(src).expect("parsing error").value;
+// Reads, parses and resolves `source`, then hands the CST to `f`. It's done this way
+// because the CST borrows from the source.
+fn with_cst<T>(
+  source: &str,
+  prefix: Option<&str>,
+  f: impl FnOnce(Cst) -> Result<T, String>,
+) -> Result<T, String> {
+  let spec = Spec::read(source).map_err(|e| e.to_string())?;
+  let ast = spec.parse().map_err(|e| e.to_string())?;
 
   let debug_ast = env::var("VECS_DEBUG_AST").is_ok_and(|e| !e.is_empty());
   if debug_ast {
     println!("{}", ast);
   }
 
-  let cst = resolve(ast).expect("resolving error");
+  let mut cst = resolve(ast).map_err(|e| vecs_cli::Error::from(e).to_string())?;
+
+  if let Some(prefix) = prefix {
+    set_prefix(&mut cst, prefix).map_err(|e| e.to_string())?;
+  }
+
+  f(cst)
+}
+
+// Writes the system stubs. With `--update`, the ones that are missing are appended
+// to the existing file instead.
+fn stubs(args: &StubsArgs) -> Result<(), String> {
+  with_cst(&args.source, args.prefix.as_deref(), |cst| {
+    if args.update && args.output == "-" {
+      return Err("--update needs an output file".to_string());
+    }
+
+    // A missing file is simply created.
+    let existing = args
+      .update
+      .then(|| fs::read_to_string(&args.output).ok())
+      .flatten();
+
+    let mut out = Output::new(Path::new("."), &args.output);
+
+    if let Some(existing) = &existing {
+      out
+        .write_all(existing.as_bytes())
+        .expect("error buffering stubs");
+      generate_stubs(&cst, None, &defined_systems(existing), &mut out)
+    } else {
+      generate_stubs(&cst, Some(&args.h_output), &[], &mut out)
+    }
+    .expect("error generating stubs");
+
+    out
+      .finish()
+      .map_err(|e| format!("failed to write stubs `{}`: {}", args.output, e))?;
+    Ok(())
+  })
+}
+
+fn graph(args: &GraphArgs) -> Result<(), String> {
+  with_cst(&args.source, None, |cst| {
+    let mut out = Output::new(Path::new("."), &args.output);
+    generate_graph(&cst, &mut out).expect("error generating graph");
+
+    out
+      .finish()
+      .map_err(|e| format!("failed to write graph `{}`: {}", args.output, e))?;
+    Ok(())
+  })
+}
 
-  let dest = PathBuf::from_str(&cli.dest).expect("failed to parse output directory");
+// Formats every source in place, or with `--check` lists the ones that aren't
+// formatted. Sources that fail to parse are reported and left alone.
+fn fmt(args: &FmtArgs) -> Result<(), String> {
+  let mut failed = false;
+
+  for source in args.sources.iter() {
+    let text = if source == "-" {
+      io::read_to_string(io::stdin())
+    } else {
+      fs::read_to_string(source)
+    }
+    .map_err(|e| format!("error reading `{}`: {}", source, e))?;
+
+    let formatted = match format(Some(source), &text, args.width) {
+      Ok(formatted) => formatted,
+      Err(e) => {
+        eprintln!("{}", e);
+        failed = true;
+        continue;
+      }
+    };
+
+    if args.check {
+      if formatted != text {
+        eprintln!("`{}` is not formatted", source);
+        failed = true;
+      }
+      continue;
+    }
+
+    let mut out = Output::new(Path::new("."), source);
+    out
+      .write_all(formatted.as_bytes())
+      .expect("error buffering formatted source");
+    out
+      .finish()
+      .map_err(|e| format!("failed to write `{}`: {}", source, e))?;
+  }
+
+  if failed {
+    return Err("formatting failed".to_string());
+  }
+
+  Ok(())
+}
+
+// Parses, resolves and generates. Nothing is written unless all of it succeeded.
+fn run(cli: &Cli, source: &str) -> Result<(), String> {
+  with_cst(source, cli.prefix.as_deref(), |mut cst| {
+    if cli.profile {
+      cst.settings.profile = true;
+    }
+
+    if cli.single_header {
+      cst.settings.single_header = true;
+    }
+
+    if cli.emit == Some(Emit::CstJson) {
+      let mut out = Output::new(Path::new("."), "-");
+      generate_cst_json(&cst, &mut out)
+        .map_err(|e| format!("error generating CST JSON: {}", e))?;
+      return out
+        .finish()
+        .map(|_| ())
+        .map_err(|e| format!("failed to write CST JSON: {}", e));
+    }
+
+    generate(cli, source, &cst)
+  })
+}
+
+// Writes the outputs selected by `cli`, and the depfile if asked for.
+fn generate(cli: &Cli, source: &str, cst: &Cst) -> Result<(), String> {
+  let dest = PathBuf::from_str(&cli.dest)
+    .map_err(|_| "failed to parse output directory".to_string())?;
 
   if !dest.is_dir() {
-    panic!("dest ({}) should be a directory!", dest.display());
+    return Err(format!("dest ({}) should be a directory!", dest.display()));
   }
 
-  let open_for_write = |filename: &str| -> io::Result<File> {
-    OpenOptions::new()
-      .create(true)
-      .write(true)
-      .truncate(true)
-      .open(dest.join(filename))
-  };
+  // The outputs are only written once everything was generated.
+  let mut outputs = Vec::new();
 
-  let mut h_out_file: Box<dyn Write> = if cli.h_output == "-" {
-    Box::new(stdout())
+  if cli.target == Target::Rust {
+    let mut rs_out = Output::new(&dest, &cli.rs_output);
+    generate_rust(cst, &mut rs_out)
+      .map_err(|e| format!("error generating Rust output: {}", e))?;
+    outputs.push(rs_out);
   } else {
-    Box::new(
-      open_for_write(&cli.h_output)
-        .expect(&format!("failed to open header output `{}`", &cli.h_output)),
-    )
-  };
+    let mut h_out = Output::new(&dest, &cli.h_output);
 
-  let mut c_out_file: Box<dyn Write> = if cli.c_output == "-" {
-    Box::new(stdout())
-  } else {
-    Box::new(open_for_write(&cli.c_output).expect(&format!(
-      "failed to open implementation output `{}`",
-      &cli.c_output
-    )))
-  };
+    if cst.settings.single_header {
+      generate_single_header(cst, &mut h_out)
+        .map_err(|e| format!("error generating header output: {}", e))?;
+      outputs.push(h_out);
+    } else {
+      let mut c_out = Output::new(&dest, &cli.c_output);
+
+      generate_header(cst, &mut h_out)
+        .map_err(|e| format!("error generating header output: {}", e))?;
+      generate_impl(cst, &cli.h_output, &mut c_out)
+        .map_err(|e| format!("error generating implementation output: {}", e))?;
+      outputs.push(h_out);
+      outputs.push(c_out);
+    }
+
+    if cli.target == Target::Cpp {
+      let mut hpp_out = Output::new(&dest, &cli.hpp_output);
+      generate_cpp(cst, &cli.h_output, &mut hpp_out)
+        .map_err(|e| format!("error generating C++ header output: {}", e))?;
+      outputs.push(hpp_out);
+    }
+  }
+
+  for output in outputs.iter() {
+    output
+      .finish()
+      .map_err(|e| format!("failed to write output `{}`: {}", output.name, e))?;
+  }
+
+  if let Some(depfile_path) = &cli.depfile {
+    let includes = include_deps(Path::new(source), &cst.includes);
+    let depfile = Depfile {
+      targets: outputs.iter().filter_map(|o| o.path.as_deref()).collect(),
+      deps: std::iter::once(Path::new(source))
+        .chain(includes.iter().map(|p| p.as_path()))
+        .collect(),
+    };
+
+    let mut depfile_out = Output::new(Path::new("."), depfile_path);
+    write!(depfile_out, "{}", depfile)
+      .map_err(|e| format!("error generating depfile: {}", e))?;
+    depfile_out
+      .finish()
+      .map_err(|e| format!("failed to write depfile `{}`: {}", depfile_path, e))?;
+  }
 
-  generate_header(&cst, &mut h_out_file).expect("error generating header output");
-  generate_impl(&cst, &cli.h_output, &mut c_out_file)
-    .expect("error generating implementation output");
+  Ok(())
 }
@@ -0,0 +1,166 @@
+use std::{
+  fmt::Display,
+  fs,
+  io::{self, Write, stdout},
+  path::{Path, PathBuf},
+};
+
+use crate::{common::StringKind, resolve::cst::Include};
+
+// A generated file. Its contents are buffered and only written if they differ from
+// what is already on disk, so that make and ninja don't rebuild everything that
+// depends on it for nothing. A name of `-` means stdout.
+pub struct Output {
+  pub name: String,
+  pub path: Option<PathBuf>,
+  buf: Vec<u8>,
+}
+
+impl Output {
+  pub fn new(dest: &Path, name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      path: (name != "-").then(|| dest.join(name)),
+      buf: Vec::new(),
+    }
+  }
+
+  // Returns whether the file was written.
+  pub fn finish(&self) -> io::Result<bool> {
+    let Some(path) = &self.path else {
+      stdout().write_all(&self.buf)?;
+      return Ok(true);
+    };
+
+    if fs::read(path).is_ok_and(|old| old == self.buf) {
+      return Ok(false);
+    }
+
+    fs::write(path, &self.buf)?;
+    Ok(true)
+  }
+}
+
+impl Write for Output {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.buf.extend_from_slice(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+// A Makefile rule, as written by `cc -MD`, stating that `targets` depend on `deps`.
+// Both make and ninja read it.
+pub struct Depfile<'a> {
+  pub targets: Vec<&'a Path>,
+  pub deps: Vec<&'a Path>,
+}
+
+// The headers included by the spec at `source` that the generated code depends on.
+// Like with `build::Build`, quoted includes are looked up in the spec's directory.
+// Those that aren't there are left out, as are system headers, like `cc -MMD` does.
+pub fn include_deps(source: &Path, includes: &[Include]) -> Vec<PathBuf> {
+  let dir = source.parent().unwrap_or(Path::new(""));
+
+  includes
+    .iter()
+    .filter_map(|include| match &include.path {
+      StringKind::DoubleQuoted(path) => Some(dir.join(path)),
+      StringKind::AngleBracketed(_) => None,
+    })
+    .filter(|path| path.is_file())
+    .collect()
+}
+
+// Escapes the characters make would otherwise interpret in a file name.
+struct DepfilePath<'a>(&'a Path);
+
+impl<'a> Display for DepfilePath<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for c in self.0.to_string_lossy().chars() {
+      match c {
+        ' ' | '#' => write!(f, "\\{}", c)?,
+        '$' => write!(f, "$$")?,
+        c => write!(f, "{}", c)?,
+      }
+    }
+
+    Ok(())
+  }
+}
+
+impl<'a> Display for Depfile<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (i, target) in self.targets.iter().enumerate() {
+      if i > 0 {
+        write!(f, " ")?;
+      }
+      write!(f, "{}", DepfilePath(target))?;
+    }
+
+    write!(f, ":")?;
+    for dep in self.deps.iter() {
+      write!(f, " {}", DepfilePath(dep))?;
+    }
+
+    writeln!(f)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{fs, path::Path};
+
+  use super::{Depfile, include_deps};
+  use crate::spec::Spec;
+
+  #[test]
+  fn test_depfile() {
+    let depfile = Depfile {
+      targets: vec![Path::new("out/vecs.h"), Path::new("out/vecs.c")],
+      deps: vec![Path::new("game.vecs")],
+    };
+    assert_eq!(depfile.to_string(), "out/vecs.h out/vecs.c: game.vecs\n");
+
+    let depfile = Depfile {
+      targets: vec![Path::new("my out/$x.h")],
+      deps: vec![Path::new("a#b.vecs")],
+    };
+    assert_eq!(depfile.to_string(), "my\\ out/$$x.h: a\\#b.vecs\n");
+
+    let dir =
+      std::env::temp_dir().join(format!("vecs_deps_test_{}", std::process::id()));
+    fs::create_dir_all(dir.join("proto"))
+      .expect("failed to create the test directory");
+    fs::write(dir.join("proto/types.h"), "").expect("write error");
+
+    let source = dir.join("game.vecs");
+    let text = concat!(
+      "include \"proto/types.h\";\n",
+      "include \"missing.h\";\n",
+      "include <stdio.h>;\n",
+    );
+    let spec =
+      Spec::new(&source.to_string_lossy(), text.to_string()).expect("spec error");
+    let cst = spec.resolve().expect("resolve error");
+    let includes = include_deps(&source, &cst.includes);
+
+    let depfile = Depfile {
+      targets: vec![Path::new("vecs.h")],
+      deps: std::iter::once(source.as_path())
+        .chain(includes.iter().map(|p| p.as_path()))
+        .collect(),
+    };
+    let expected = format!(
+      "vecs.h: {} {}\n",
+      source.display(),
+      dir.join("proto/types.h").display()
+    );
+
+    fs::remove_dir_all(&dir).expect("failed to remove the test directory");
+    assert_eq!(depfile.to_string(), expected);
+  }
+}
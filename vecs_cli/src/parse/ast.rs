@@ -22,6 +22,8 @@ pub enum ExpressionKind<'src> {
 
   Application(Vec<Expression<'src>>),
   List(Vec<ListEntry<'src>>),
+  // `(a, b)`, only around the parameters of a macro and the arguments of its calls.
+  Tuple(Vec<Expression<'src>>),
 }
 
 #[derive(Debug, Clone, Eq, Educe)]
@@ -31,6 +33,46 @@ pub struct Expression<'src> {
 
   #[educe(PartialEq(ignore))]
   pub span: Span<'src>,
+
+  // Only filled in by `attach_comments`.
+  #[educe(PartialEq(ignore))]
+  pub comments: Comments<'src>,
+}
+
+// A comment as written, with its delimiters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comment<'src> {
+  pub text: &'src str,
+  // Whether an empty line separates it from what comes before.
+  pub blank_line_before: bool,
+}
+
+impl<'src> Comment<'src> {
+  // `// ...`, which has to be followed by a line break.
+  pub fn is_line(&self) -> bool {
+    self.text.starts_with("//")
+  }
+}
+
+// The comments around an expression. The parser doesn't see comments, they are
+// attached afterwards for the formatter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comments<'src> {
+  // Before the expression.
+  pub leading: Vec<Comment<'src>>,
+  // After the expression, on the line where it ends.
+  pub trailing: Vec<Comment<'src>>,
+  // Inside a list, after its last entry.
+  pub dangling: Vec<Comment<'src>>,
+  // Whether an empty line separates the expression from what comes before, be it a
+  // leading comment or the previous expression.
+  pub blank_line_before: bool,
+}
+
+impl<'src> Comments<'src> {
+  pub fn is_empty(&self) -> bool {
+    self.leading.is_empty() && self.trailing.is_empty() && self.dangling.is_empty()
+  }
 }
 
 fn write_indent<W: fmt::Write>(w: &mut W, indent: usize) -> fmt::Result {
@@ -43,7 +85,11 @@ fn write_indent<W: fmt::Write>(w: &mut W, indent: usize) -> fmt::Result {
 
 impl<'src> Expression<'src> {
   pub fn new(kind: ExpressionKind<'src>, span: Span<'src>) -> Self {
-    Self { kind, span }
+    Self {
+      kind,
+      span,
+      comments: Comments::default(),
+    }
   }
 
   fn show<W: fmt::Write>(&self, indent: usize, w: &mut W) -> fmt::Result {
@@ -93,18 +139,46 @@ impl<'src> Expression<'src> {
           write!(w, "}}\n")
         }
       }
+      ExpressionKind::Tuple(items) => {
+        if items.is_empty() {
+          writeln!(w, "tuple ({}) {{}}", span)
+        } else {
+          writeln!(w, "tuple ({}) {{", span)?;
+
+          for item in items {
+            write_indent(w, indent)?;
+            item.show(indent + 1, w)?;
+          }
+
+          write_indent(w, indent - 1)?;
+          writeln!(w, "}}")
+        }
+      }
     }
   }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Ast<'src>(pub VecDeque<Expression<'src>>);
+pub struct Ast<'src> {
+  pub expressions: VecDeque<Expression<'src>>,
+  // After the last expression. Only filled in by `attach_comments`.
+  pub comments: Vec<Comment<'src>>,
+}
+
+impl<'src> Ast<'src> {
+  pub fn new(expressions: VecDeque<Expression<'src>>) -> Self {
+    Self {
+      expressions,
+      comments: Vec::new(),
+    }
+  }
+}
 
 impl<'src> fmt::Display for Ast<'src> {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     write!(f, "Ast\n")?;
 
-    for expr in self.0.iter() {
+    for expr in self.expressions.iter() {
       expr.show(1, f)?;
     }
 
@@ -1,128 +1,221 @@
 use crate::parse::{
-  data::{
-    result::{ParseResult, ParseSuccess},
-    src::ParseSrc,
-  },
-  util::str::parse_str,
+  ast::{Ast, Comment, Expression, ExpressionKind, ListEntry},
+  data::str::line_breaks,
+  tokens::{Token, TokenKind},
 };
 
-pub fn parse_inline_comment<'src>(
-  mut src: ParseSrc<'src>,
-) -> ParseResult<'src, &'src str> {
-  let start = src.clone();
+// Attaches the comments among `tokens` to the expressions of `ast`, which was parsed
+// from `text` with the comments blanked out. Each comment goes to the expression that
+// follows it, unless it's on the line where an entry of a list (or the AST) ends,
+// then it trails that entry. Comments with nothing after them in a list dangle from
+// it, and those at the end of the source are kept in the AST.
+pub fn attach_comments<'src>(
+  ast: &mut Ast<'src>,
+  text: &'src str,
+  tokens: &[Token<'src>],
+) {
+  let comments = tokens
+    .iter()
+    .filter(|t| t.kind == TokenKind::Comment)
+    .copied()
+    .collect::<Vec<_>>();
+
+  let mut attacher = Attacher {
+    text,
+    comments: &comments,
+    next: 0,
+    previous_end: 0,
+  };
+
+  let expressions = ast.expressions.iter_mut().collect();
+  ast.comments = attacher.entries(expressions, text.len());
+}
+
+struct Attacher<'a, 'src> {
+  text: &'src str,
+  comments: &'a [Token<'src>],
+  // The next comment to attach.
+  next: usize,
+  // Where the last thing visited ended, to find empty lines.
+  previous_end: usize,
+}
+
+impl<'a, 'src> Attacher<'a, 'src> {
+  fn blank_line_before(&self, offset: usize) -> bool {
+    line_breaks(&self.text[self.previous_end..offset]) >= 2
+  }
 
-  src = parse_str("//", src)?.src;
+  fn take(&mut self, blank_line_before: bool) -> Comment<'src> {
+    let span = self.comments[self.next].span;
+    self.next += 1;
+    self.previous_end = span.end_byte_offset;
 
-  while let Some(content) = src.peek() {
-    if content == '\n' {
-      break;
+    Comment {
+      text: span.slice(self.text),
+      blank_line_before,
     }
+  }
+
+  // Moves past the `;` ending an entry, which can come after its trailing comments.
+  fn skip_terminator(&mut self, next_start: usize) {
+    let rest = self.text[self.previous_end..next_start].trim_start();
 
-    src.next();
+    if rest.starts_with(';') {
+      self.previous_end = next_start - rest.len() + 1;
+    }
   }
 
-  let span = src.span_from(&start);
+  // The comments starting before `offset`.
+  fn take_before(&mut self, offset: usize) -> Vec<Comment<'src>> {
+    let mut taken = Vec::new();
 
-  Ok(ParseSuccess {
-    value: &src.slice(span)[2..].trim(),
-    span,
-    src,
-  })
-}
+    while let Some(comment) = self.comments.get(self.next)
+      && comment.span.start_byte_offset < offset
+    {
+      let blank_line_before = self.blank_line_before(comment.span.start_byte_offset);
+      taken.push(self.take(blank_line_before));
+    }
 
-pub fn parse_multiline_comment<'src>(
-  mut src: ParseSrc<'src>,
-) -> ParseResult<'src, &'src str> {
-  let start = src.clone();
+    taken
+  }
 
-  src = parse_str("/*", src)?.src;
+  // The comments starting before `offset` on `line`.
+  fn take_on_line(&mut self, line: usize, offset: usize) -> Vec<Comment<'src>> {
+    let mut taken = Vec::new();
 
-  loop {
-    let result = parse_str("*/", src.clone());
+    while let Some(comment) = self.comments.get(self.next)
+      && comment.span.start_byte_offset < offset
+      && comment.span.start_line == line
+    {
+      taken.push(self.take(false));
+    }
 
-    if let Ok(success) = result {
-      src = success.src;
-      break;
-    } else {
-      src.next();
+    taken
+  }
+
+  // Visits a sequence of entries ending at `end`, and returns the comments left after
+  // the last one.
+  fn entries(
+    &mut self,
+    mut expressions: Vec<&mut Expression<'src>>,
+    end: usize,
+  ) -> Vec<Comment<'src>> {
+    let starts = expressions
+      .iter()
+      .map(|e| e.span.start_byte_offset)
+      .collect::<Vec<_>>();
+
+    for (i, expression) in expressions.iter_mut().enumerate() {
+      self.expression(expression);
+
+      let (line, _) = content_end(expression);
+      let next_start = starts.get(i + 1).copied().unwrap_or(end);
+      expression.comments.trailing = self.take_on_line(line, next_start);
+      self.skip_terminator(next_start);
     }
+
+    self.take_before(end)
   }
 
-  let span = src.span_from(&start);
-  let slice = src.slice(span);
+  fn expression(&mut self, expression: &mut Expression<'src>) {
+    let start = expression.span.start_byte_offset;
+    expression.comments.leading = self.take_before(start);
+    expression.comments.blank_line_before = self.blank_line_before(start);
+
+    match &mut expression.kind {
+      ExpressionKind::Application(children) | ExpressionKind::Tuple(children) => {
+        for child in children.iter_mut() {
+          self.expression(child);
+        }
+      }
+      ExpressionKind::List(entries) => {
+        // Past the `{`.
+        self.previous_end = start + 1;
+
+        let expressions = entries
+          .iter_mut()
+          .map(|entry| match entry {
+            ListEntry::Expr(e) | ListEntry::Embed(e) => e,
+          })
+          .collect();
+
+        // Before the `}`.
+        let end = expression.span.end_byte_offset - 1;
+        expression.comments.dangling = self.entries(expressions, end);
+      }
+      _ => {}
+    }
 
-  Ok(ParseSuccess {
-    value: &slice[2..slice.len() - 2].trim(),
-    span,
-    src,
-  })
+    self.previous_end = content_end(expression).1;
+  }
 }
 
-pub fn parse_comment<'src>(src: ParseSrc<'src>) -> ParseResult<'src, &'src str> {
-  parse_inline_comment(src.clone()).or_else(|_| parse_multiline_comment(src))
+// The line and offset where the expression ends. Applications end with whatever
+// whitespace follows them, so this is where their last element ends instead.
+fn content_end(expression: &Expression) -> (usize, usize) {
+  match &expression.kind {
+    ExpressionKind::Application(children) if !children.is_empty() => {
+      content_end(children.last().unwrap())
+    }
+    _ => (expression.span.end_line, expression.span.end_byte_offset),
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use crate::parse::{
-    comments::{parse_comment, parse_inline_comment, parse_multiline_comment},
+    ast::{ExpressionKind, ListEntry},
+    comments::attach_comments,
     data::src::ParseSrc,
+    parse, strip_comments,
+    tokens::tokenize,
   };
 
   #[test]
-  fn test_parse_inline_comment_collect() {
-    // Good EOF.
-    let src = ParseSrc::new(None, "// ab c");
-    let result = parse_inline_comment(src).expect("parse error");
-    assert_eq!(result.value, "ab c");
-    assert_eq!(result.src.remaining_str(), "");
-
-    // Good EOL.
-    let src = ParseSrc::new(None, "// ab c\nd");
-    let result = parse_inline_comment(src).expect("parse error");
-    assert_eq!(result.value, "ab c");
-    assert_eq!(result.src.remaining_str(), "\nd");
-
-    // Different characters.
-    let src = ParseSrc::new(None, "abc");
-    let _ = parse_inline_comment(src).expect_err("parse not error");
-  }
-
-  #[test]
-  fn test_parse_multiline_comment_collect() {
-    // Good EOF.
-    let src = ParseSrc::new(None, "/* ab c */");
-    let result = parse_multiline_comment(src).expect("parse error");
-    assert_eq!(result.value, "ab c");
-    assert_eq!(result.src.remaining_str(), "");
-
-    // Good EOL.
-    let src = ParseSrc::new(None, "/* ab c\nd*/\ne");
-    let result = parse_multiline_comment(src).expect("parse error");
-    assert_eq!(result.value, "ab c\nd");
-    assert_eq!(result.src.remaining_str(), "\ne");
-
-    // Different characters.
-    let src = ParseSrc::new(None, "abc");
-    let _ = parse_multiline_comment(src).expect_err("parse not error");
-  }
-
-  #[test]
-  fn test_parse_comment_collect() {
-    // Inline.
-    let src = ParseSrc::new(None, "// ab c\nx");
-    let result = parse_comment(src).expect("parse error");
-    assert_eq!(result.value, "ab c");
-    assert_eq!(result.src.remaining_str(), "\nx");
-
-    // Multiline.
-    let src = ParseSrc::new(None, "/* ab c */\nx");
-    let result = parse_comment(src).expect("parse error");
-    assert_eq!(result.value, "ab c");
-    assert_eq!(result.src.remaining_str(), "\nx");
-
-    // Different characters.
-    let src = ParseSrc::new(None, "abc");
-    let _ = parse_comment(src).expect_err("parse not error");
+  fn test_attach_comments() {
+    let text = "// a\n\n// b\n\ncomponent c { int x; // x\n /* y */ };\n// end\n";
+    let tokens = tokenize(ParseSrc::from(text));
+    let stripped = strip_comments(text, &tokens).expect("strip error");
+
+    let mut ast = parse(ParseSrc::from(stripped.as_str()))
+      .expect("parse error")
+      .value;
+    attach_comments(&mut ast, text, &tokens);
+
+    let component = &ast.expressions[0];
+    let leading = component
+      .comments
+      .leading
+      .iter()
+      .map(|c| (c.text, c.blank_line_before))
+      .collect::<Vec<_>>();
+    assert_eq!(leading, [("// a", false), ("// b", true)]);
+    assert!(component.comments.blank_line_before);
+
+    let ExpressionKind::Application(children) = &component.kind else {
+      panic!("not an application");
+    };
+    let ExpressionKind::List(entries) = &children[2].kind else {
+      panic!("not a list");
+    };
+    let ListEntry::Expr(field) = &entries[0] else {
+      panic!("not an expression");
+    };
+    assert_eq!(field.comments.trailing[0].text, "// x");
+    assert_eq!(children[2].comments.dangling[0].text, "/* y */");
+    assert_eq!(ast.comments[0].text, "// end");
+
+    // Empty lines are counted from the `;`, even after a trailing comment.
+    let text = "node n { a } // t\n;\ncomponent c;\n";
+    let tokens = tokenize(ParseSrc::from(text));
+    let stripped = strip_comments(text, &tokens).expect("strip error");
+
+    let mut ast = parse(ParseSrc::from(stripped.as_str()))
+      .expect("parse error")
+      .value;
+    attach_comments(&mut ast, text, &tokens);
+
+    assert_eq!(ast.expressions[0].comments.trailing[0].text, "// t");
+    assert!(!ast.expressions[1].comments.blank_line_before);
   }
 }
@@ -30,6 +30,14 @@ impl<'src> ParseError<'src> {
     }
   }
 
+  pub fn location(&self) -> Location<'src> {
+    self.location
+  }
+
+  pub fn message(&self) -> &str {
+    self.message.as_ref()
+  }
+
   pub fn wrap_message<T>(self, msg: T) -> ParseError<'src>
   where
     T: Into<Cow<'static, str>>,
@@ -12,7 +12,7 @@ pub struct ParseSrc<'src> {
   pub chars: Chars<'src>,
 }
 
-// Handles CRLF by effectively converting to LF.
+// Handles CRLF and CR by effectively converting them to LF.
 impl<'src> ParseSrc<'src> {
   pub fn new(file: Option<&'src str>, text: &'src str) -> Self {
     Self {
@@ -40,9 +40,10 @@ impl<'src> ParseSrc<'src> {
     ParseSrcZip::new(self, b)
   }
 
+  // Like `next`, line breaks are `\n`.
   pub fn peek(&self) -> Option<char> {
     let mut clone = self.chars.clone();
-    clone.next()
+    clone.next().map(|c| if c == '\r' { '\n' } else { c })
   }
 
   pub fn advance_bytes(&mut self, bytes: usize) {
@@ -88,33 +89,23 @@ impl<'src> Iterator for ParseSrc<'src> {
   type Item = char;
 
   fn next(&mut self) -> Option<Self::Item> {
-    let next = self.chars.next();
-
-    if let Some(next_char) = next {
-      self.location.advance(next_char);
-
-      // Handle CRLF right here and now.
-      let next_char = {
-        if next_char == '\r' {
-          let chars_backup = self.chars.clone();
-          let next_next_char = self.chars.next();
-
-          if let Some('\n') = next_next_char {
-            self.location.advance(next_char);
-            '\n'
-          } else {
-            self.chars = chars_backup;
-            next_char
-          }
-        } else {
-          next_char
-        }
-      };
+    let next = self.chars.next()?;
 
-      Some(next_char)
-    } else {
-      None
+    // Line breaks are all read as `\n`.
+    match next {
+      '\r' if self.chars.as_str().starts_with('\n') => {
+        self.chars.next();
+        self.location.advance_line("\r\n");
+      }
+      '\r' => self.location.advance_line("\r"),
+      '\n' => self.location.advance_line("\n"),
+      c => {
+        self.location.advance(c);
+        return Some(c);
+      }
     }
+
+    Some('\n')
   }
 }
 
@@ -165,3 +156,44 @@ impl<'src> Iterator for ParseSrcZip<'src> {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use crate::parse::data::src::ParseSrc;
+
+  #[test]
+  fn test_locations() {
+    let mut src = ParseSrc::from("a\r\nb\rc\n\tx\u{e9}\u{1F600}y");
+    let mut read = String::new();
+    let mut locations = Vec::new();
+
+    while let Some(c) = src.next() {
+      read.push(c);
+      let location = src.location;
+      locations.push((
+        location.byte_offset,
+        location.line,
+        location.column,
+        location.column_utf16,
+      ));
+    }
+
+    assert_eq!(read, "a\nb\nc\n\tx\u{e9}\u{1F600}y");
+    assert_eq!(
+      locations,
+      [
+        (1, 1, 1, 1),
+        (3, 2, 0, 0),
+        (4, 2, 1, 1),
+        (5, 3, 0, 0),
+        (6, 3, 1, 1),
+        (7, 4, 0, 0),
+        (8, 4, 8, 1),
+        (9, 4, 9, 2),
+        (11, 4, 10, 3),
+        (15, 4, 11, 5),
+        (16, 4, 12, 6),
+      ]
+    );
+  }
+}
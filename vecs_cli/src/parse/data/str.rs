@@ -1,9 +1,15 @@
+// Tabs move the column to the next multiple of this, like in most terminals.
+pub const TAB_WIDTH: usize = 8;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Hash)]
 pub struct Location<'src> {
   pub file: Option<&'src str>,
   pub byte_offset: usize,
   pub line: usize,
+  // As displayed, with tabs expanded.
   pub column: usize,
+  // In UTF-16 code units, which is how LSP counts characters.
+  pub column_utf16: usize,
 }
 
 impl<'src> Location<'src> {
@@ -13,23 +19,35 @@ impl<'src> Location<'src> {
       byte_offset: 0,
       line: 1,
       column: 0,
+      column_utf16: 0,
     }
   }
 
-  // Only supports LF (not CRLF)!
+  // Moves past `c`, which isn't part of a line break.
   pub fn advance(&mut self, c: char) {
-    match c {
-      '\n' => {
-        self.byte_offset += c.len_utf8();
-        self.line += 1;
-        self.column = 0;
-      }
-      c => {
-        self.byte_offset += c.len_utf8();
-        self.column += 1;
-      }
+    self.byte_offset += c.len_utf8();
+    self.column_utf16 += c.len_utf16();
+
+    if c == '\t' {
+      self.column += TAB_WIDTH - self.column % TAB_WIDTH;
+    } else {
+      self.column += 1;
     }
   }
+
+  // Moves past a line break: `\n`, `\r\n` or `\r`.
+  pub fn advance_line(&mut self, line_break: &str) {
+    self.byte_offset += line_break.len();
+    self.line += 1;
+    self.column = 0;
+    self.column_utf16 = 0;
+  }
+}
+
+// How many line breaks there are in `text`, counting `\r\n` as one.
+pub fn line_breaks(text: &str) -> usize {
+  text.matches('\n').count() + text.matches('\r').count()
+    - text.matches("\r\n").count()
 }
 
 impl<'src> std::fmt::Display for Location<'src> {
@@ -48,9 +66,11 @@ pub struct Span<'src> {
   pub start_byte_offset: usize,
   pub start_line: usize,
   pub start_column: usize,
+  pub start_column_utf16: usize,
   pub end_byte_offset: usize,
   pub end_line: usize,
   pub end_column: usize,
+  pub end_column_utf16: usize,
 }
 
 impl<'src> Span<'src> {
@@ -60,9 +80,21 @@ impl<'src> Span<'src> {
       start_byte_offset: start.byte_offset,
       start_line: start.line,
       start_column: start.column,
+      start_column_utf16: start.column_utf16,
       end_byte_offset: end.byte_offset,
       end_line: end.line,
       end_column: end.column,
+      end_column_utf16: end.column_utf16,
+    }
+  }
+
+  pub const fn start(&self) -> Location<'src> {
+    Location {
+      file: self.file,
+      byte_offset: self.start_byte_offset,
+      line: self.start_line,
+      column: self.start_column,
+      column_utf16: self.start_column_utf16,
     }
   }
 
@@ -74,6 +106,10 @@ impl<'src> Span<'src> {
   pub fn len(&self) -> usize {
     self.end_byte_offset - self.start_byte_offset
   }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
 }
 
 impl<'src> std::fmt::Display for Span<'src> {
@@ -5,32 +5,28 @@ use regex::Regex;
 use crate::parse::{
   ast::{Expression, ExpressionKind},
   data::{
-    result::{ParseResult, ParseSuccess},
+    result::{ParseError, ParseResult, ParseSuccess},
     src::ParseSrc,
   },
   util::regex::parse_regex,
 };
 
+// Everything up to the end of the word, so that bad digits are an error instead of
+// the start of another expression.
 static INTEGER_REGEX: LazyLock<Regex> =
-  LazyLock::new(|| Regex::new("^[0-9][0-9_]*").expect("integer regex error"));
+  LazyLock::new(|| Regex::new("^-?[0-9][0-9A-Za-z_]*").expect("integer regex error"));
 
 pub fn parse_integer<'src>(
   mut src: ParseSrc<'src>,
 ) -> ParseResult<'src, Expression<'src>> {
   let start = src.clone();
 
-  let matsh = parse_regex(src, &INTEGER_REGEX)?;
+  let matsh = parse_regex(src, &INTEGER_REGEX)
+    .map_err(|err| err.sub_message("expected an integer"))?;
   src = matsh.src;
 
-  let mut accum: i128 = 0;
-
-  for digit in matsh.value.as_str().bytes() {
-    if digit == b'_' {
-      continue;
-    }
-
-    accum = accum * 10 + ((digit - b'0') as i128);
-  }
+  let accum = integer_value(matsh.value.as_str())
+    .map_err(|message| ParseError::new(start.location, message))?;
 
   let span = src.span_from(&start);
   Ok(ParseSuccess {
@@ -40,6 +36,60 @@ pub fn parse_integer<'src>(
   })
 }
 
+// An optional `-`, an optional `0x`, `0o` or `0b` prefix, digits which can be
+// separated by `_`, and an optional `K`, `M` or `G` suffix, multiplying the value by
+// 2^10, 2^20 or 2^30.
+fn integer_value(literal: &str) -> Result<i128, String> {
+  let (negative, rest) = match literal.strip_prefix('-') {
+    Some(rest) => (true, rest),
+    None => (false, literal),
+  };
+
+  let (radix, rest) = match rest.get(..2) {
+    Some("0x") => (16, &rest[2..]),
+    Some("0o") => (8, &rest[2..]),
+    Some("0b") => (2, &rest[2..]),
+    _ => (10, rest),
+  };
+
+  let (digits, multiplier) = match rest.as_bytes().last() {
+    Some(b'K') => (&rest[..rest.len() - 1], 1 << 10),
+    Some(b'M') => (&rest[..rest.len() - 1], 1 << 20),
+    Some(b'G') => (&rest[..rest.len() - 1], 1 << 30),
+    _ => (rest, 1),
+  };
+
+  if !digits.chars().any(|c| c != '_') {
+    return Err(format!("integer `{}` has no digits", literal));
+  }
+
+  let out_of_range = || format!("integer `{}` doesn't fit in 128 bits", literal);
+  let mut magnitude: u128 = 0;
+
+  for c in digits.chars().filter(|c| *c != '_') {
+    let Some(digit) = c.to_digit(radix) else {
+      return Err(format!(
+        "`{}` is not a valid base {} digit in integer `{}`",
+        c, radix, literal
+      ));
+    };
+
+    magnitude = magnitude
+      .checked_mul(radix.into())
+      .and_then(|m| m.checked_add(digit.into()))
+      .ok_or_else(out_of_range)?;
+  }
+
+  let magnitude = magnitude.checked_mul(multiplier).ok_or_else(out_of_range)?;
+
+  if negative {
+    0i128.checked_sub_unsigned(magnitude)
+  } else {
+    i128::try_from(magnitude).ok()
+  }
+  .ok_or_else(out_of_range)
+}
+
 #[cfg(test)]
 mod tests {
   use crate::parse::{
@@ -60,6 +110,35 @@ mod tests {
     assert_eq!(result.value, int!(12345));
     assert_eq!(result.src.remaining_str(), " 6789");
 
+    // Signs, prefixes and suffixes.
+    for (text, value) in [
+      ("-42", -42),
+      ("0xff_ff", 0xffff),
+      ("0o17", 0o17),
+      ("-0b1010", -0b1010),
+      ("4K", 4096),
+      ("0x1M", 1 << 20),
+      ("2G", 2 << 30),
+      ("-170141183460469231731687303715884105728", i128::MIN),
+    ] {
+      let result = parse_integer(ParseSrc::new(None, text)).expect("parse error");
+      assert_eq!(result.value, int!(value));
+      assert_eq!(result.src.remaining_str(), "");
+    }
+
+    // Bad digits, no digits and out of range.
+    for text in [
+      "12abc",
+      "0b102",
+      "0x",
+      "-",
+      "170141183460469231731687303715884105728",
+      "0x100000000000000000000000000000000",
+      "0x7fffffffffffffffffffffffffffffffK",
+    ] {
+      let _ = parse_integer(ParseSrc::new(None, text)).expect_err("parse not error");
+    }
+
     // Different characters.
     let src = ParseSrc::new(None, "abc1");
     let _ = parse_integer(src).expect_err("parse not error");
@@ -3,6 +3,7 @@ pub mod integer;
 pub mod list;
 pub mod string;
 pub mod symbol;
+pub mod tuple;
 pub mod variable;
 
 use crate::parse::{
@@ -15,8 +16,11 @@ use crate::parse::{
     application::parse_application,
     integer::parse_integer,
     list::parse_list,
-    string::{parse_angle_bracketed_string, parse_double_quoted_string},
+    string::{
+      parse_angle_bracketed_string, parse_double_quoted_string, parse_raw_string,
+    },
     symbol::parse_symbol,
+    tuple::parse_tuple,
     variable::parse_variable,
   },
 };
@@ -37,8 +41,11 @@ fn parse_basic_expression<'src>(
   match head.unwrap() {
     '$' => parse_variable(src.clone()),
     '{' => parse_list(src.clone()),
+    '(' => parse_tuple(src.clone()),
     '"' => parse_double_quoted_string(src.clone()),
     '<' => parse_angle_bracketed_string(src.clone()),
+    'r' if raw_string_start(&src) => parse_raw_string(src.clone()),
+    '0'..='9' | '-' => parse_integer(src.clone()),
     _ => parse_integer(src.clone())
       .or_else(|_| parse_symbol(src.clone()))
       .map_err(|err| {
@@ -48,6 +55,13 @@ fn parse_basic_expression<'src>(
   }
 }
 
+// `r"` or `r#`, which can't start anything but a raw string.
+fn raw_string_start(src: &ParseSrc) -> bool {
+  let mut chars = src.chars.clone();
+  chars.next();
+  matches!(chars.next(), Some('"' | '#'))
+}
+
 pub fn parse_expression<'src>(
   src: ParseSrc<'src>,
 ) -> ParseResult<'src, Expression<'src>> {
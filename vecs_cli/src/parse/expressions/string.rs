@@ -10,6 +10,12 @@ use crate::{
   },
 };
 
+fn unterminated<'src>(start: &ParseSrc<'src>) -> ParseError<'src> {
+  ParseError::new(start.location, "unterminated string")
+}
+
+// Strings can't span lines, except raw ones. The usual C escapes are supported,
+// along with `\uXXXX` and `\UXXXXXXXX` for Unicode code points.
 pub fn parse_double_quoted_string<'src>(
   mut src: ParseSrc<'src>,
 ) -> ParseResult<'src, Expression<'src>> {
@@ -18,27 +24,191 @@ pub fn parse_double_quoted_string<'src>(
 
   src = parse_char('"', src)?.src;
 
-  while let Some(next) = src.next() {
-    if next == '"' {
-      break;
-    } else if next == '\\' {
+  loop {
+    let escape = src.clone();
+
+    match src.next() {
+      Some('"') => break,
+      Some('\\') => accum.push(parse_escape(&start, &escape, &mut src)?),
+      Some('\n') | None => return Err(unterminated(&start)),
+      Some(next) => accum.push(next),
+    }
+  }
+
+  let span = src.span_from(&start);
+  Ok(ParseSuccess {
+    value: Expression::new(ExpressionKind::String(DoubleQuoted(accum)), span),
+    span,
+    src,
+  })
+}
+
+// The character escaped by the sequence at `escape`, whose backslash has just been
+// read from `src`.
+fn parse_escape<'src>(
+  start: &ParseSrc<'src>,
+  escape: &ParseSrc<'src>,
+  src: &mut ParseSrc<'src>,
+) -> Result<char, ParseError<'src>> {
+  let Some(next) = src.next() else {
+    return Err(unterminated(start));
+  };
+
+  let c = match next {
+    'n' => '\n',
+    't' => '\t',
+    'r' => '\r',
+    'a' => '\x07',
+    'b' => '\x08',
+    'f' => '\x0c',
+    'v' => '\x0b',
+    '\\' | '"' | '\'' | '?' => next,
+    '0'..='7' => {
+      let mut value = next.to_digit(8).unwrap();
+      for _ in 0..2 {
+        let Some(digit) = src.peek().and_then(|c| c.to_digit(8)) else {
+          break;
+        };
+        src.next();
+        value = value * 8 + digit;
+      }
+
+      ascii(escape, src, value)?
+    }
+    'x' => {
+      let value = parse_hex_digits(escape, src, 1, 2)?;
+      ascii(escape, src, value)?
+    }
+    'u' | 'U' => {
+      let digits = if next == 'u' { 4 } else { 8 };
+      let value = parse_hex_digits(escape, src, digits, digits)?;
+
+      char::from_u32(value).ok_or_else(|| {
+        ParseError::new(
+          escape.location,
+          format!("`{}` is not a valid code point", sequence(escape, src)),
+        )
+      })?
+    }
+    '\n' => return Err(unterminated(start)),
+    _ => {
       return Err(ParseError::new(
-        start.location,
-        format!("currently, backslashes are not considered valid in strings."),
+        escape.location,
+        format!("unknown escape sequence `{}`", sequence(escape, src)),
       ));
     }
+  };
+
+  Ok(c)
+}
+
+// Between `min` and `max` hex digits.
+fn parse_hex_digits<'src>(
+  escape: &ParseSrc<'src>,
+  src: &mut ParseSrc<'src>,
+  min: usize,
+  max: usize,
+) -> Result<u32, ParseError<'src>> {
+  let mut value = 0;
+  let mut count = 0;
+
+  while count < max {
+    let Some(digit) = src.peek().and_then(|c| c.to_digit(16)) else {
+      break;
+    };
+    src.next();
+    value = value * 16 + digit;
+    count += 1;
+  }
+
+  if count < min {
+    return Err(ParseError::new(
+      escape.location,
+      format!(
+        "expected {} hex digits in escape sequence `{}`",
+        min,
+        sequence(escape, src)
+      ),
+    ));
+  }
+
+  Ok(value)
+}
+
+// Strings are UTF-8, so bytes past ASCII can only be written as code points.
+fn ascii<'src>(
+  escape: &ParseSrc<'src>,
+  src: &ParseSrc<'src>,
+  value: u32,
+) -> Result<char, ParseError<'src>> {
+  if value > 0x7f {
+    return Err(ParseError::new(
+      escape.location,
+      format!(
+        "`{}` is not ASCII, use `\\u` for other characters",
+        sequence(escape, src)
+      ),
+    ));
+  }
+
+  Ok(char::from_u32(value).unwrap())
+}
 
-    accum.push(next);
+// The escape sequence read so far, for errors.
+fn sequence<'src>(escape: &ParseSrc<'src>, src: &ParseSrc<'src>) -> &'src str {
+  src.slice(src.span_from(escape))
+}
+
+// `r"..."`, or `r#"..."#` with as many `#` as needed for the string not to contain
+// its end. Nothing is escaped, and newlines are kept.
+pub fn parse_raw_string<'src>(
+  mut src: ParseSrc<'src>,
+) -> ParseResult<'src, Expression<'src>> {
+  let start = src.clone();
+
+  src = parse_char('r', src)?.src;
+
+  let mut hashes = 0;
+  while src.peek() == Some('#') {
+    src.next();
+    hashes += 1;
   }
 
+  src = parse_char('"', src)?.src;
+  let content_start = src.clone();
+
+  let content = loop {
+    let content_end = src.clone();
+
+    match src.next() {
+      Some('"') => {
+        let mut closing = 0;
+        while closing < hashes && src.peek() == Some('#') {
+          src.next();
+          closing += 1;
+        }
+
+        if closing == hashes {
+          break src.slice(content_end.span_from(&content_start));
+        }
+      }
+      Some(_) => {}
+      None => return Err(unterminated(&start)),
+    }
+  };
+
   let span = src.span_from(&start);
   Ok(ParseSuccess {
-    value: Expression::new(ExpressionKind::String(DoubleQuoted(accum)), span),
+    value: Expression::new(
+      ExpressionKind::String(DoubleQuoted(content.to_string())),
+      span,
+    ),
     span,
     src,
   })
 }
 
+// Like C header names, backslashes are kept as they are.
 pub fn parse_angle_bracketed_string<'src>(
   mut src: ParseSrc<'src>,
 ) -> ParseResult<'src, Expression<'src>> {
@@ -47,17 +217,12 @@ pub fn parse_angle_bracketed_string<'src>(
 
   src = parse_char('<', src)?.src;
 
-  while let Some(next) = src.next() {
-    if next == '>' {
-      break;
-    } else if next == '\\' {
-      return Err(ParseError::new(
-        start.location,
-        format!("currently, backslashes are not considered valid in strings."),
-      ));
+  loop {
+    match src.next() {
+      Some('>') => break,
+      Some('\n') | None => return Err(unterminated(&start)),
+      Some(next) => accum.push(next),
     }
-
-    accum.push(next);
   }
 
   let span = src.span_from(&start);
@@ -73,7 +238,9 @@ mod tests {
   use crate::parse::{
     ast::string,
     data::src::ParseSrc,
-    expressions::string::{parse_angle_bracketed_string, parse_double_quoted_string},
+    expressions::string::{
+      parse_angle_bracketed_string, parse_double_quoted_string, parse_raw_string,
+    },
   };
 
   #[test]
@@ -90,9 +257,30 @@ mod tests {
     assert_eq!(result.value, string!("", "a b c d"));
     assert_eq!(result.src.remaining_str(), " 6789");
 
-    // Backslash.
-    let src = ParseSrc::new(None, "\"ab\\c1\"");
-    let _ = parse_double_quoted_string(src).expect_err("parse not error");
+    // Escapes.
+    let src = ParseSrc::new(None, r#""C:\\a\tb\"\x41\101\u00e9\U0001F600""#);
+    let result = parse_double_quoted_string(src).expect("parse error");
+    assert_eq!(result.value, string!("", "C:\\a\tb\"AA\u{e9}\u{1F600}"));
+
+    // Bad escapes.
+    for text in [
+      r#""\q""#,
+      r#""\x""#,
+      r#""\xff""#,
+      r#""\u12""#,
+      r#""\ud800""#,
+    ] {
+      let src = ParseSrc::new(None, text);
+      let _ = parse_double_quoted_string(src).expect_err("parse not error");
+    }
+
+    // Unterminated.
+    for text in ["\"abc", "\"abc\ndef\"", "\"abc\\\""] {
+      let src = ParseSrc::new(None, text);
+      let err = parse_double_quoted_string(src).expect_err("parse not error");
+      assert_eq!(err.message(), "unterminated string");
+      assert_eq!(err.location().byte_offset, 0);
+    }
 
     // Different characters.
     let src = ParseSrc::new(None, "abc1");
@@ -115,10 +303,34 @@ mod tests {
 
     // Backslash.
     let src = ParseSrc::new(None, "<ab\\c1>");
-    let _ = parse_double_quoted_string(src).expect_err("parse not error");
+    let result = parse_angle_bracketed_string(src).expect("parse error");
+    assert_eq!(result.value, string!(<>, "ab\\c1"));
+
+    // Unterminated.
+    let src = ParseSrc::new(None, "<abc");
+    let _ = parse_angle_bracketed_string(src).expect_err("parse not error");
 
     // Different characters.
     let src = ParseSrc::new(None, "abc1");
     let _ = parse_angle_bracketed_string(src).expect_err("parse not error");
   }
+
+  #[test]
+  fn test_parse_raw_string() {
+    // Good.
+    let src = ParseSrc::new(None, r#"r"C:\a\b.h" x"#);
+    let result = parse_raw_string(src).expect("parse error");
+    assert_eq!(result.value, string!("", r"C:\a\b.h"));
+    assert_eq!(result.src.remaining_str(), " x");
+
+    // Hashes.
+    let src = ParseSrc::new(None, "r##\"a \"# \n b\"##;");
+    let result = parse_raw_string(src).expect("parse error");
+    assert_eq!(result.value, string!("", "a \"# \n b"));
+    assert_eq!(result.src.remaining_str(), ";");
+
+    // Unterminated.
+    let src = ParseSrc::new(None, "r#\"abc\"");
+    let _ = parse_raw_string(src).expect_err("parse not error");
+  }
 }
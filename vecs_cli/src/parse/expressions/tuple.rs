@@ -0,0 +1,87 @@
+use crate::parse::{
+  ast::{Expression, ExpressionKind},
+  data::{
+    result::{ParseResult, ParseSuccess},
+    src::ParseSrc,
+  },
+  expressions::parse_basic_expression,
+  util::str::{parse_char, parse_whitespace},
+};
+
+// `($a, $b)`, the parameters of a macro or the arguments of a call to one.
+pub fn parse_tuple<'src>(
+  mut src: ParseSrc<'src>,
+) -> ParseResult<'src, Expression<'src>> {
+  let start = src.clone();
+
+  let mut items = Vec::<Expression>::new();
+
+  let marker = parse_char('(', src)?;
+  src = parse_whitespace(marker.src)?.src;
+
+  loop {
+    if let Ok(finish) = parse_char(')', src.clone()) {
+      src = finish.src;
+      break;
+    }
+
+    let item = parse_basic_expression(src)?;
+    src = parse_whitespace(item.src)?.src;
+    items.push(item.value);
+
+    // The last item doesn't need a `,`.
+    if let Ok(sep) = parse_char(',', src.clone()) {
+      src = parse_whitespace(sep.src)?.src;
+    } else {
+      let finish = parse_char(')', src)
+        .map_err(|e| e.wrap_message("expected `,` or `)` after a tuple item"))?;
+      src = finish.src;
+      break;
+    }
+  }
+
+  let span = src.span_from(&start);
+  Ok(ParseSuccess {
+    value: Expression::new(ExpressionKind::Tuple(items), span),
+    span,
+    src,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::parse::{
+    ast::{Expression, ExpressionKind, sym, var},
+    data::src::ParseSrc,
+    expressions::tuple::parse_tuple,
+  };
+
+  #[test]
+  fn test_parse_tuple() {
+    // Good empty.
+    let src = ParseSrc::new(None, "() a");
+    let result = parse_tuple(src).expect("parse error");
+    assert_eq!(result.value.kind, ExpressionKind::Tuple(vec![]));
+    assert_eq!(result.src.remaining_str(), " a");
+
+    // Good.
+    let src = ParseSrc::new(None, "( $name, foo ) a");
+    let expected: Vec<Expression> = vec![var!("name"), sym!("foo")];
+    let result = parse_tuple(src).expect("parse error");
+    assert_eq!(result.value.kind, ExpressionKind::Tuple(expected));
+    assert_eq!(result.src.remaining_str(), " a");
+
+    // Good trailing comma.
+    let src = ParseSrc::new(None, "($name,)");
+    let result = parse_tuple(src).expect("parse error");
+    assert_eq!(result.value.kind, ExpressionKind::Tuple(vec![var!("name")]));
+
+    // Missing separator.
+    let src = ParseSrc::new(None, "($a $b)");
+    let _ = parse_tuple(src).expect_err("parse not error");
+
+    // Unterminated.
+    let src = ParseSrc::new(None, "($a, ");
+    let _ = parse_tuple(src).expect_err("parse not error");
+  }
+}
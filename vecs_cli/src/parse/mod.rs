@@ -2,53 +2,53 @@ pub mod ast;
 pub mod comments;
 pub mod data;
 pub mod expressions;
+pub mod tokens;
 mod util;
 
 use std::collections::VecDeque;
 
 use crate::parse::{
   ast::{Ast, Expression},
-  comments::parse_comment,
   data::{
-    result::{ParseResult, ParseSuccess},
+    result::{ParseError, ParseResult, ParseSuccess},
     src::ParseSrc,
   },
   expressions::parse_expression,
+  tokens::{Token, TokenKind},
   util::str::{parse_char, parse_whitespace},
 };
 
-pub fn strip_comments(t: &mut str) {
-  let mut src = ParseSrc::from(&*t);
-  let mut spans = Vec::<(usize, usize)>::new(); // Just start and end bytes.
-
-  loop {
-    match parse_comment(src.clone()) {
-      Ok(success) => {
-        src = success.src;
-
-        let span = success.span;
-        spans.push((span.start_byte_offset, span.end_byte_offset));
-      }
-      Err(_) => {
-        let c = src.next();
-
-        match c {
-          Some(_) => {}
-          None => break,
-        }
-      }
+// The text with its comments blanked out, for the parser. Offsets and line breaks are
+// kept, so locations stay the same. `tokens` are the tokens of `text`, which know that
+// `//` in a string doesn't start a comment. A `/*` without a `*/` is an error.
+pub fn strip_comments<'src>(
+  text: &str,
+  tokens: &[Token<'src>],
+) -> Result<String, ParseError<'src>> {
+  let mut stripped = String::with_capacity(text.len());
+  let mut end = 0;
+
+  for token in tokens.iter().filter(|t| t.kind == TokenKind::Comment) {
+    let span = token.span;
+    let comment = span.slice(text);
+
+    if comment.starts_with("/*") && (comment.len() < 4 || !comment.ends_with("*/")) {
+      return Err(ParseError::new(span.start(), "unterminated block comment"));
     }
-  }
 
-  for (start, end) in spans {
-    // SAFETY: Start and end come from `Location`s, which always advances in UTF-8
-    // code points and so always lands in valid boundaries.
-    unsafe {
-      for byte in t[start..end].as_bytes_mut() {
-        *byte = b' ';
+    stripped.push_str(&text[end..span.start_byte_offset]);
+    for c in comment.chars() {
+      if c == '\n' || c == '\r' {
+        stripped.push(c);
+      } else {
+        stripped.extend(std::iter::repeat_n(' ', c.len_utf8()));
       }
     }
+    end = span.end_byte_offset;
   }
+
+  stripped.push_str(&text[end..]);
+  Ok(stripped)
 }
 
 pub fn parse<'src>(mut src: ParseSrc<'src>) -> ParseResult<'src, Ast<'src>> {
@@ -81,7 +81,7 @@ pub fn parse<'src>(mut src: ParseSrc<'src>) -> ParseResult<'src, Ast<'src>> {
   }
 
   return Ok(ParseSuccess {
-    value: Ast(parsed),
+    value: Ast::new(parsed),
     span: src.span_from(&start),
     src,
   });
@@ -95,11 +95,12 @@ mod tests {
     ast::{Ast, app, list, sym},
     data::src::ParseSrc,
     parse, strip_comments,
+    tokens::tokenize,
   };
 
   #[test]
   fn test_strip_comments() {
-    let mut src_str = String::from(
+    let src_str = String::from(
       "
 // this component is a component
 component airton {\r\nint x;
@@ -119,8 +120,40 @@ component airton {\r\nint x;
 ",
     );
 
-    strip_comments(&mut src_str);
-    assert_eq!(&src_str, &target_str);
+    let tokens = tokenize(ParseSrc::from(src_str.as_str()));
+    let stripped = strip_comments(&src_str, &tokens).expect("strip error");
+    assert_eq!(&stripped, &target_str);
+
+    // Strings and block comments.
+    let text = "include \"proto//gen.h\" /* é\n */ <a/*b>; // \"c\"";
+    let tokens = tokenize(ParseSrc::from(text));
+    let stripped = strip_comments(text, &tokens).expect("strip error");
+    assert_eq!(
+      stripped,
+      "include \"proto//gen.h\"      \n    <a/*b>;       "
+    );
+  }
+
+  #[test]
+  fn test_strip_unterminated_comment() {
+    let text = "component a;\n  /* b\n";
+    let tokens = tokenize(ParseSrc::from(text));
+    let err = strip_comments(text, &tokens).expect_err("strip not error");
+    assert_eq!(err.message(), "unterminated block comment");
+    assert_eq!((err.location().line, err.location().column), (2, 2));
+    assert_eq!(err.location().byte_offset, 15);
+
+    // The error is at the first `/*` outside of strings and line comments.
+    let text = "include \"a/*\"; // /*\ncomponent b; /* c */ /* d";
+    let tokens = tokenize(ParseSrc::from(text));
+    let err = strip_comments(text, &tokens).expect_err("strip not error");
+    assert_eq!((err.location().line, err.location().column), (2, 21));
+
+    // Only `/*` and `*/` matter in block comments.
+    let text = "/* \"a */ b;";
+    let tokens = tokenize(ParseSrc::from(text));
+    let stripped = strip_comments(text, &tokens).expect("strip error");
+    assert_eq!(stripped, "         b;");
   }
 
   #[test]
@@ -150,7 +183,7 @@ component airton {\r\nint x;
     let result = parse(src).expect("parse error");
     assert_eq!(
       result.value,
-      Ast(VecDeque::from([
+      Ast::new(VecDeque::from([
         app!(
           sym!("component"),
           sym!("transform"),
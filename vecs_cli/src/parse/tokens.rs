@@ -0,0 +1,172 @@
+use crate::parse::data::{src::ParseSrc, str::Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+  Whitespace,
+  // Both `// ...` and `/* ... */`.
+  Comment,
+  // `"..."`, `r"..."` and `<...>`.
+  String,
+  // `{`, `}`, `;` and `,`.
+  Punct,
+  // Anything else: symbols, integers, variables and `...`.
+  Word,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'src> {
+  pub kind: TokenKind,
+  pub span: Span<'src>,
+}
+
+// Splits the source into tokens without losing anything: the tokens' slices add up to
+// the whole source. It knows that `//` in a string doesn't start a comment. It never
+// fails, unterminated comments and strings run to the end of the source, and it's up
+// to `strip_comments` and the parser to complain about them.
+pub fn tokenize<'src>(mut src: ParseSrc<'src>) -> Vec<Token<'src>> {
+  let mut tokens = Vec::new();
+
+  while let Some(c) = src.peek() {
+    let start = src.clone();
+    src.next();
+
+    let kind = match c {
+      c if c.is_whitespace() => {
+        skip_while(&mut src, char::is_whitespace);
+        TokenKind::Whitespace
+      }
+      '/' if src.peek() == Some('/') => {
+        skip_while(&mut src, |c| c != '\n');
+        TokenKind::Comment
+      }
+      '/' if src.peek() == Some('*') => {
+        src.next();
+
+        let mut previous = None;
+        for c in src.by_ref() {
+          if previous == Some('*') && c == '/' {
+            break;
+          }
+          previous = Some(c);
+        }
+
+        TokenKind::Comment
+      }
+      '"' => {
+        while let Some(c) = src.next() {
+          match c {
+            '"' => break,
+            '\\' => {
+              src.next();
+            }
+            _ => {}
+          }
+        }
+
+        TokenKind::String
+      }
+      '<' => {
+        skip_while(&mut src, |c| c != '>');
+        src.next();
+        TokenKind::String
+      }
+      'r' if skip_raw_string(&mut src) => TokenKind::String,
+      '{' | '}' | ';' | ',' => TokenKind::Punct,
+      _ => {
+        while !src.peek().is_none_or(|c| ends_word(c, &src)) {
+          src.next();
+        }
+        TokenKind::Word
+      }
+    };
+
+    tokens.push(Token {
+      kind,
+      span: src.span_from(&start),
+    });
+  }
+
+  tokens
+}
+
+fn skip_while(src: &mut ParseSrc, f: impl Fn(char) -> bool) {
+  while src.peek().is_some_and(&f) {
+    src.next();
+  }
+}
+
+// Skips the rest of a raw string, right after its `r`. Returns false, without
+// skipping anything, if it isn't one.
+fn skip_raw_string(src: &mut ParseSrc) -> bool {
+  let mut chars = src.chars.clone();
+  let hashes = chars.by_ref().take_while(|c| *c == '#').count();
+  let mut rest = src.clone();
+
+  for _ in 0..hashes {
+    rest.next();
+  }
+  if rest.next() != Some('"') {
+    return false;
+  }
+
+  let end = format!("\"{}", "#".repeat(hashes));
+  while !rest.is_empty() && !rest.chars.as_str().starts_with(&end) {
+    rest.next();
+  }
+  for _ in 0..end.len() {
+    rest.next();
+  }
+
+  *src = rest;
+  true
+}
+
+// Whether `c`, about to be read from `src`, starts another token.
+fn ends_word(c: char, src: &ParseSrc) -> bool {
+  match c {
+    '/' => {
+      let mut chars = src.chars.clone();
+      chars.next();
+      matches!(chars.next(), Some('/' | '*'))
+    }
+    c => c.is_whitespace() || matches!(c, '{' | '}' | ';' | ',' | '"' | '<'),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::parse::{
+    data::src::ParseSrc,
+    tokens::{TokenKind, tokenize},
+  };
+
+  #[test]
+  fn test_tokenize() {
+    let text = concat!(
+      "include \"proto//gen.h\"; // generated\n",
+      "component a { int/*x*/b, };\r\n",
+      "include r#\"C:\\\"//\"#, \"\\\"//\";\n",
+      "/* unterminated",
+    );
+
+    let tokens = tokenize(ParseSrc::from(text));
+    let slices = tokens
+      .iter()
+      .map(|t| t.span.slice(text))
+      .collect::<Vec<_>>();
+
+    assert_eq!(slices.concat(), text);
+
+    let comments = tokens
+      .iter()
+      .filter(|t| t.kind == TokenKind::Comment)
+      .map(|t| t.span.slice(text))
+      .collect::<Vec<_>>();
+
+    assert_eq!(comments, ["// generated", "/*x*/", "/* unterminated"]);
+    assert_eq!(slices[2], "\"proto//gen.h\"");
+    assert_eq!(slices[13], "int");
+    assert_eq!(slices[23], "r#\"C:\\\"//\"#");
+    assert_eq!(slices[26], "\"\\\"//\"");
+  }
+}
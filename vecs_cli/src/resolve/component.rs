@@ -3,6 +3,7 @@ use std::collections::VecDeque;
 use crate::resolve::{
   ResolveMeta,
   cst::{TypeName, TypeNameBuilder},
+  field::resolve_fields,
   result::{ResolveError, ResolveResult},
   values::{Value, ValueKind},
 };
@@ -45,6 +46,24 @@ pub fn resolve_component<'src>(
     ));
   }
 
+  if let Some(ValueKind::List(_)) = values.front().map(|v| &v.kind) {
+    let body = values.pop_front().unwrap();
+
+    for field in resolve_fields("component", body)? {
+      s.add_field(field);
+    }
+
+    if let Some(extra) = values.pop_front() {
+      return Err(ResolveError::new(
+        extra.span,
+        format!(
+          "unexpected value in component: {} (maybe you're missing a semicolon?)",
+          extra
+        ),
+      ));
+    }
+  }
+
   while let Some(value) = values.pop_front() {
     if let ValueKind::Symbol(name) = value.kind {
       s.add_type_component(name);
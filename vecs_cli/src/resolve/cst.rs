@@ -1,4 +1,6 @@
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::collections::BTreeSet;
+
+use indexmap::IndexMap;
 
 use derive_builder::Builder;
 
@@ -19,11 +21,21 @@ pub struct TypeName<'src> {
 
   #[builder(field(vis = "pub"))]
   pub type_components: Vec<&'src str>,
+
+  // Only present if the struct was declared with a body, in which case
+  // `type_components` is empty.
+  #[builder(default = vec![], field(vis = "pub"))]
+  pub fields: Vec<Field<'src>>,
+
+  // The `///` comments right before the declaration, without the slashes. Written
+  // above the corresponding declarations in the header.
+  #[builder(default = vec![], field(vis = "pub"))]
+  pub doc: Vec<&'src str>,
 }
 
 impl<'src> TypeName<'src> {
   pub fn is_empty(&self) -> bool {
-    self.type_components.is_empty()
+    self.type_components.is_empty() && self.fields.is_empty()
   }
 }
 
@@ -35,6 +47,23 @@ impl<'src> TypeNameBuilder<'src> {
       self.type_components = Some(vec![component]);
     }
   }
+
+  pub fn add_field(&mut self, field: Field<'src>) {
+    if let Some(ref mut fields) = self.fields {
+      fields.push(field);
+    } else {
+      self.fields = Some(vec![field]);
+    }
+  }
+}
+
+// A field of a struct declared in the spec, e.g. `double x` in
+// `component transform { double x; double y };`.
+#[derive(Debug, Clone)]
+pub struct Field<'src> {
+  pub span: Span<'src>,
+  pub name: &'src str,
+  pub type_components: Vec<&'src str>,
 }
 
 impl<'src> PartialEq for TypeName<'src> {
@@ -76,7 +105,7 @@ impl<'src> Component<'src> {
   }
 
   pub fn is_empty(&self) -> bool {
-    self.typ.type_components.is_empty()
+    self.typ.is_empty()
   }
 }
 
@@ -95,6 +124,10 @@ pub struct Node<'src> {
   // it just means that all components afterwards are zero.
   #[builder(default = vec![], field(vis = "pub"))]
   pub mask: Vec<u64>,
+
+  // See `TypeName::doc`.
+  #[builder(default = vec![], field(vis = "pub"))]
+  pub doc: Vec<&'src str>,
 }
 
 impl<'src> Node<'src> {
@@ -147,6 +180,10 @@ pub struct System<'src> {
   // there are zero (this system is not used at all).
   #[builder(default = 0, field(vis = "pub"))]
   pub in_state_count: usize,
+
+  // See `TypeName::doc`.
+  #[builder(default = vec![], field(vis = "pub"))]
+  pub doc: Vec<&'src str>,
 }
 
 // States.
@@ -164,6 +201,10 @@ pub struct State<'src> {
 
   #[builder(field(vis = "pub"))]
   pub nodes: Vec<&'src str>,
+
+  // See `TypeName::doc`.
+  #[builder(default = vec![], field(vis = "pub"))]
+  pub doc: Vec<&'src str>,
 }
 
 impl<'src> PartialEq for State<'src> {
@@ -185,40 +226,82 @@ impl<'src> StateBuilder<'src> {
   }
 }
 
+// Includes.
+
+#[derive(Debug, Clone)]
+pub struct Include<'src> {
+  pub span: Span<'src>,
+  pub path: StringKind,
+}
+
 // Settings.
 
-// #[derive(Debug, Copy, Clone)]
-// pub struct Settings {}
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+  // Wrap systems and deferred operations in timing counters.
+  pub profile: bool,
+  // Prepended to every generated symbol.
+  pub prefix: Prefix,
+  // Inline the runtime and the implementation into the header.
+  pub single_header: bool,
+  // How many entities there is room for after initialization.
+  pub entity_capacity: u32,
+}
+
+// Symbol prefix, e.g. `vecs` in `vecs_engine_t` and `VECS_ECS_H`.
+#[derive(Debug, Clone)]
+pub struct Prefix {
+  pub lower: String,
+  pub upper: String,
+}
+
+impl Prefix {
+  // Whether `prefix` can start a C identifier and survive changing its case.
+  pub fn is_valid(prefix: &str) -> bool {
+    let mut chars = prefix.chars();
+
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+      && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+  }
 
-// impl Default for Settings {
-//   fn default() -> Self {
-//     Self {}
-//   }
-// }
+  pub fn new(prefix: &str) -> Self {
+    Self {
+      lower: prefix.to_ascii_lowercase(),
+      upper: prefix.to_ascii_uppercase(),
+    }
+  }
+}
+
+impl Default for Prefix {
+  fn default() -> Self {
+    Self::new("vecs")
+  }
+}
 
 // CST. See the top comment for what it means.
 
 #[derive(Debug, Clone, Default)]
 pub struct Cst<'src> {
-  pub includes: Vec<StringKind>,
-  pub globals: HashMap<&'src str, TypeName<'src>>,
-  // pub settings: Settings,
-  pub components: HashMap<&'src str, Component<'src>>,
-  pub events: HashMap<&'src str, TypeName<'src>>,
-  pub systems: HashMap<&'src str, System<'src>>,
-  pub nodes: HashMap<&'src str, Node<'src>>,
+  pub includes: Vec<Include<'src>>,
+  // Everything is kept in declaration order, so that the output only depends on the
+  // input. That is also the order in which events are processed.
+  pub globals: IndexMap<&'src str, TypeName<'src>>,
+  pub settings: Settings,
+  pub components: IndexMap<&'src str, Component<'src>>,
+  pub events: IndexMap<&'src str, TypeName<'src>>,
+  pub systems: IndexMap<&'src str, System<'src>>,
+  pub nodes: IndexMap<&'src str, Node<'src>>,
   pub node_mask_arr_size: u16,
 
-  // We use a BTreeMap instead of a HashMap to have a consistent order of iteration.
-  // That is important in codegen.
-  pub states: BTreeMap<&'src str, State<'src>>,
+  // State ids are indices into this, so generators rely on its order of iteration.
+  pub states: IndexMap<&'src str, State<'src>>,
 }
 
 // These methods do not check for errors (e.g. a non-existent component in a node or
 // system) because it's usually more efficient to check for such things during
 // resolution.
 impl<'src> Cst<'src> {
-  pub fn add_include(&mut self, include: StringKind) {
+  pub fn add_include(&mut self, include: Include<'src>) {
     self.includes.push(include);
   }
 
@@ -3,6 +3,7 @@ use std::collections::VecDeque;
 use crate::resolve::{
   ResolveMeta,
   cst::{TypeName, TypeNameBuilder},
+  field::resolve_fields,
   result::{ResolveError, ResolveResult},
   values::{Value, ValueKind},
 };
@@ -52,6 +53,24 @@ pub fn resolve_event<'src>(
     ));
   }
 
+  if let Some(ValueKind::List(_)) = values.front().map(|v| &v.kind) {
+    let body = values.pop_front().unwrap();
+
+    for field in resolve_fields("event", body)? {
+      s.add_field(field);
+    }
+
+    if let Some(extra) = values.pop_front() {
+      return Err(ResolveError::new(
+        extra.span,
+        format!(
+          "unexpected value in event: {} (maybe you're missing a semicolon?)",
+          extra
+        ),
+      ));
+    }
+  }
+
   while let Some(value) = values.pop_front() {
     if let ValueKind::Symbol(name) = value.kind {
       s.add_type_component(name);
@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+
+use crate::{
+  parse::data::str::Span,
+  resolve::{
+    cst::Field,
+    result::{ResolveError, ResolveResult},
+    values::{Value, ValueKind},
+  },
+};
+
+// Resolves the body of a struct, e.g. `{ double x; double y }`. `what` is only used
+// in error messages.
+pub fn resolve_fields<'src>(
+  what: &str,
+  body: Value<'src>,
+) -> ResolveResult<'src, Vec<Field<'src>>> {
+  let mut fields = Vec::<Field<'src>>::new();
+
+  if let ValueKind::List(values) = body.kind {
+    for value in values {
+      if let ValueKind::Application(values) = value.kind {
+        let field = resolve_field(what, value.span, values)?;
+
+        if let Some(previous) = fields.iter().find(|f| f.name == field.name) {
+          return Err(ResolveError::new(
+            field.span,
+            format!(
+              "duplicated {} field '{}'. previously defined at {}",
+              what, field.name, previous.span
+            ),
+          ));
+        }
+
+        fields.push(field);
+      } else {
+        panic!(
+          "malformed ast: root expression is not an application. this is a bug. run with VECS_DEBUG_AST set to dump the AST",
+        );
+      }
+    }
+  } else {
+    return Err(ResolveError::new(
+      body.span,
+      format!("body of {} should be a list. instead it's {}", what, body),
+    ));
+  }
+
+  Ok(fields)
+}
+
+fn resolve_field<'src>(
+  what: &str,
+  span: Span<'src>,
+  values: VecDeque<Value<'src>>,
+) -> ResolveResult<'src, Field<'src>> {
+  if values.len() < 2 {
+    return Err(ResolveError::new(
+      span,
+      format!(
+        "{} field should be a type followed by the field name, e.g. `double x`",
+        what
+      ),
+    ));
+  }
+
+  let mut symbols = Vec::<&'src str>::with_capacity(values.len());
+
+  for value in values.iter() {
+    if let ValueKind::Symbol(symbol) = value.kind {
+      symbols.push(symbol);
+    } else {
+      return Err(ResolveError::new(
+        value.span,
+        format!(
+          "{} field must be a sequence of symbols. instead found {}",
+          what, value
+        ),
+      ));
+    }
+  }
+
+  // Checked above.
+  let name = symbols.pop().unwrap();
+
+  Ok(Field {
+    span,
+    name,
+    type_components: symbols,
+  })
+}
@@ -4,6 +4,7 @@ use crate::{
   common::StringKind,
   resolve::{
     ResolveMeta,
+    cst::Include,
     result::{ResolveError, ResolveResult},
     values::{Value, ValueKind},
   },
@@ -12,7 +13,7 @@ use crate::{
 pub fn resolve_include<'src>(
   meta: ResolveMeta<'src, '_>,
   mut cdr: VecDeque<Value<'src>>,
-) -> ResolveResult<'src, StringKind> {
+) -> ResolveResult<'src, Include<'src>> {
   let maybe_value = cdr.pop_front();
 
   if !cdr.is_empty() {
@@ -28,19 +29,35 @@ pub fn resolve_include<'src>(
 
   if let Some(value) = maybe_value {
     if let ValueKind::String(path) = value.kind {
-      return Ok(path);
+      // The path is written as it is in an `#include`, which can't escape anything.
+      if let StringKind::DoubleQuoted(x) = &path
+        && x.contains(|c: char| c == '"' || c.is_control())
+      {
+        return Err(ResolveError::new(
+          value.span,
+          format!(
+            "include path can't contain quotes or control characters: {}",
+            path
+          ),
+        ));
+      }
+
+      Ok(Include {
+        span: meta.span,
+        path,
+      })
     } else {
-      return Err(ResolveError::new(
+      Err(ResolveError::new(
         value.span,
         format!("include path must be a string. instead found {}", value),
-      ));
+      ))
     }
   } else {
-    return Err(ResolveError::new(
+    Err(ResolveError::new(
       meta.span,
       format!(
         "an include tag must be followed by the include path, either in double quotes or angle brackets"
       ),
-    ));
+    ))
   }
 }
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use crate::{
+  parse::{
+    ast::{Expression, ExpressionKind, ListEntry},
+    data::str::Span,
+  },
+  resolve::{
+    ResolveMeta,
+    result::{ResolveError, ResolveResult},
+    values::{Value, VarTable},
+  },
+};
+
+// Declarations that can't be shadowed by a macro.
+const TAGS: [&str; 9] = [
+  "component",
+  "event",
+  "node",
+  "system",
+  "state",
+  "include",
+  "global",
+  "set",
+  "macro",
+];
+
+// `macro name($a, $b) { ... }`, or `macro name $a $b { ... }`. Calling it, e.g.
+// `name(x, { int y });` or `name x { int y };`, resolves the declarations in its body
+// with `$a` and `$b` bound to the arguments.
+#[derive(Debug, Clone)]
+pub struct Macro<'src> {
+  pub name: &'src str,
+  pub span: Span<'src>,
+  pub params: Vec<&'src str>,
+  pub body: Vec<Expression<'src>>,
+}
+
+impl<'src> Macro<'src> {
+  // The variables of a call to the macro, spanning `call`.
+  pub fn bind(
+    &self,
+    call: Span<'src>,
+    args: Vec<Value<'src>>,
+  ) -> ResolveResult<'src, VarTable<'src>> {
+    if args.len() != self.params.len() {
+      return Err(ResolveError::new(
+        call,
+        format!(
+          "macro `{}` takes {} argument{}, but {} were given",
+          self.name,
+          self.params.len(),
+          if self.params.len() == 1 { "" } else { "s" },
+          args.len()
+        ),
+      ));
+    }
+
+    let mut table = VarTable::new();
+    for (param, arg) in self.params.iter().zip(args) {
+      table.add(param, arg);
+    }
+
+    Ok(table)
+  }
+}
+
+// Unlike other declarations, macros are resolved from the expressions, since their
+// variables are only bound when they're called.
+pub fn resolve_macro<'src>(
+  meta: ResolveMeta<'src, '_>,
+  macros: &HashMap<&'src str, Macro<'src>>,
+  mut exprs: Vec<Expression<'src>>,
+) -> ResolveResult<'src, Macro<'src>> {
+  let Some(ExpressionKind::List(_)) = exprs.last().map(|e| &e.kind) else {
+    return Err(ResolveError::new(
+      meta.span,
+      "a macro must end with its body, a list of declarations",
+    ));
+  };
+  let ExpressionKind::List(entries) = exprs.pop().unwrap().kind else {
+    unreachable!();
+  };
+
+  let mut exprs = exprs.into_iter();
+
+  let name = match exprs.next() {
+    Some(Expression {
+      kind: ExpressionKind::Symbol(name),
+      span,
+      ..
+    }) => {
+      if TAGS.contains(&name) {
+        return Err(ResolveError::new(
+          span,
+          format!("`{}` is a tag, it can't be the name of a macro", name),
+        ));
+      }
+
+      if let Some(previous) = macros.get(name) {
+        return Err(ResolveError::new(
+          span,
+          format!(
+            "duplicated macro name '{}'. previously defined at {}",
+            name, previous.span
+          ),
+        ));
+      }
+
+      name
+    }
+    Some(other) => {
+      return Err(ResolveError::new(other.span, "macro name must be a symbol"));
+    }
+    None => {
+      return Err(ResolveError::new(
+        meta.span,
+        "a macro tag must be followed by the macro name",
+      ));
+    }
+  };
+
+  let mut params = Vec::new();
+
+  for expr in untuple(exprs.collect()) {
+    let ExpressionKind::Variable(param) = expr.kind else {
+      return Err(ResolveError::new(
+        expr.span,
+        format!(
+          "the parameters of macro `{}` must be variables, e.g. `$name`",
+          name
+        ),
+      ));
+    };
+
+    if params.contains(&param) {
+      return Err(ResolveError::new(
+        expr.span,
+        format!("duplicated parameter ${} in macro `{}`", param, name),
+      ));
+    }
+
+    params.push(param);
+  }
+
+  let mut body = Vec::new();
+
+  for entry in entries {
+    match entry {
+      ListEntry::Expr(expr) => body.push(expr),
+      ListEntry::Embed(expr) => {
+        return Err(ResolveError::new(
+          expr.span,
+          "only lists of values can be embedded, not declarations",
+        ));
+      }
+    }
+  }
+
+  Ok(Macro {
+    name,
+    span: meta.span,
+    params,
+    body,
+  })
+}
+
+// The parameters or arguments of a macro, which may be in parentheses.
+pub fn untuple<'src>(mut exprs: Vec<Expression<'src>>) -> Vec<Expression<'src>> {
+  if let [
+    Expression {
+      kind: ExpressionKind::Tuple(_),
+      ..
+    },
+  ] = exprs.as_slice()
+    && let ExpressionKind::Tuple(items) = exprs.pop().unwrap().kind
+  {
+    return items;
+  }
+
+  exprs
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    parse::{data::src::ParseSrc, parse},
+    resolve::resolve,
+    spec::Spec,
+  };
+
+  #[test]
+  fn test_macros() {
+    let text = concat!(
+      "macro body $name $fields {\n",
+      "  /// Has mass.\n",
+      "  component $name { double mass; ...$fields };\n",
+      "  system $name on frame { $name };\n",
+      "};\n",
+      "macro pair $a $b { body $a {}; body $b { int size } };\n",
+      "/// The pair.\n",
+      "pair player rock;\n",
+    );
+
+    let spec = Spec::new("macros.vecs", text.to_string()).expect("spec error");
+    let cst = spec.resolve().expect("resolve error");
+
+    let fields = cst.components["rock"]
+      .typ
+      .fields
+      .iter()
+      .map(|f| f.name)
+      .collect::<Vec<_>>();
+    assert_eq!(fields, ["mass", "size"]);
+    assert!(cst.components.contains_key("player"));
+    assert!(cst.systems.contains_key("player"));
+
+    // The doc comment of a call goes before those of the declarations it expands to.
+    assert_eq!(
+      cst.components["rock"].typ.doc,
+      ["The pair.", "", "Has mass."]
+    );
+    assert_eq!(cst.systems["rock"].doc, ["The pair."]);
+
+    // Parameters and arguments in parentheses.
+    let text = concat!(
+      "macro physics_body($name) {\n",
+      "  component $name { double mass };\n",
+      "  system $name on frame { $name };\n",
+      "};\n",
+      "physics_body(ball);\n",
+      "physics_body crate;\n",
+    );
+
+    let ast = parse(ParseSrc::from(text)).expect("parse error").value;
+    let cst = resolve(ast).expect("resolve error");
+    assert_eq!(
+      cst.components.keys().copied().collect::<Vec<_>>(),
+      ["ball", "crate"]
+    );
+    assert!(cst.systems.contains_key("ball"));
+
+    // The error is in the definition, and the calls leading to it are kept.
+    let text = concat!(
+      "macro body $name { node $name { missing } };\n",
+      "macro pair $a $b { body $a; body $b };\n",
+      "pair x y;\n",
+    );
+
+    let ast = parse(ParseSrc::from(text)).expect("parse error").value;
+    let err = resolve(ast).expect_err("resolve not error");
+    let calls = err
+      .expansions()
+      .iter()
+      .map(|e| (e.name, e.call.start_line, e.definition.start_line))
+      .collect::<Vec<_>>();
+
+    assert_eq!(err.span().start_line, 1);
+    assert_eq!(calls, [("body", 2, 1), ("pair", 3, 2)]);
+  }
+}
@@ -1,8 +1,10 @@
 pub mod component;
 pub mod cst;
 pub mod event;
+pub mod field;
 pub mod global;
 pub mod include;
+pub mod macros;
 pub mod node;
 pub mod result;
 pub mod setting;
@@ -10,24 +12,34 @@ pub mod state;
 pub mod system;
 pub mod values;
 
+use std::collections::HashMap;
+
 use node::resolve_node;
 use setting::resolve_setting;
 
 use crate::{
-  parse::{ast::Ast, data::str::Span},
+  parse::{
+    ast::{Ast, Comments, Expression, ExpressionKind},
+    data::str::Span,
+  },
   resolve::{
     component::resolve_component,
     cst::Cst,
     event::resolve_event,
     global::resolve_global,
     include::resolve_include,
-    result::{ResolveError, ResolveResult},
+    macros::{Macro, resolve_macro, untuple},
+    result::{Expansion, ResolveError, ResolveResult},
     state::resolve_state,
     system::resolve_system,
     values::{ValueKind, VarTable},
   },
 };
 
+// How many macro calls can be nested, which is only reached by macros calling
+// themselves.
+const MAX_MACRO_DEPTH: usize = 64;
+
 // This bundles information that is communicated between functions but is only used
 // in error messages.
 #[derive(Debug, Copy, Clone)]
@@ -45,67 +57,30 @@ pub fn resolve<'src>(ast: Ast<'src>) -> ResolveResult<'src, Cst<'src>> {
     span: Span::default(),
     name: "frame",
     type_components: vec!["vecs_frame_t"],
+    fields: vec![],
+    doc: vec![],
   });
 
-  let exprs = ast.0;
+  let mut macros = HashMap::new();
 
-  for expr in exprs {
-    let span = expr.span.clone();
-    let info = ResolveMeta { span, cst: &cst };
+  for expr in ast.expressions {
+    // Macros are only defined at the top level, and can be called after that.
+    if head_symbol(&expr) == Some("macro") {
+      let meta = ResolveMeta {
+        span: expr.span,
+        cst: &cst,
+      };
+      let ExpressionKind::Application(mut exprs) = expr.kind else {
+        unreachable!();
+      };
+      exprs.remove(0);
 
-    let application = table.resolve(expr)?;
-
-    if let ValueKind::Application(mut els) = application.kind {
-      if els.is_empty() {
-        // TODO: Can this happen?
-        continue;
-      }
-
-      let car = els.pop_front().unwrap();
-
-      if car.kind == ValueKind::Symbol("component") {
-        let component = resolve_component(info, els)?;
-        cst.add_component(component);
-      } else if car.kind == ValueKind::Symbol("event") {
-        let event = resolve_event(info, els)?;
-        cst.add_event(event);
-      } else if car.kind == ValueKind::Symbol("node") {
-        let node = resolve_node(info, els)?;
-        cst.add_node(node);
-      } else if car.kind == ValueKind::Symbol("system") {
-        let (system, maybe_node) = resolve_system(info, els)?;
-        cst.add_system(system);
-
-        if let Some(node) = maybe_node {
-          cst.add_node(node);
-        }
-      } else if car.kind == ValueKind::Symbol("state") {
-        let state = resolve_state(info, els)?;
-        cst.add_state(state);
-      } else if car.kind == ValueKind::Symbol("include") {
-        let include = resolve_include(info, els)?;
-        cst.add_include(include);
-      } else if car.kind == ValueKind::Symbol("global") {
-        let global = resolve_global(info, els)?;
-        cst.add_global(global);
-      } else if car.kind == ValueKind::Symbol("set") {
-        resolve_setting(info.span, els, &mut cst)?;
-      } else if let ValueKind::Symbol(_) = car.kind {
-        return Err(ResolveError::new(car.span, format!("unknown tag {}", car)));
-      } else {
-        return Err(ResolveError::new(
-          car.span,
-          format!(
-            "expected a tag: `component`, `event`, `node`, `system`, `state`, `include` or `set`. instead found {}",
-            car,
-          ),
-        ));
-      }
-    } else {
-      panic!(
-        "malformed ast: root expression is not an application. this is a bug. run with VECS_DEBUG_AST set to dump the AST",
-      );
+      let definition = resolve_macro(meta, &macros, exprs)?;
+      macros.insert(definition.name, definition);
+      continue;
     }
+
+    resolve_declaration(&mut cst, &macros, &table, expr, &[], 0)?;
   }
 
   for system in cst.systems.values() {
@@ -119,3 +94,231 @@ pub fn resolve<'src>(ast: Ast<'src>) -> ResolveResult<'src, Cst<'src>> {
 
   Ok(cst)
 }
+
+// A declaration, or a call to a macro expanding to declarations. `call_doc` is the
+// doc comment of the macro calls the declaration comes from, which goes before its own.
+fn resolve_declaration<'src>(
+  cst: &mut Cst<'src>,
+  macros: &HashMap<&'src str, Macro<'src>>,
+  table: &VarTable<'src>,
+  expr: Expression<'src>,
+  call_doc: &[&'src str],
+  depth: usize,
+) -> ResolveResult<'src, ()> {
+  let doc = merge_doc(call_doc, doc_comment(&expr.comments));
+
+  match head_symbol(&expr) {
+    Some("macro") => {
+      return Err(ResolveError::new(
+        expr.span,
+        "macros can only be defined at the top level",
+      ));
+    }
+    Some(name) if macros.contains_key(name) => {
+      return expand_macro(cst, macros, table, &macros[name], expr, &doc, depth);
+    }
+    _ => {}
+  }
+
+  let span = expr.span;
+  let info = ResolveMeta { span, cst };
+
+  let application = table.resolve(expr)?;
+
+  if let ValueKind::Application(mut els) = application.kind {
+    if els.is_empty() {
+      // TODO: Can this happen?
+      return Ok(());
+    }
+
+    let car = els.pop_front().unwrap();
+
+    if car.kind == ValueKind::Symbol("component") {
+      let mut component = resolve_component(info, els)?;
+      component.doc = doc;
+      cst.add_component(component);
+    } else if car.kind == ValueKind::Symbol("event") {
+      let mut event = resolve_event(info, els)?;
+      event.doc = doc;
+      cst.add_event(event);
+    } else if car.kind == ValueKind::Symbol("node") {
+      let mut node = resolve_node(info, els)?;
+      node.doc = doc;
+      cst.add_node(node);
+    } else if car.kind == ValueKind::Symbol("system") {
+      let (mut system, maybe_node) = resolve_system(info, els)?;
+      system.doc = doc;
+      cst.add_system(system);
+
+      if let Some(node) = maybe_node {
+        cst.add_node(node);
+      }
+    } else if car.kind == ValueKind::Symbol("state") {
+      let mut state = resolve_state(info, els)?;
+      state.doc = doc;
+      cst.add_state(state);
+    } else if car.kind == ValueKind::Symbol("include") {
+      let include = resolve_include(info, els)?;
+      cst.add_include(include);
+    } else if car.kind == ValueKind::Symbol("global") {
+      let mut global = resolve_global(info, els)?;
+      global.doc = doc;
+      cst.add_global(global);
+    } else if car.kind == ValueKind::Symbol("set") {
+      resolve_setting(info.span, els, cst)?;
+    } else if let ValueKind::Symbol(_) = car.kind {
+      return Err(ResolveError::new(
+        car.span,
+        format!("unknown tag or macro {}", car),
+      ));
+    } else {
+      return Err(ResolveError::new(
+        car.span,
+        format!(
+          "expected a tag: `component`, `event`, `node`, `system`, `state`, `include` or `set`. instead found {}",
+          car,
+        ),
+      ));
+    }
+  } else {
+    panic!(
+      "malformed ast: root expression is not an application. this is a bug. run with VECS_DEBUG_AST set to dump the AST",
+    );
+  }
+
+  Ok(())
+}
+
+// Resolves the declarations in the body of a macro, with its parameters bound to the
+// arguments of the call. The arguments are resolved where the macro is called, so
+// they can use the variables of another macro calling it.
+fn expand_macro<'src>(
+  cst: &mut Cst<'src>,
+  macros: &HashMap<&'src str, Macro<'src>>,
+  table: &VarTable<'src>,
+  definition: &Macro<'src>,
+  expr: Expression<'src>,
+  call_doc: &[&'src str],
+  depth: usize,
+) -> ResolveResult<'src, ()> {
+  let call = expr.span;
+
+  if depth >= MAX_MACRO_DEPTH {
+    return Err(ResolveError::new(
+      call,
+      format!(
+        "macro `{}` is nested too deeply. maybe it calls itself?",
+        definition.name
+      ),
+    ));
+  }
+
+  let ExpressionKind::Application(mut exprs) = expr.kind else {
+    unreachable!();
+  };
+
+  let args = untuple(exprs.split_off(1))
+    .into_iter()
+    .map(|e| table.resolve(e))
+    .collect::<ResolveResult<Vec<_>>>()?;
+  let body_table = definition.bind(call, args)?;
+
+  for expr in definition.body.iter().cloned() {
+    resolve_declaration(cst, macros, &body_table, expr, call_doc, depth + 1)
+      .map_err(|e| {
+        e.in_expansion(Expansion {
+          name: definition.name,
+          call,
+          definition: definition.span,
+        })
+      })?;
+  }
+
+  Ok(())
+}
+
+// The symbol an application starts with, e.g. its tag.
+fn head_symbol<'src>(expr: &Expression<'src>) -> Option<&'src str> {
+  let ExpressionKind::Application(exprs) = &expr.kind else {
+    return None;
+  };
+
+  match exprs.first()?.kind {
+    ExpressionKind::Symbol(name) => Some(name),
+    _ => None,
+  }
+}
+
+// `outer` and `inner`, as separate paragraphs.
+fn merge_doc<'src>(outer: &[&'src str], inner: Vec<&'src str>) -> Vec<&'src str> {
+  if outer.is_empty() {
+    return inner;
+  }
+
+  let mut doc = outer.to_vec();
+  if !inner.is_empty() {
+    doc.push("");
+    doc.extend(inner);
+  }
+
+  doc
+}
+
+// The lines of the `///` comments right before a declaration, without the slashes and
+// the space after them.
+fn doc_comment<'src>(comments: &Comments<'src>) -> Vec<&'src str> {
+  let is_doc = |text: &str| text.starts_with("///") && !text.starts_with("////");
+
+  let start = comments
+    .leading
+    .iter()
+    .rposition(|c| !is_doc(c.text))
+    .map_or(0, |i| i + 1);
+
+  comments.leading[start..]
+    .iter()
+    .map(|c| {
+      let line = &c.text[3..];
+      line.strip_prefix(' ').unwrap_or(line).trim_end()
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::spec::Spec;
+
+  #[test]
+  fn test_declaration_order() {
+    // Not in alphabetical order, and enough of them that a hash map would shuffle
+    // them.
+    let names = [
+      "zeta", "alpha", "mu", "beta", "omega", "kappa", "delta", "pi", "gamma", "chi",
+      "eta", "sigma", "iota", "tau", "lambda", "nu",
+    ];
+
+    let mut text = String::new();
+    for name in names {
+      text.push_str(&format!("component {} {{ int x }};\n", name));
+    }
+    for name in names {
+      text.push_str(&format!("system {} {{ {} }};\n", name, name));
+    }
+    for name in names {
+      text.push_str(&format!(
+        "state {} {{ systems {{ {{ {} }} }} }};\n",
+        name, name
+      ));
+    }
+
+    let spec = Spec::new("order.vecs", text).expect("spec error");
+    for _ in 0..2 {
+      let cst = spec.resolve().expect("resolve error");
+
+      assert_eq!(cst.components.keys().copied().collect::<Vec<_>>(), names);
+      assert_eq!(cst.systems.keys().copied().collect::<Vec<_>>(), names);
+      assert_eq!(cst.nodes.keys().copied().collect::<Vec<_>>(), names);
+      assert_eq!(cst.states.keys().copied().collect::<Vec<_>>(), names);
+    }
+  }
+}
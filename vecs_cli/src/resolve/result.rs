@@ -2,10 +2,20 @@ use std::{borrow::Cow, fmt::Write as _};
 
 use crate::parse::data::str::Span;
 
+// A macro call an error happened while expanding.
+#[derive(Debug, Copy, Clone)]
+pub struct Expansion<'src> {
+  pub name: &'src str,
+  pub call: Span<'src>,
+  pub definition: Span<'src>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ResolveError<'src> {
   span: Span<'src>,
   message: Cow<'static, str>,
+  // Innermost first. Boxed to keep results small, since it's usually empty.
+  expansions: Box<[Expansion<'src>]>,
 }
 
 impl<'src> std::fmt::Display for ResolveError<'src> {
@@ -25,9 +35,31 @@ impl<'src> ResolveError<'src> {
     Self {
       span,
       message: message.into(),
+      expansions: Box::default(),
     }
   }
 
+  pub fn span(&self) -> Span<'src> {
+    self.span
+  }
+
+  pub fn message(&self) -> &str {
+    self.message.as_ref()
+  }
+
+  pub fn expansions(&self) -> &[Expansion<'src>] {
+    &self.expansions
+  }
+
+  // Notes that the error happened while expanding a macro. Called from the innermost
+  // expansion out.
+  pub fn in_expansion(mut self, expansion: Expansion<'src>) -> Self {
+    let mut expansions = self.expansions.into_vec();
+    expansions.push(expansion);
+    self.expansions = expansions.into_boxed_slice();
+    self
+  }
+
   // pub fn wrap_message<T>(self, msg: T) -> ResolveError<'src>
   // where
   //   T: Into<Cow<'static, str>>,
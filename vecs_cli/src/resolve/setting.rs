@@ -2,65 +2,112 @@ use std::collections::VecDeque;
 
 use crate::{
   parse::data::str::Span,
-  resolve::{result::ResolveResult, values::Value},
+  resolve::{
+    cst::Prefix,
+    result::{ResolveError, ResolveResult},
+    values::{Value, ValueKind},
+  },
 };
 
 use super::cst::Cst;
 
 // Modifies the CST directly because that's the easiest thing to do.
 pub fn resolve_setting<'src>(
-  _span: Span<'src>,
-  _cdr: VecDeque<Value<'src>>,
-  _cst: &mut Cst,
+  span: Span<'src>,
+  cdr: VecDeque<Value<'src>>,
+  cst: &mut Cst,
 ) -> ResolveResult<'src, ()> {
-  // There are currently no settings.
+  let setting_name: &'src str;
+  let maybe_value = cdr.front();
 
-  // let setting_name: &'src str;
-  // let maybe_value = cdr.get(0);
+  if let Some(value) = maybe_value {
+    if let ValueKind::Symbol(name) = value.kind {
+      setting_name = name
+    } else {
+      return Err(ResolveError::new(
+        value.span,
+        format!("setting name must be a symbol. instead found {}", value),
+      ));
+    }
 
-  // if let Some(value) = maybe_value {
-  //   if let ValueKind::Symbol(name) = value.kind {
-  //     setting_name = name
-  //   } else {
-  //     return Err(ResolveError::new(
-  //       value.span,
-  //       format!("setting name must be a symbol. instead found {}", value),
-  //     ));
-  //   }
+    if cdr.len() > 2 {
+      return Err(ResolveError::new(
+        span,
+        format!(
+          "extraneous value under setting {}. maybe you forgot a semicolon?",
+          setting_name,
+        ),
+      ));
+    }
 
-  //   if cdr.len() > 2 {
-  //     return Err(ResolveError::new(
-  //       span,
-  //       format!(
-  //         "extraneous value under setting {}. maybe you forgot a semicolon?",
-  //         setting_name,
-  //       ),
-  //     ));
-  //   }
+    let maybe_value = cdr.get(1);
 
-  //   let maybe_value = cdr.get(1);
-
-  //   if let Some(value) = maybe_value {
-  //     match setting_name {
-  //       other => {
-  //         return Err(ResolveError::new(
-  //           value.span,
-  //           format!("unrecognized setting {}", other,),
-  //         ));
-  //       }
-  //     }
-  //   } else {
-  //     return Err(ResolveError::new(
-  //       span,
-  //       format!("setting {} is missing its body", value),
-  //     ));
-  //   }
-  // } else {
-  //   return Err(ResolveError::new(
-  //     span,
-  //     "a setting tag must be followed by the setting name",
-  //   ));
-  // }
+    if let Some(value) = maybe_value {
+      match setting_name {
+        "prefix" => {
+          if let ValueKind::Symbol(prefix) = value.kind
+            && Prefix::is_valid(prefix)
+          {
+            cst.settings.prefix = Prefix::new(prefix);
+          } else {
+            return Err(ResolveError::new(
+              value.span,
+              format!(
+                "prefix must be a symbol made of letters, digits and underscores, starting with a letter. instead found {}",
+                value
+              ),
+            ));
+          }
+        }
+        "entity_capacity" => {
+          cst.settings.entity_capacity = integer(setting_name, "uint32_t", value)?;
+        }
+        other => {
+          return Err(ResolveError::new(
+            value.span,
+            format!("unrecognized setting {}", other,),
+          ));
+        }
+      }
+    } else {
+      return Err(ResolveError::new(
+        span,
+        format!("setting {} is missing its body", value),
+      ));
+    }
+  } else {
+    return Err(ResolveError::new(
+      span,
+      "a setting tag must be followed by the setting name",
+    ));
+  }
 
   Ok(())
 }
+
+// The value of an integer setting, which must fit in `T`, the C type it ends up as.
+fn integer<'src, T: TryFrom<i128>>(
+  setting_name: &str,
+  c_type: &str,
+  value: &Value<'src>,
+) -> ResolveResult<'src, T> {
+  let ValueKind::Integer(x) = value.kind else {
+    return Err(ResolveError::new(
+      value.span,
+      format!(
+        "{} must be an integer. instead found {}",
+        setting_name, value
+      ),
+    ));
+  };
+
+  T::try_from(x).map_err(|_| {
+    ResolveError::new(
+      value.span,
+      format!(
+        "{} must fit in a {}. instead found {}",
+        setting_name, c_type, x
+      ),
+    )
+  })
+}
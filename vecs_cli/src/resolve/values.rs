@@ -82,7 +82,7 @@ impl<'src> Display for Value<'src> {
   }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct VarTable<'src> {
   pub variables: HashMap<&'src str, Value<'src>>,
 }
@@ -94,9 +94,9 @@ impl<'src> VarTable<'src> {
     }
   }
 
-  // pub fn add(&mut self, name: &'src str, value: Value<'src>) {
-  //   self.variables.insert(name, value);
-  // }
+  pub fn add(&mut self, name: &'src str, value: Value<'src>) {
+    self.variables.insert(name, value);
+  }
 
   pub fn resolve_var(&self, name: &'src str) -> Option<Value<'src>> {
     self.variables.get(name).cloned()
@@ -136,7 +136,14 @@ impl<'src> VarTable<'src> {
               resolved.push_back(value);
             }
             ListEntry::Embed(expression) => {
-              let value = self.resolve(expression)?;
+              let mut value = self.resolve(expression)?;
+
+              // Like every entry, what follows `...` is parsed as an application.
+              if let ValueKind::Application(ref mut values) = value.kind
+                && values.len() == 1
+              {
+                value = values.pop_front().unwrap();
+              }
 
               if let ValueKind::List(mut inner) = value.kind {
                 resolved.append(&mut inner);
@@ -152,6 +159,10 @@ impl<'src> VarTable<'src> {
 
         Ok(Value::new(ValueKind::List(resolved), span))
       }
+      ExpressionKind::Tuple(_) => Err(ResolveError::new(
+        span,
+        "parentheses only go around the parameters of a macro or the arguments of a call to it",
+      )),
     }
   }
 }
@@ -0,0 +1,134 @@
+use std::{fs, path::Path};
+
+use crate::{
+  error::Error,
+  parse::{
+    ast::Ast, comments::attach_comments, data::src::ParseSrc, parse, strip_comments,
+    tokens::tokenize,
+  },
+  resolve::{
+    cst::{Cst, Prefix},
+    resolve,
+  },
+};
+
+// The text of a spec, along with the path it was read from. The AST and the CST
+// borrow from it.
+pub struct Spec {
+  pub path: String,
+  text: String,
+  // The text with comments blanked out, which is what the parser sees.
+  stripped: String,
+}
+
+impl Spec {
+  // `path` is only used in error messages and spans. Fails on unterminated block
+  // comments.
+  pub fn new(path: &str, text: String) -> Result<Self, Error> {
+    let tokens = tokenize(ParseSrc::new(Some(path), &text));
+    let stripped = strip_comments(&text, &tokens)?;
+
+    Ok(Self {
+      path: path.to_string(),
+      text,
+      stripped,
+    })
+  }
+
+  pub fn read(path: &str) -> Result<Self, Error> {
+    let text = fs::read_to_string(path).map_err(|error| Error::Io {
+      path: Path::new(path).to_path_buf(),
+      error,
+    })?;
+
+    Self::new(path, text)
+  }
+
+  // Comments are attached to the expressions, for doc comments.
+  pub fn parse(&self) -> Result<Ast<'_>, Error> {
+    let src = ParseSrc::new(Some(&self.path), &self.stripped);
+    let mut ast = parse(src)?.value;
+
+    let tokens = tokenize(ParseSrc::new(Some(&self.path), &self.text));
+    attach_comments(&mut ast, &self.text, &tokens);
+
+    Ok(ast)
+  }
+
+  pub fn resolve(&self) -> Result<Cst<'_>, Error> {
+    Ok(resolve(self.parse()?)?)
+  }
+}
+
+// Overrides the prefix set in the spec, if any.
+pub fn set_prefix(cst: &mut Cst, prefix: &str) -> Result<(), Error> {
+  if !Prefix::is_valid(prefix) {
+    return Err(Error::InvalidPrefix(prefix.to_string()));
+  }
+
+  cst.settings.prefix = Prefix::new(prefix);
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Spec, set_prefix};
+  use crate::error::{Error, ErrorLocation};
+
+  #[test]
+  fn test_spec_errors() {
+    let text = concat!(
+      "component a;\n",
+      "macro body($name) { node $name { missing } };\n",
+      "body(x);\n",
+    );
+    let spec = Spec::new("errors.vecs", text.to_string()).expect("spec error");
+
+    let Err(Error::Resolve {
+      location,
+      message,
+      expansions,
+    }) = spec.resolve()
+    else {
+      panic!("resolve not error");
+    };
+    assert_eq!(message, "component `missing` not found.");
+    assert_eq!(
+      location,
+      ErrorLocation {
+        file: Some("errors.vecs".to_string()),
+        line: 2,
+        column: 33,
+      }
+    );
+    assert_eq!(expansions.len(), 1);
+    assert_eq!(expansions[0].call.line, 3);
+
+    let Err(error) = spec.resolve() else {
+      panic!("resolve not error");
+    };
+    assert_eq!(
+      error.to_string(),
+      concat!(
+        "errors.vecs:2:33: resolving error: component `missing` not found.\n",
+        "errors.vecs:3:0: note: in expansion of macro `body`, defined at errors.vecs:2:0",
+      )
+    );
+
+    let error = Spec::new("errors.vecs", "component a;\n/* a\n".to_string())
+      .err()
+      .expect("spec not error");
+    assert_eq!(
+      error.to_string(),
+      "errors.vecs:2:0: parsing error: unterminated block comment"
+    );
+
+    let spec =
+      Spec::new("errors.vecs", "component a;\n".to_string()).expect("spec error");
+    let mut cst = spec.resolve().expect("resolve error");
+    assert!(matches!(
+      set_prefix(&mut cst, "1x"),
+      Err(Error::InvalidPrefix(_))
+    ));
+  }
+}
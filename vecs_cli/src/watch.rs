@@ -0,0 +1,99 @@
+use std::{
+  ffi::{CString, OsStr, OsString, c_char, c_int},
+  fs::File,
+  io::{self, Read},
+  os::{
+    fd::{FromRawFd, OwnedFd},
+    unix::ffi::OsStrExt,
+  },
+  path::Path,
+};
+
+unsafe extern "C" {
+  fn inotify_init1(flags: c_int) -> c_int;
+  fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int;
+}
+
+const IN_CLOEXEC: c_int = 0o2000000;
+
+const IN_CLOSE_WRITE: u32 = 0x8;
+const IN_MOVED_TO: u32 = 0x80;
+
+// Size of `struct inotify_event` without the name.
+const EVENT_HEADER_LEN: usize = 16;
+
+// Waits for files to change, using inotify. The directories containing the files are
+// watched rather than the files themselves, because editors often save by writing a
+// new file and renaming it over the old one.
+pub struct Watcher {
+  inotify: File,
+  // Watch descriptor of the directory and name of each file.
+  files: Vec<(c_int, OsString)>,
+}
+
+impl Watcher {
+  pub fn new(paths: &[&Path]) -> io::Result<Self> {
+    let fd = unsafe { inotify_init1(IN_CLOEXEC) };
+    if fd < 0 {
+      return Err(io::Error::last_os_error());
+    }
+
+    let inotify = File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+    let mut files = Vec::with_capacity(paths.len());
+
+    for path in paths {
+      let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+      };
+
+      let name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+          io::ErrorKind::InvalidInput,
+          format!("`{}` is not a file", path.display()),
+        )
+      })?;
+
+      let dir = CString::new(dir.as_os_str().as_bytes())?;
+      let wd =
+        unsafe { inotify_add_watch(fd, dir.as_ptr(), IN_CLOSE_WRITE | IN_MOVED_TO) };
+      if wd < 0 {
+        return Err(io::Error::last_os_error());
+      }
+
+      files.push((wd, name.to_os_string()));
+    }
+
+    Ok(Self { inotify, files })
+  }
+
+  // Blocks until one of the files was written or replaced.
+  pub fn wait(&mut self) -> io::Result<()> {
+    let mut buf = [0u8; 4096];
+
+    loop {
+      let len = self.inotify.read(&mut buf)?;
+      let mut events = &buf[..len];
+
+      let mut changed = false;
+      while events.len() >= EVENT_HEADER_LEN {
+        let field =
+          |i: usize| u32::from_ne_bytes(events[i..i + 4].try_into().unwrap());
+
+        let wd = field(0) as c_int;
+        let name_len = field(12) as usize;
+
+        // The name is padded with NULs.
+        let name = &events[EVENT_HEADER_LEN..EVENT_HEADER_LEN + name_len];
+        let name = OsStr::from_bytes(name.split(|b| *b == 0).next().unwrap_or(name));
+
+        changed |= self.files.iter().any(|(w, n)| *w == wd && n == name);
+        events = &events[EVENT_HEADER_LEN + name_len..];
+      }
+
+      if changed {
+        return Ok(());
+      }
+    }
+  }
+}